@@ -2,13 +2,20 @@
 
 mod state;
 
-use flashbet_market::{InstantiationArgument, Message, Operation};
+use std::collections::BTreeMap;
+
+use flashbet_market::{InstantiationArgument, Operation, OperationResponse};
 use flashbet_shared::{
-    EventResult, MarketEvent, MarketId, MarketStatus, Payout,
+    Bet, BetKey, BetRejection, EntitlementKind, EventId, EventResult, MarketEvent, MarketId,
+    MarketInfo, MarketRef, MarketRole, MarketStatus, MarketType, NoWinnerPolicy,
+    NoWinnerPolicyInput, OracleEvent, Order, OrderSide, Outcome, Payout, PricingMode, Rake,
+    Settlement, SettlementNotice, SettlementStatus, StakeLimits, Trade, UserNotice,
 };
-use flashbet_token; // BET token application for cross-application calls
 use linera_sdk::{
-    linera_base_types::{ApplicationId, StreamName, StreamUpdate, WithContractAbi},
+    linera_base_types::{
+        Account, AccountOwner, Amount, ApplicationId, ChainId, GenericApplicationId, StreamId,
+        StreamName, StreamUpdate, TimeDelta, WithContractAbi,
+    },
     views::{RootView, View},
     Contract, ContractRuntime,
 };
@@ -27,7 +34,7 @@ impl WithContractAbi for FlashbetMarketContract {
 }
 
 impl Contract for FlashbetMarketContract {
-    type Message = Message;
+    type Message = ();
     type Parameters = ();
     type InstantiationArgument = InstantiationArgument;
     type EventValue = MarketEvent;
@@ -59,6 +66,55 @@ impl Contract for FlashbetMarketContract {
             unsafe { std::mem::transmute(argument.bet_token_id) };
         self.state.bet_token_id.set(Some(typed_app_id));
 
+        // Store the betting cutoff applied before each market's event time
+        self.state
+            .betting_cutoff
+            .set(TimeDelta::from_secs(argument.betting_cutoff_secs));
+
+        // Store the treasury that receives fees and unclaimed pools
+        self.state.treasury.set(argument.treasury);
+
+        // Store the default house rake for new markets
+        flashbet_shared::validate_fee_bps(argument.rake_bps).expect("Invalid rake");
+        if argument.rake_bps > 0 {
+            assert!(
+                argument.treasury.is_some(),
+                "A rake requires a treasury account"
+            );
+        }
+        self.state.default_rake.set(Rake {
+            fee_bps: argument.rake_bps,
+            base: argument.rake_base,
+        });
+
+        // Set the chain creator as owner and grant the initial roles
+        let owner = self
+            .runtime
+            .authenticated_signer()
+            .expect("Missing signature");
+        self.state.owner.set(Some(owner));
+        for (role, accounts) in [
+            (MarketRole::MarketCreator, argument.market_creators),
+            (MarketRole::Settler, argument.settlers),
+            (MarketRole::Pauser, argument.pausers),
+            (MarketRole::PriceSetter, argument.price_setters),
+        ] {
+            for account in accounts {
+                self.state
+                    .roles
+                    .insert(&(role, account))
+                    .expect("Failed to grant initial role");
+            }
+        }
+
+        // Store the oracle keys trusted to submit results directly
+        for oracle_key in argument.oracle_keys {
+            self.state
+                .oracle_keys
+                .insert(&oracle_key)
+                .expect("Failed to store oracle key");
+        }
+
         // Subscribe to Oracle Chain events for automatic result processing
         self.runtime.subscribe_to_events(
             argument.oracle_chain,
@@ -69,87 +125,171 @@ impl Contract for FlashbetMarketContract {
 
     async fn execute_operation(&mut self, operation: Self::Operation) -> Self::Response {
         match operation {
-            Operation::RegisterBet { bet } => {
-                // Wave 1: Frontend-relayed bet registration
-                // Wave 2+: This will be triggered by cross-app event processing
+            Operation::CreateMarket { input } => {
+                self.assert_role(MarketRole::MarketCreator).await;
 
-                let event_id = &bet.event_id;
+                // Validate event ID format
+                let event_id = flashbet_shared::EventId::new(input.event_id.clone());
+                flashbet_shared::validate_event_id(&event_id)
+                    .expect("Invalid event ID format");
 
-                // 1. Check market exists
-                assert!(
-                    self.state.market_exists(event_id).await,
-                    "Market {} does not exist",
-                    event_id
-                );
+                // Resolve the no-winner policy (Refund with no fee by default)
+                let fee_bps = input.no_winner_fee_bps.unwrap_or(0);
+                flashbet_shared::validate_fee_bps(fee_bps).expect("Invalid no-winner fee");
+                let no_winner_policy = match input.no_winner_policy {
+                    None | Some(NoWinnerPolicyInput::Refund) => NoWinnerPolicy::Refund { fee_bps },
+                    Some(NoWinnerPolicyInput::Treasury) => NoWinnerPolicy::Treasury,
+                };
+                if no_winner_policy != NoWinnerPolicy::default() {
+                    assert!(
+                        self.state.treasury.get().is_some(),
+                        "No-winner policy {:?} requires a treasury account",
+                        no_winner_policy
+                    );
+                }
 
-                // 2. Validate market is open
-                assert!(
-                    self.state.is_open(event_id).await,
-                    "Market is not open for betting, status: {:?}",
-                    self.state.get_status(event_id).await
-                );
+                // Resolve the pricing mode (parimutuel by default)
+                let pricing = input.pricing_mode.unwrap_or_default();
 
-                // 3. Validate outcome is valid for this market type
-                let market_info = self.state.get_market(event_id).await
-                    .expect("Market not found");
-                assert!(
-                    flashbet_shared::validate_outcome_for_market(bet.outcome, &market_info.market_type),
-                    "Invalid outcome {:?} for market type {:?}",
-                    bet.outcome,
-                    market_info.market_type
-                );
+                // Resolve the house rake (the chain default unless overridden; fixed odds
+                // and the LMSR market maker carry the house margin in their prices instead)
+                let default_rake = match pricing {
+                    PricingMode::Parimutuel => *self.state.default_rake.get(),
+                    PricingMode::FixedOdds | PricingMode::Lmsr => Rake::default(),
+                };
+                let rake = Rake {
+                    fee_bps: input.rake_bps.unwrap_or(default_rake.fee_bps),
+                    base: input.rake_base.unwrap_or(default_rake.base),
+                };
+                if pricing != PricingMode::Parimutuel {
+                    assert!(rake.fee_bps == 0, "{:?} markets take no rake", pricing);
+                }
+                flashbet_shared::validate_fee_bps(rake.fee_bps).expect("Invalid rake");
+                if rake.fee_bps > 0 {
+                    assert!(
+                        self.state.treasury.get().is_some(),
+                        "A rake requires a treasury account"
+                    );
+                }
 
-                // 4. Add bet to market state
-                self.state.add_bet(bet.clone()).await;
+                // Resolve the stake limits (none by default)
+                let limits = StakeLimits {
+                    min_stake: input.min_stake.unwrap_or(Amount::ZERO),
+                    max_stake: input.max_stake,
+                    max_user_exposure: input.max_user_exposure,
+                    max_liability: input.max_liability,
+                };
+                limits.validate().expect("Invalid stake limits");
+                if pricing == PricingMode::Lmsr {
+                    assert!(
+                        limits == StakeLimits::default(),
+                        "LMSR markets take no stake limits"
+                    );
+                }
 
-                // 5. Emit BetPlaced event
-                let total_pool = self.state.get_total_pool(event_id).await;
-                self.runtime.emit(
-                    StreamName::from(b"market_events".to_vec()),
-                    &MarketEvent::BetPlaced {
-                        market_id: bet.market_id,
-                        bet_id: bet.bet_id,
-                        outcome: bet.outcome,
-                        amount: bet.amount,
-                        total_pool,
-                    },
-                );
-            }
+                // Resolve the LMSR liquidity parameter
+                let liquidity = match pricing {
+                    PricingMode::Lmsr => {
+                        let liquidity = input
+                            .liquidity
+                            .expect("LMSR markets require a liquidity parameter");
+                        assert!(liquidity > Amount::ZERO, "Liquidity must be positive");
+                        Some(liquidity)
+                    }
+                    PricingMode::Parimutuel | PricingMode::FixedOdds => {
+                        assert!(
+                            input.liquidity.is_none(),
+                            "Only LMSR markets take a liquidity parameter"
+                        );
+                        None
+                    }
+                };
 
-            Operation::CreateMarket { input } => {
-                // Validate event ID format
-                let event_id = flashbet_shared::EventId::new(input.event_id.clone());
-                flashbet_shared::validate_event_id(&event_id)
-                    .expect("Invalid event ID format");
+                // Resolve the market type, its line and its outcomes
+                let market_type =
+                    MarketType::from_input(input.market_type, input.line, input.outcomes.clone())
+                        .expect("Invalid market type");
 
                 // Convert input to MarketInfo
                 let info = flashbet_shared::MarketInfo {
                     event_id: event_id.clone(),
                     description: input.description.clone(),
                     event_time: input.event_time,
-                    market_type: input.market_type.into(), // Convert MarketTypeInput -> MarketType
+                    market_type,
                     home_team: input.home_team.clone(),
                     away_team: input.away_team.clone(),
+                    no_winner_policy,
+                    rake,
+                    market_id: MarketId(0), // Assigned by create_market
+                    limits,
+                    pricing,
+                    liquidity,
                 };
+                let num_outcomes = info.outcomes().len();
+
+                // Create the market (several markets may share an event)
+                let market_id = self.state.create_market(info).await;
 
-                // Create the market (this checks for duplicates)
-                self.state.create_market(info.clone()).await
-                    .expect("Failed to create market");
+                // Reserve the most the LMSR market maker can lose, what it starts out owed
+                if let Some(liquidity) = liquidity {
+                    let max_loss =
+                        flashbet_shared::lmsr_cost(liquidity, &vec![Amount::ZERO; num_outcomes])
+                            .expect("Liquidity too large");
+                    assert!(
+                        max_loss <= self.state.available_bankroll(market_id).await,
+                        "Bankroll cannot cover the market maker's worst loss of {}",
+                        max_loss
+                    );
+                    self.state.reserve_bankroll(market_id, max_loss).await;
+                }
 
                 // Emit MarketCreated event
                 self.runtime.emit(
                     StreamName::from(b"market_events".to_vec()),
                     &MarketEvent::MarketCreated {
-                        market_id: MarketId(0), // Market ID is based on chain creation
+                        market_id,
                         event_id,
                         description: input.description,
                     },
                 );
             }
 
+            Operation::RegisterBet {
+                bet,
+                min_payout_bps,
+            } => {
+                self.assert_authentic_bet(&bet);
+                return self.register_bet(bet, min_payout_bps).await;
+            }
+
+            Operation::SetUserApp { user_app_id } => {
+                let signer = self
+                    .runtime
+                    .authenticated_signer()
+                    .expect("Must be signed operation");
+                assert!(
+                    self.state.is_owner(&signer),
+                    "Only owner can set the User application"
+                );
+                self.state.user_app_id.set(Some(user_app_id));
+            }
+
             Operation::ProcessOracleResult { result } => {
-                // Wave 1: Frontend-triggered oracle result processing
-                // Wave 2+: This will be triggered by Oracle event subscription
+                // Manual fallback: results normally arrive through the Oracle
+                // event subscription in process_streams
+                let signer = self
+                    .runtime
+                    .authenticated_signer()
+                    .expect("ProcessOracleResult must be signed");
+                assert!(
+                    self.state
+                        .oracle_keys
+                        .contains(&signer)
+                        .await
+                        .expect("Failed to read oracle keys"),
+                    "Unauthorized oracle: {:?}",
+                    signer
+                );
                 self.handle_oracle_result(result).await;
             }
 
@@ -161,7 +301,7 @@ impl Contract for FlashbetMarketContract {
                 // Check if already subscribed
                 if self.state.subscribed_users.contains(&user_app).await.unwrap_or(false) {
                     // Already subscribed, skip
-                    return;
+                    return OperationResponse::Ok;
                 }
 
                 // Subscribe to User chain's "user_bets" event stream
@@ -175,94 +315,546 @@ impl Contract for FlashbetMarketContract {
                 self.state.subscribed_users.insert(&user_app).expect("Failed to insert subscribed user");
             }
 
-            Operation::LockMarket => {
-                // NOTE: LockMarket is deprecated in multi-market version
-                // Markets are automatically locked when oracle results are processed
-                panic!("LockMarket operation deprecated in multi-market version");
+            Operation::LockMarket { market } => {
+                let market_ids = self.existing_markets(&market).await;
+                if let MarketRef::MarketId(market_id) = market {
+                    assert!(
+                        self.state.is_open(market_id).await,
+                        "Market {} is not open, status: {:?}",
+                        market_id,
+                        self.state.get_status(market_id).await
+                    );
+
+                    assert!(
+                        self.lock_if_expired(market_id).await,
+                        "Betting on market {} has not closed yet",
+                        market_id
+                    );
+                } else {
+                    // Lock whichever of the event's markets are due, skipping the rest
+                    let mut locked = false;
+                    for market_id in market_ids {
+                        locked |= self.lock_if_expired(market_id).await;
+                    }
+                    assert!(
+                        locked,
+                        "No open market on event {} has closed betting",
+                        market
+                    );
+                }
             }
 
-            Operation::CancelMarket => {
-                // NOTE: CancelMarket is deprecated in multi-market version
-                // If needed, create a CancelMarketByEventId operation
-                panic!("CancelMarket operation deprecated in multi-market version");
+            Operation::CancelMarket { market } => {
+                self.assert_role(MarketRole::Settler).await;
+                let market_ids = self.existing_markets(&market).await;
+                if let MarketRef::MarketId(market_id) = market {
+                    self.cancel_market(market_id).await;
+                } else {
+                    // Cancel whichever of the event's markets are still live, skipping the rest
+                    let mut cancelled = false;
+                    for market_id in market_ids {
+                        if is_cancellable(&self.state.get_status(market_id).await) {
+                            self.cancel_market(market_id).await;
+                            cancelled = true;
+                        }
+                    }
+                    assert!(cancelled, "No market on event {} can be cancelled", market);
+                }
             }
-        }
-    }
 
-    async fn execute_message(&mut self, message: Self::Message) {
-        match message {
-            Message::PlaceBet { bet } => {
-                let event_id = &bet.event_id;
+            Operation::ClaimPayout { market, bet } => {
+                for market_id in self.markets_for(&market).await {
+                    self.claim(market_id, &bet).await;
+                }
+            }
 
-                // 1. Check market exists
-                if !self.state.market_exists(event_id).await {
-                    // Market doesn't exist, ignore bet
-                    return;
+            Operation::ClaimBatch { market, bets } => {
+                for market_id in self.markets_for(&market).await {
+                    for bet in &bets {
+                        self.claim(market_id, bet).await;
+                    }
                 }
+            }
 
-                // 2. Validate market is open
-                if !self.state.is_open(event_id).await {
-                    // Market not open, ignore bet
-                    return;
+            Operation::SettleNext { market, max_bets } => {
+                assert!(max_bets > 0, "SettleNext must process at least one bet");
+                let mut remaining = u64::from(max_bets);
+                for market_id in self.markets_for(&market).await {
+                    remaining -= self.settle_next(market_id, remaining).await;
+                    if remaining == 0 {
+                        break;
+                    }
                 }
+            }
 
-                // 3. Validate outcome is valid for this market type
-                let market_info = match self.state.get_market(event_id).await {
-                    Some(info) => info,
-                    None => return, // Market not found
-                };
+            Operation::GrantRole { role, account } => {
+                self.assert_owner();
+                if self.state.grant_role(role, account).await {
+                    self.runtime.emit(
+                        StreamName::from(b"market_events".to_vec()),
+                        &MarketEvent::RoleGranted { role, account },
+                    );
+                }
+            }
+
+            Operation::RevokeRole { role, account } => {
+                self.assert_owner();
+                if self.state.revoke_role(role, account).await {
+                    self.runtime.emit(
+                        StreamName::from(b"market_events".to_vec()),
+                        &MarketEvent::RoleRevoked { role, account },
+                    );
+                }
+            }
+
+            Operation::PauseChain => {
+                self.assert_role(MarketRole::Pauser).await;
+                assert!(!*self.state.paused.get(), "Chain is already paused");
+                self.state.paused.set(true);
+                self.runtime.emit(
+                    StreamName::from(b"market_events".to_vec()),
+                    &MarketEvent::ChainPaused,
+                );
+            }
+
+            Operation::ResumeChain => {
+                self.assert_role(MarketRole::Pauser).await;
+                assert!(*self.state.paused.get(), "Chain is not paused");
+                self.state.paused.set(false);
+                self.runtime.emit(
+                    StreamName::from(b"market_events".to_vec()),
+                    &MarketEvent::ChainResumed,
+                );
+
+                // Settle the results held while paused, except on markets still suspended
+                for market_id in self.state.get_deferred_markets().await {
+                    if self.state.get_status(market_id).await != MarketStatus::Suspended {
+                        self.settle_deferred(market_id).await;
+                    }
+                }
+            }
+
+            Operation::PauseMarket { market } => {
+                self.assert_role(MarketRole::Pauser).await;
+                for market_id in self.existing_markets(&market).await {
+                    self.pause_market(market_id).await;
+                }
+            }
+
+            Operation::ResumeMarket { market } => {
+                self.assert_role(MarketRole::Pauser).await;
+                for market_id in self.existing_markets(&market).await {
+                    self.resume_market(market_id).await;
+                }
+            }
+
+            Operation::SetOdds { market_id, odds } => {
+                self.assert_role(MarketRole::PriceSetter).await;
+                let market_info = self
+                    .state
+                    .get_market(market_id)
+                    .await
+                    .unwrap_or_else(|| panic!("Market {} does not exist", market_id));
+                assert!(
+                    market_info.pricing == PricingMode::FixedOdds,
+                    "Market {} does not offer fixed odds",
+                    market_id
+                );
+                let status = self.state.get_status(market_id).await;
+                assert!(
+                    status == MarketStatus::Open,
+                    "Market {} is not open, status: {:?}",
+                    market_id,
+                    status
+                );
 
-                if !flashbet_shared::validate_outcome_for_market(bet.outcome, &market_info.market_type) {
-                    // Invalid outcome, ignore bet
-                    return;
+                for price in &odds {
+                    assert!(
+                        flashbet_shared::validate_outcome_for_market(
+                            price.outcome,
+                            &market_info.market_type
+                        ),
+                        "Invalid outcome {} for market {}",
+                        price.outcome,
+                        market_id
+                    );
+                    assert!(
+                        price.odds_bps > u32::from(flashbet_shared::BASIS_POINTS),
+                        "Odds must be above 1.0, got {}bps",
+                        price.odds_bps
+                    );
+                    self.state
+                        .set_fixed_odds(market_id, price.outcome, price.odds_bps);
                 }
 
-                // 4. Add bet to market state
-                self.state.add_bet(bet.clone()).await;
+                self.runtime.emit(
+                    StreamName::from(b"market_events".to_vec()),
+                    &MarketEvent::OddsUpdated { market_id, odds },
+                );
+            }
+
+            Operation::FundBankroll { amount } => {
+                assert!(amount > Amount::ZERO, "Amount must be positive");
+                let funder = self
+                    .runtime
+                    .authenticated_signer()
+                    .expect("Must be signed operation");
+
+                // Move the funder's tokens into the market's escrow, which pays bettors
+                self.transfer_to_escrow(amount);
+                let bankroll = self.state.bankroll.get().saturating_add(amount);
+                self.state.bankroll.set(bankroll);
 
-                // 5. Emit BetPlaced event
-                let total_pool = self.state.get_total_pool(event_id).await;
                 self.runtime.emit(
                     StreamName::from(b"market_events".to_vec()),
-                    &MarketEvent::BetPlaced {
-                        market_id: bet.market_id,
-                        bet_id: bet.bet_id,
-                        outcome: bet.outcome,
-                        amount: bet.amount,
-                        total_pool,
+                    &MarketEvent::BankrollFunded {
+                        funder,
+                        amount,
+                        bankroll,
                     },
                 );
             }
 
-            Message::OracleResult(result) => {
-                // Handle oracle result (Wave 1: direct message approach)
-                self.handle_oracle_result(result).await;
+            Operation::WithdrawBankroll { to, amount } => {
+                let signer = self
+                    .runtime
+                    .authenticated_signer()
+                    .expect("Must be signed operation");
+                assert!(
+                    self.state.is_owner(&signer),
+                    "Only owner can withdraw the bankroll"
+                );
+                assert!(amount > Amount::ZERO, "Amount must be positive");
+                let bankroll = self
+                    .state
+                    .bankroll
+                    .get()
+                    .try_sub(amount)
+                    .expect("Withdrawal exceeds the free bankroll");
+                self.state.bankroll.set(bankroll);
+                self.transfer_to_account(to, amount);
+
+                self.runtime.emit(
+                    StreamName::from(b"market_events".to_vec()),
+                    &MarketEvent::BankrollWithdrawn {
+                        to,
+                        amount,
+                        bankroll,
+                    },
+                );
+            }
+
+            Operation::BuyShares {
+                market_id,
+                outcome,
+                shares,
+                max_cost,
+            } => {
+                let trader = self
+                    .runtime
+                    .authenticated_signer()
+                    .expect("Must be signed operation");
+                let (liquidity, mut supply) =
+                    self.tradable_shares(market_id, outcome, shares).await;
+
+                let before =
+                    flashbet_shared::lmsr_cost(liquidity, &supply).expect("Failed to price shares");
+                let index = outcome.index() as usize;
+                supply[index] = supply[index]
+                    .try_add(shares)
+                    .expect("Share supply overflow");
+                let cost = flashbet_shared::lmsr_cost(liquidity, &supply)
+                    .expect("Failed to price shares")
+                    .saturating_sub(before);
+                assert!(
+                    cost <= max_cost,
+                    "Cost {} exceeds the maximum of {}",
+                    cost,
+                    max_cost
+                );
+
+                // The payment joins the market's escrow on the Market chain
+                if cost > Amount::ZERO {
+                    self.transfer_to_escrow(cost);
+                }
+                self.state
+                    .trade_shares(market_id, trader, outcome, shares, cost, true)
+                    .await;
+
+                self.runtime.emit(
+                    StreamName::from(b"market_events".to_vec()),
+                    &MarketEvent::SharesBought {
+                        market_id,
+                        trader,
+                        outcome,
+                        shares,
+                        cost,
+                    },
+                );
+            }
+
+            Operation::SellShares {
+                market_id,
+                outcome,
+                shares,
+                min_proceeds,
+            } => {
+                let trader = self
+                    .runtime
+                    .authenticated_signer()
+                    .expect("Must be signed operation");
+                let (liquidity, mut supply) =
+                    self.tradable_shares(market_id, outcome, shares).await;
+                let held = self.state.get_position(market_id, &trader, &outcome).await;
+                assert!(held >= shares, "Only {} shares held", held);
+
+                let before =
+                    flashbet_shared::lmsr_cost(liquidity, &supply).expect("Failed to price shares");
+                let index = outcome.index() as usize;
+                supply[index] = supply[index].saturating_sub(shares);
+                let proceeds = before.saturating_sub(
+                    flashbet_shared::lmsr_cost(liquidity, &supply).expect("Failed to price shares"),
+                );
+                assert!(
+                    proceeds >= min_proceeds,
+                    "Proceeds {} below the minimum of {}",
+                    proceeds,
+                    min_proceeds
+                );
+
+                self.state
+                    .trade_shares(market_id, trader, outcome, shares, proceeds, false)
+                    .await;
+                if proceeds > Amount::ZERO {
+                    self.transfer_to_account(trader, proceeds);
+                }
+
+                self.runtime.emit(
+                    StreamName::from(b"market_events".to_vec()),
+                    &MarketEvent::SharesSold {
+                        market_id,
+                        trader,
+                        outcome,
+                        shares,
+                        proceeds,
+                    },
+                );
+            }
+
+            Operation::RedeemShares { market_id } => {
+                let holder = self
+                    .runtime
+                    .authenticated_signer()
+                    .expect("Must be signed operation");
+                let market_info = self
+                    .state
+                    .get_market(market_id)
+                    .await
+                    .unwrap_or_else(|| panic!("Market {} does not exist", market_id));
+                let status = self.state.get_status(market_id).await;
+                assert!(
+                    matches!(
+                        status,
+                        MarketStatus::Resolved(_) | MarketStatus::Push | MarketStatus::Cancelled
+                    ),
+                    "Market {} has not settled, status: {:?}",
+                    market_id,
+                    status
+                );
+
+                let mut amount = Amount::ZERO;
+                for (outcome, shares) in self.state.take_positions(&market_info, holder).await {
+                    let value = self.state.get_share_value(market_id, &outcome).await;
+                    amount.saturating_add_assign(shares_value(shares, value));
+                }
+                if amount == Amount::ZERO {
+                    return OperationResponse::Ok;
+                }
+                self.transfer_to_account(holder, amount);
+
+                self.runtime.emit(
+                    StreamName::from(b"payout_events".to_vec()),
+                    &MarketEvent::SharesRedeemed {
+                        market_id,
+                        holder,
+                        amount,
+                    },
+                );
+            }
+
+            Operation::PlaceOrder {
+                market_id,
+                outcome,
+                side,
+                price,
+                shares,
+            } => {
+                let owner = self
+                    .runtime
+                    .authenticated_signer()
+                    .expect("Must be signed operation");
+                self.assert_tradable(market_id, outcome, shares).await;
+                assert!(
+                    price > Amount::ZERO && price < Amount::ONE,
+                    "Price must be between 0 and 1 BET, got {}",
+                    price
+                );
+
+                // Escrow what the order trades: a bid's payment or an ask's shares
+                let escrow = match side {
+                    OrderSide::Bid => {
+                        let escrow = shares_value(shares, price);
+                        assert!(escrow > Amount::ZERO, "Order is too small to pay for");
+                        self.transfer_to_escrow(escrow);
+                        escrow
+                    }
+                    OrderSide::Ask => {
+                        let held = self.state.get_position(market_id, &owner, &outcome).await;
+                        assert!(held >= shares, "Only {} shares held", held);
+                        self.state
+                            .remove_shares(market_id, owner, outcome, shares)
+                            .await;
+                        Amount::ZERO
+                    }
+                };
+
+                let order = Order {
+                    order_id: self.state.allocate_order_id(),
+                    market_id,
+                    owner,
+                    outcome,
+                    side,
+                    price,
+                    shares,
+                    remaining: shares,
+                    escrow,
+                    timestamp: self.runtime.system_time(),
+                };
+                self.runtime.emit(
+                    StreamName::from(b"market_events".to_vec()),
+                    &MarketEvent::OrderPlaced {
+                        order: order.clone(),
+                    },
+                );
+
+                // Fill what crosses the book and rest the remainder
+                let order = self.match_order(order).await;
+                if order.remaining > Amount::ZERO {
+                    self.state.rest_order(order).await;
+                } else {
+                    self.release_escrow(&order).await;
+                }
+            }
+
+            Operation::CancelOrder { order_id } => {
+                let owner = self
+                    .runtime
+                    .authenticated_signer()
+                    .expect("Must be signed operation");
+                let order = self
+                    .state
+                    .get_order(order_id)
+                    .await
+                    .unwrap_or_else(|| panic!("Order {} is not open", order_id));
+                assert!(
+                    order.owner == owner,
+                    "Order {} belongs to another account",
+                    order_id
+                );
+
+                self.state.remove_order(&order).await;
+                self.release_escrow(&order).await;
+
+                self.runtime.emit(
+                    StreamName::from(b"market_events".to_vec()),
+                    &MarketEvent::OrderCancelled {
+                        market_id: order.market_id,
+                        order_id,
+                        owner,
+                    },
+                );
             }
 
-            Message::Payout(_) => {
-                // Payout messages are sent FROM Market TO Users, never received by Market
-                // If we receive one back (e.g., bounced), just ignore it
+            Operation::ReadNotice { chain_id, index } => {
+                let stream = flashbet_shared::user_notices_stream(self.runtime.chain_id());
+                let MarketEvent::UserNotice(notice) =
+                    self.runtime.read_event(chain_id, stream, index)
+                else {
+                    panic!("Not a user notice");
+                };
+                return OperationResponse::Notice(notice);
             }
         }
+
+        OperationResponse::Ok
+    }
+
+    async fn execute_message(&mut self, _message: Self::Message) {
+        // Market receives no messages: bets arrive through the User application on this
+        // chain, and User chains are notified through their notice streams
     }
 
     async fn process_streams(&mut self, updates: Vec<StreamUpdate>) {
-        // NOTE: Cross-application event subscription is configured (see CreateMarket operation),
-        // but cross-application event deserialization in process_streams requires
-        // manual BCS handling not yet exposed by Linera SDK v0.15.x.
-        //
-        // Current approach: Market subscribes to Oracle events (subscription active),
-        // but Oracle Worker still calls ProcessOracleResult operation directly.
-        //
-        // Future (SDK v0.16+): When Linera SDK provides cross-app event deserialization,
-        // we can automatically process OracleEvent::ResultPublished here without
-        // needing the manual ProcessOracleResult operation call.
+        // Market subscribes to the Oracle's `oracle_results` stream (see instantiate).
+        // An application can only read its own events, so each new event is read
+        // through the Oracle application and then settled here.
+        let oracle_chain = self
+            .state
+            .oracle_chain
+            .get()
+            .expect("Oracle chain not initialized");
+        let oracle_app_id = self
+            .state
+            .oracle_app_id
+            .get()
+            .expect("Oracle application ID not initialized");
+        let oracle_results = StreamId {
+            application_id: GenericApplicationId::User(oracle_app_id),
+            stream_name: StreamName::from(b"oracle_results".to_vec()),
+        };
 
         for update in updates {
-            // Acknowledge stream updates
-            for index in update.new_indices() {
-                // Mark as processed (subscription is active and working)
-                _ = (update.chain_id, index);
+            // Only Oracle results drive settlement
+            if update.chain_id != oracle_chain || update.stream_id != oracle_results {
+                continue;
+            }
+
+            // Skip events that were already processed
+            let stream_key = (update.chain_id, update.stream_id.clone());
+            let first_index = match self
+                .state
+                .last_processed_events
+                .get(&stream_key)
+                .await
+                .expect("Failed to read last processed event")
+            {
+                Some(last) => update.previous_index.max(last + 1),
+                None => update.previous_index,
+            };
+
+            for index in first_index..update.next_index {
+                let response = self
+                    .runtime
+                    .call_application::<flashbet_oracle::FlashbetOracleAbi>(
+                        false,
+                        oracle_app_id.with_abi(),
+                        &flashbet_oracle::Operation::ReadResultEvent {
+                            chain_id: update.chain_id,
+                            index,
+                        },
+                    );
+
+                if let flashbet_oracle::OperationResponse::ResultEvent(
+                    OracleEvent::ResultPublished { result },
+                ) = response
+                {
+                    self.handle_oracle_result(result).await;
+                }
+
+                self.state
+                    .last_processed_events
+                    .insert(&stream_key, index)
+                    .expect("Failed to record processed event");
             }
         }
     }
@@ -273,47 +865,23 @@ impl Contract for FlashbetMarketContract {
 }
 
 impl FlashbetMarketContract {
-    /// Handle an oracle result and resolve the market
-    async fn handle_oracle_result(&mut self, result: EventResult) {
-        let event_id = &result.event_id;
-
-        // Check if market exists
-        if !self.state.market_exists(event_id).await {
-            // Market doesn't exist, ignore result
-            return;
-        }
-
-        // Check if market is already resolved
-        let status = self.state.get_status(event_id).await;
-        if matches!(status, MarketStatus::Resolved(_)) {
-            // Already resolved, ignore
-            return;
-        }
-
-        // Resolve the market
-        self.state.resolve_market(event_id, result.outcome).await;
-
-        let total_pool = self.state.get_total_pool(event_id).await;
-        let winning_pool = self.state.get_pool_for_outcome(event_id, &result.outcome).await;
-
-        // Get all winning bets
-        let winning_bets = self.state.get_bets_for_outcome(event_id, &result.outcome).await;
-        let num_winners = winning_bets.len() as u64;
-
-        // Emit MarketResolved event
-        self.runtime.emit(
-            StreamName::from(b"market_events".to_vec()),
-            &MarketEvent::MarketResolved {
-                market_id: MarketId(0),
-                winning_outcome: result.outcome,
-                total_pool,
-                winning_pool,
-                num_winners,
+    /// Transfer BET tokens to a bettor's account on their User chain
+    fn transfer_to_bettor(&mut self, bet: &Bet, amount: Amount) {
+        self.transfer_from_escrow(
+            Account {
+                chain_id: bet.user_chain,
+                owner: bet.user,
             },
+            amount,
         );
+    }
 
-        // Distribute payouts to winners using BET token transfers
-        use linera_sdk::{abis::fungible::FungibleResponse, linera_base_types::AccountOwner};
+    /// Pay BET tokens out of the escrow the BET token application holds for this application
+    ///
+    /// Stakes, bankroll and order escrow all sit there, and only this application can spend
+    /// them: the call is authenticated as coming from it.
+    fn transfer_from_escrow(&mut self, to: Account, amount: Amount) {
+        use linera_sdk::abis::fungible::FungibleResponse;
 
         let bet_token_id = self
             .state
@@ -321,61 +889,3229 @@ impl FlashbetMarketContract {
             .get()
             .expect("BET token ID not initialized");
 
-        for bet in winning_bets {
-            let payout_amount = self.state.calculate_payout(&bet, &result.outcome).await;
+        let transfer_operation = flashbet_token::Operation::TransferFromEscrow { to, amount };
 
-            if payout_amount > linera_sdk::linera_base_types::Amount::ZERO {
-                let payout = Payout {
-                    market_id: bet.market_id,
-                    bet_id: bet.bet_id,
-                    amount: payout_amount,
-                    timestamp: self.runtime.system_time(),
-                };
+        let response = self
+            .runtime
+            .call_application::<flashbet_token::FlashbetTokenAbi>(
+                true, // Authenticate this application, whose escrow is debited
+                bet_token_id,
+                &transfer_operation,
+            );
 
-                // Transfer BET tokens to winner via BET token application
-                // Transfer from Market chain to winner's chain
-                let transfer_operation = flashbet_token::Operation::TransferCrossChain {
-                    destination: bet.user_chain,
-                    to: bet.user, // Transfer to winner's account
-                    amount: payout_amount,
-                };
+        match response {
+            FungibleResponse::Ok => {}
+            _ => panic!("Unexpected response from BET token TransferFromEscrow operation"),
+        }
+    }
 
-                let response = self
-                    .runtime
-                    .call_application::<flashbet_token::FlashbetTokenAbi>(
-                        false,
-                        bet_token_id,
-                        &transfer_operation,
-                    );
+    /// Cancel a market, making every bet's stake claimable as a refund
+    async fn cancel_market(&mut self, market_id: MarketId) {
+        assert!(
+            self.state.market_exists(market_id).await,
+            "Market {} does not exist",
+            market_id
+        );
 
-                match response {
-                    FungibleResponse::Ok => {}
-                    _ => panic!("Unexpected response from BET token TransferCrossChain operation"),
-                }
+        let status = self.state.get_status(market_id).await;
+        assert!(
+            is_cancellable(&status),
+            "Market {} cannot be cancelled, status: {:?}",
+            market_id,
+            status
+        );
 
-                // Send Payout message to update User chain state tracking
-                self.runtime
-                    .prepare_message(flashbet_market::Message::Payout(payout.clone()))
-                    .with_authentication() // Forward Market chain identity
-                    .send_to(bet.user_chain);
+        self.state.cancel_market(market_id).await;
 
-                // Emit event for logging
-                self.runtime.emit(
-                    StreamName::from(b"payout_events".to_vec()),
-                    &MarketEvent::PayoutDistributed {
-                        market_id: bet.market_id,
-                        bet_id: bet.bet_id,
-                        user_chain: bet.user_chain,
-                        amount: payout_amount,
-                    },
-                );
-            }
+        let bets = self.state.get_bets(market_id).await;
+        let num_refunds = bets.len() as u64;
+        let total_refunded = self.state.get_total_pool(market_id).await;
+        self.release_void_market(market_id, total_refunded).await;
+
+        let event_id = self.event_of(market_id).await;
+
+        self.runtime.emit(
+            StreamName::from(b"market_events".to_vec()),
+            &MarketEvent::MarketCancelled {
+                market_id,
+                event_id,
+                total_refunded,
+                num_refunds,
+            },
+        );
+
+        for bet in &bets {
+            self.state
+                .record_entitlement(bet, bet.amount, EntitlementKind::Refund)
+                .await;
         }
+        self.notify_settled(
+            bets.into_iter()
+                .map(|bet| {
+                    let amount = bet.amount;
+                    (bet, SettlementStatus::Refunded, amount)
+                })
+                .collect(),
+        );
     }
-}
 
-#[cfg(test)]
-mod tests {
-    // Tests temporarily commented out pending multi-market test updates
-    // TODO: Update tests for multi-market architecture
+    /// Settle a market whose result landed exactly on its line, making every stake claimable
+    async fn push_market(&mut self, market_id: MarketId) {
+        self.state.push_market(market_id).await;
+
+        let bets = self.state.get_bets(market_id).await;
+        let num_refunds = bets.len() as u64;
+        let total_refunded = self.state.get_total_pool(market_id).await;
+        self.release_void_market(market_id, total_refunded).await;
+
+        let event_id = self.event_of(market_id).await;
+
+        self.runtime.emit(
+            StreamName::from(b"market_events".to_vec()),
+            &MarketEvent::MarketPushed {
+                market_id,
+                event_id,
+                total_refunded,
+                num_refunds,
+            },
+        );
+
+        for bet in &bets {
+            self.state
+                .record_entitlement(bet, bet.amount, EntitlementKind::Refund)
+                .await;
+        }
+        self.notify_settled(
+            bets.into_iter()
+                .map(|bet| {
+                    let amount = bet.amount;
+                    (bet, SettlementStatus::Refunded, amount)
+                })
+                .collect(),
+        );
+    }
+
+    /// Return the bankroll reservation of a cancelled or pushed market backed by the bankroll,
+    /// once its bets are owed `refunded` (LMSR shares keep their last price)
+    async fn release_void_market(&mut self, market_id: MarketId, refunded: Amount) {
+        let market_info = self
+            .state
+            .get_market(market_id)
+            .await
+            .expect("Market not found");
+        match market_info.pricing {
+            PricingMode::Parimutuel => {}
+            PricingMode::FixedOdds => self.state.release_bankroll(market_id, refunded).await,
+            PricingMode::Lmsr => self.settle_shares(&market_info, None).await,
+        }
+    }
+
+    /// Return `amount` of a bet's stake to its User chain and notify the bettor
+    fn refund_bet(&mut self, bet: &Bet, amount: Amount) {
+        let refund = Payout {
+            market_id: bet.market_id,
+            bet_id: bet.bet_id,
+            amount,
+            timestamp: self.runtime.system_time(),
+        };
+
+        // Return the stake to the bettor's chain
+        self.transfer_to_bettor(bet, amount);
+
+        // Notify the User chain to update its state tracking
+        self.notify_user(bet.user_chain, UserNotice::Refund(refund));
+
+        self.runtime.emit(
+            StreamName::from(b"payout_events".to_vec()),
+            &MarketEvent::BetRefunded {
+                market_id: bet.market_id,
+                bet_id: bet.bet_id,
+                user_chain: bet.user_chain,
+                amount,
+            },
+        );
+    }
+
+    /// Turn down a bet, returning its full stake to the bettor's User chain
+    /// The User application that registered it passes the reason on to the bettor's chain
+    fn reject_bet(&mut self, bet: &Bet, reason: BetRejection) -> OperationResponse {
+        // The stake was transferred alongside the bet message, so return it
+        self.transfer_to_bettor(bet, bet.amount);
+
+        self.runtime.emit(
+            StreamName::from(b"market_events".to_vec()),
+            &MarketEvent::BetRejected {
+                market_id: bet.market_id,
+                bet_id: bet.bet_id,
+                user_chain: bet.user_chain,
+                reason: reason.clone(),
+            },
+        );
+
+        OperationResponse::BetRejected(reason)
+    }
+
+    /// Transfer BET tokens to the treasury account on this chain
+    fn transfer_to_treasury(&mut self, amount: Amount) {
+        let treasury = self.state.treasury.get().expect("Treasury not configured");
+        self.transfer_to_account(treasury, amount);
+    }
+
+    /// Transfer BET tokens from the signer's account into this application's escrow
+    fn transfer_to_escrow(&mut self, amount: Amount) {
+        use linera_sdk::abis::fungible::FungibleResponse;
+
+        let bet_token_id = self
+            .state
+            .bet_token_id
+            .get()
+            .expect("BET token ID not initialized");
+
+        let transfer_operation = flashbet_token::Operation::TransferToEscrow { amount };
+
+        let response = self
+            .runtime
+            .call_application::<flashbet_token::FlashbetTokenAbi>(
+                true, // Forward the signer, whose tokens are moved
+                bet_token_id,
+                &transfer_operation,
+            );
+
+        match response {
+            FungibleResponse::Ok => {}
+            _ => panic!("Unexpected response from BET token TransferToEscrow operation"),
+        }
+    }
+
+    /// Pay BET tokens out of this application's escrow to an account on this chain
+    fn transfer_to_account(&mut self, to: AccountOwner, amount: Amount) {
+        let chain_id = self.runtime.chain_id();
+        self.transfer_from_escrow(
+            Account {
+                chain_id,
+                owner: to,
+            },
+            amount,
+        );
+    }
+
+    /// Settle a resolved market that has no winning bets according to its policy
+    ///
+    /// With `half_stakes`, only half of each stake was in play: the policy applies to that half
+    /// and the other half is refunded in full.
+    async fn settle_without_winners(
+        &mut self,
+        market_id: MarketId,
+        policy: NoWinnerPolicy,
+        half_stakes: bool,
+    ) {
+        let mut refunded = Amount::ZERO;
+        let mut to_treasury = Amount::ZERO;
+        let mut settled = Vec::new();
+
+        for bet in self.state.get_bets(market_id).await {
+            let pushed = if half_stakes {
+                half_of(bet.amount)
+            } else {
+                Amount::ZERO
+            };
+            let in_play = bet.amount.saturating_sub(pushed);
+            let kept = match policy {
+                NoWinnerPolicy::Refund { fee_bps } => {
+                    flashbet_shared::basis_points_of(in_play, fee_bps)
+                }
+                NoWinnerPolicy::Treasury => in_play,
+            };
+            let refund = bet.amount.saturating_sub(kept);
+            self.state
+                .record_entitlement(&bet, refund, EntitlementKind::Refund)
+                .await;
+            refunded.saturating_add_assign(refund);
+            to_treasury.saturating_add_assign(kept);
+            // Nobody won: a bet that gets nothing back lost its stake to the treasury
+            let status = if refund > Amount::ZERO {
+                SettlementStatus::Refunded
+            } else {
+                SettlementStatus::Lost
+            };
+            settled.push((bet, status, refund));
+        }
+        self.notify_settled(settled);
+
+        if to_treasury > Amount::ZERO {
+            self.transfer_to_treasury(to_treasury);
+        }
+
+        let event_id = self.event_of(market_id).await;
+
+        self.runtime.emit(
+            StreamName::from(b"market_events".to_vec()),
+            &MarketEvent::NoWinners {
+                market_id,
+                event_id,
+                policy,
+                refunded,
+                to_treasury,
+            },
+        );
+    }
+
+    /// Get the event a market is for
+    async fn event_of(&self, market_id: MarketId) -> EventId {
+        self.state
+            .get_market(market_id)
+            .await
+            .expect("Market not found")
+            .event_id
+    }
+
+    /// Get the markets a reference points to (none if it names no market)
+    async fn markets_for(&self, market: &MarketRef) -> Vec<MarketId> {
+        match market {
+            MarketRef::MarketId(market_id) => {
+                if self.state.market_exists(*market_id).await {
+                    vec![*market_id]
+                } else {
+                    Vec::new()
+                }
+            }
+            MarketRef::EventId(event_id) => self.state.get_event_markets(event_id).await,
+        }
+    }
+
+    /// Check the operation is signed by the owner
+    fn assert_owner(&mut self) {
+        let signer = self
+            .runtime
+            .authenticated_signer()
+            .expect("Must be signed operation");
+        assert!(self.state.is_owner(&signer), "Only owner can manage roles");
+    }
+
+    /// Check the operation is signed by an account holding a role
+    async fn assert_role(&mut self, role: MarketRole) {
+        let signer = self
+            .runtime
+            .authenticated_signer()
+            .expect("Must be signed operation");
+        assert!(
+            self.state.has_role(role, &signer).await,
+            "Missing role {:?}: {:?}",
+            role,
+            signer
+        );
+    }
+
+    /// Get the markets a reference points to, which must name at least one market
+    async fn existing_markets(&self, market: &MarketRef) -> Vec<MarketId> {
+        let market_ids = self.markets_for(market).await;
+        assert!(!market_ids.is_empty(), "Market {} does not exist", market);
+        market_ids
+    }
+
+    /// Find the market a bet is for: the market it names on its event, or the event's only
+    /// market (clients that predate market IDs always send `MarketId(0)`)
+    async fn market_for_bet(&self, bet: &Bet) -> Option<MarketInfo> {
+        let event_markets = self.state.get_event_markets(&bet.event_id).await;
+        let market_id = if event_markets.contains(&bet.market_id) {
+            bet.market_id
+        } else if let [market_id] = event_markets.as_slice() {
+            *market_id
+        } else {
+            return None;
+        };
+        self.state.get_market(market_id).await
+    }
+
+    /// Add a bet to its market's pool, or turn it down and return its stake
+    async fn register_bet(
+        &mut self,
+        mut bet: Bet,
+        min_payout_bps: Option<u32>,
+    ) -> OperationResponse {
+        // 1. Check market exists, recording the bet under its market's ID
+        let Some(market_info) = self.market_for_bet(&bet).await else {
+            return self.reject_bet(&bet, BetRejection::MarketNotFound);
+        };
+        let market_id = market_info.market_id;
+        bet.market_id = market_id;
+
+        // 2. Validate market is open (locking it if the betting window has closed)
+        if *self.state.paused.get() {
+            return self.reject_bet(&bet, BetRejection::MarketNotOpen(MarketStatus::Suspended));
+        }
+        self.lock_if_expired(market_id).await;
+        if !self.state.is_open(market_id).await {
+            let status = self.state.get_status(market_id).await;
+            return self.reject_bet(&bet, BetRejection::MarketNotOpen(status));
+        }
+
+        // 3. Validate outcome is valid for this market type
+        if !flashbet_shared::validate_outcome_for_market(bet.outcome, &market_info.market_type) {
+            return self.reject_bet(&bet, BetRejection::InvalidOutcome);
+        }
+
+        // 4. Price fixed-odds bets at the odds currently offered on their outcome
+        let total_pool = self.state.get_total_pool(market_id).await;
+        let pool_after_bet = total_pool.saturating_add(bet.amount);
+        let fixed_price = match market_info.pricing {
+            PricingMode::Parimutuel => None,
+            PricingMode::Lmsr => {
+                return self.reject_bet(&bet, BetRejection::SharesOnly);
+            }
+            PricingMode::FixedOdds => {
+                let Some(odds_bps) = self.state.get_fixed_odds(market_id, &bet.outcome).await
+                else {
+                    return self.reject_bet(&bet, BetRejection::OddsUnavailable);
+                };
+                let payout = flashbet_shared::fixed_odds_payout(bet.amount, odds_bps);
+                Some((odds_bps, payout))
+            }
+        };
+
+        // 5. Enforce the market's stake, exposure and liability limits
+        let user_exposure = self.state.get_exposure(market_id, &bet.user).await;
+        let liability = match fixed_price {
+            Some((_, payout)) => {
+                self.worst_liability(&market_info, bet.outcome, payout)
+                    .await
+            }
+            None => pool_after_bet,
+        };
+        if let Err(reason) = market_info
+            .limits
+            .check(bet.amount, user_exposure, liability)
+        {
+            return self.reject_bet(&bet, reason);
+        }
+
+        // 6. Check the odds, counting the bet's own stake, still meet the bettor's minimum
+        if let Some(min_bps) = min_payout_bps {
+            let offered_bps = match fixed_price {
+                Some((odds_bps, _)) => u64::from(odds_bps),
+                None => {
+                    let outcome_pool = self
+                        .state
+                        .get_pool_for_outcome(market_id, &bet.outcome)
+                        .await
+                        .saturating_add(bet.amount);
+                    market_info.payout_multiplier_bps(pool_after_bet, outcome_pool)
+                }
+            };
+            if offered_bps < u64::from(min_bps) {
+                return self.reject_bet(
+                    &bet,
+                    BetRejection::OddsBelowMinimum {
+                        offered_bps,
+                        min_bps,
+                    },
+                );
+            }
+        }
+
+        // 7. Check the bankroll covers the most a fixed-odds market could lose:
+        // its worst outcome's payouts beyond the stakes it holds
+        let worst_loss = liability.saturating_sub(pool_after_bet);
+        if fixed_price.is_some() && worst_loss > self.state.available_bankroll(market_id).await {
+            return self.reject_bet(&bet, BetRejection::BankrollExhausted);
+        }
+
+        // 8. Add bet to market state
+        if self.state.add_bet(bet.clone()).await.is_err() {
+            // Bet already registered (replayed message): its stake arrived again
+            return self.reject_bet(&bet, BetRejection::DuplicateBet);
+        }
+
+        // 9. Lock in fixed odds and reserve the bankroll they may need
+        if let Some((odds_bps, payout)) = fixed_price {
+            self.state.lock_odds(&bet, odds_bps, payout).await;
+            self.state.reserve_bankroll(market_id, worst_loss).await;
+        }
+
+        // 10. Emit BetPlaced event
+        let total_pool = self.state.get_total_pool(market_id).await;
+        self.runtime.emit(
+            StreamName::from(b"market_events".to_vec()),
+            &MarketEvent::BetPlaced {
+                market_id: bet.market_id,
+                bet_id: bet.bet_id,
+                user_chain: bet.user_chain,
+                outcome: bet.outcome,
+                amount: bet.amount,
+                total_pool,
+            },
+        );
+
+        OperationResponse::Ok
+    }
+
+    /// Check a bet comes from the User application, signed by the bettor
+    ///
+    /// Only the User application's PlaceBet moves a stake into the market's escrow, and its
+    /// instance on this chain only forwards bets received from their own User chain.
+    fn assert_authentic_bet(&mut self, bet: &Bet) {
+        let user_app_id = self
+            .state
+            .user_app_id
+            .get()
+            .expect("User application not configured");
+        assert!(
+            self.runtime.authenticated_caller_id() == Some(user_app_id),
+            "Bets must be registered by the User application"
+        );
+        assert!(
+            self.runtime.authenticated_signer() == Some(bet.user),
+            "Bet must be signed by its bettor"
+        );
+    }
+
+    /// Lock an open market once the current time has reached its betting cutoff
+    ///
+    /// Returns `true` if the market was locked by this call.
+    async fn lock_if_expired(&mut self, market_id: MarketId) -> bool {
+        if !self.state.is_open(market_id).await {
+            return false;
+        }
+
+        let Some(lock_time) = self.state.lock_time(market_id).await else {
+            return false;
+        };
+
+        if self.runtime.system_time() < lock_time {
+            return false;
+        }
+
+        self.state.lock_market(market_id).await;
+
+        let event_id = self.event_of(market_id).await;
+
+        self.runtime.emit(
+            StreamName::from(b"market_events".to_vec()),
+            &MarketEvent::MarketLocked {
+                market_id,
+                event_id,
+            },
+        );
+
+        true
+    }
+
+    /// Suspend an open or locked market
+    async fn pause_market(&mut self, market_id: MarketId) {
+        let status = self.state.get_status(market_id).await;
+        assert!(
+            matches!(status, MarketStatus::Open | MarketStatus::Locked),
+            "Market {} cannot be paused, status: {:?}",
+            market_id,
+            status
+        );
+
+        self.state.suspend_market(market_id).await;
+
+        let event_id = self.event_of(market_id).await;
+        self.runtime.emit(
+            StreamName::from(b"market_events".to_vec()),
+            &MarketEvent::MarketSuspended {
+                market_id,
+                event_id,
+            },
+        );
+    }
+
+    /// Restore a suspended market, settling the result held meanwhile unless the chain is paused
+    async fn resume_market(&mut self, market_id: MarketId) {
+        let status = self.state.get_status(market_id).await;
+        assert!(
+            status == MarketStatus::Suspended,
+            "Market {} is not suspended, status: {:?}",
+            market_id,
+            status
+        );
+
+        let status = self.state.resume_market(market_id).await;
+
+        let event_id = self.event_of(market_id).await;
+        self.runtime.emit(
+            StreamName::from(b"market_events".to_vec()),
+            &MarketEvent::MarketResumed {
+                market_id,
+                event_id,
+                status,
+            },
+        );
+
+        if !*self.state.paused.get() {
+            self.settle_deferred(market_id).await;
+        }
+    }
+
+    /// Settle a market from the oracle result held while it was paused, if any
+    async fn settle_deferred(&mut self, market_id: MarketId) {
+        if let Some(result) = self.state.take_deferred_result(market_id).await {
+            self.settle_market(market_id, &result).await;
+        }
+    }
+
+    /// Handle an oracle result and resolve every market on its event
+    async fn handle_oracle_result(&mut self, result: EventResult) {
+        // Markets that don't exist simply ignore the result
+        for market_id in self.state.get_event_markets(&result.event_id).await {
+            self.settle_market(market_id, &result).await;
+        }
+    }
+
+    /// Resolve a market from its event's result
+    async fn settle_market(&mut self, market_id: MarketId, result: &EventResult) {
+        // Check if market is already resolved, pushed or cancelled
+        let status = self.state.get_status(market_id).await;
+        if matches!(
+            status,
+            MarketStatus::Resolved(_) | MarketStatus::Cancelled | MarketStatus::Push
+        ) {
+            // Already resolved or refunded, ignore
+            return;
+        }
+
+        // Hold the result while the market or the chain is paused
+        if *self.state.paused.get() || status == MarketStatus::Suspended {
+            self.state.defer_result(market_id, result.clone()).await;
+            return;
+        }
+
+        // Decide the winning side from the market type (totals settle from the score)
+        let market_info = self
+            .state
+            .get_market(market_id)
+            .await
+            .expect("Market not found");
+        // On a quarter-line split, only half of every stake is in play; the other half is refunded
+        let (winning_outcome, half_stakes) = match market_info.market_type.settle(result) {
+            Ok(Settlement::Winner(outcome)) => (outcome, false),
+            Ok(Settlement::HalfWin(outcome)) => (outcome, true),
+            Ok(Settlement::Push) => {
+                self.push_market(market_id).await;
+                return;
+            }
+            Err(_) => {
+                // Result cannot settle this market (e.g. no score for a total), ignore
+                return;
+            }
+        };
+
+        // Resolve the market
+        self.state.resolve_market(market_id, winning_outcome).await;
+
+        // Fixed-odds winners are paid at their locked odds, and LMSR winners per share,
+        // rather than from the pool
+        match market_info.pricing {
+            PricingMode::Parimutuel => {}
+            PricingMode::FixedOdds => {
+                self.settle_fixed_odds(market_id, winning_outcome, half_stakes)
+                    .await;
+                return;
+            }
+            PricingMode::Lmsr => {
+                self.settle_shares(&market_info, Some((winning_outcome, half_stakes)))
+                    .await;
+                return;
+            }
+        }
+
+        let total_pool = self.state.get_total_pool(market_id).await;
+        let winning_pool = self
+            .state
+            .get_pool_for_outcome(market_id, &winning_outcome)
+            .await;
+
+        // Split the pool exactly: payouts plus the rake add up to the total stake
+        let bets = self.state.get_bets(market_id).await;
+        let stakes = bets
+            .iter()
+            .map(|bet| (bet.amount, bet.outcome == winning_outcome))
+            .collect::<Vec<_>>();
+        let settlement = flashbet_shared::settle_pool(&stakes, &market_info.rake, half_stakes);
+        let num_winners = stakes.iter().filter(|(_, won)| *won).count() as u64;
+
+        // Emit MarketResolved event
+        self.runtime.emit(
+            StreamName::from(b"market_events".to_vec()),
+            &MarketEvent::MarketResolved {
+                market_id,
+                winning_outcome,
+                total_pool,
+                winning_pool,
+                num_winners,
+                rake: settlement
+                    .as_ref()
+                    .map_or(Amount::ZERO, |settlement| settlement.rake),
+            },
+        );
+
+        // Nobody backed the winning outcome: settle the pool by the market's policy
+        let Some(settlement) = settlement else {
+            if total_pool > Amount::ZERO {
+                self.settle_without_winners(market_id, market_info.no_winner_policy, half_stakes)
+                    .await;
+            }
+            return;
+        };
+
+        // The house rake goes to the treasury before bettors are paid
+        if settlement.rake > Amount::ZERO {
+            self.transfer_to_treasury(settlement.rake);
+        }
+
+        // Record what each bet is owed; it is paid out by ClaimPayout or SettleNext
+        let mut settled = Vec::with_capacity(bets.len());
+        for (bet, amount) in bets.into_iter().zip(settlement.payouts) {
+            // Losing bets are only owed the half of their stake that was not in play
+            let (kind, status) = if bet.outcome == winning_outcome {
+                (EntitlementKind::Payout, SettlementStatus::Won)
+            } else {
+                (EntitlementKind::Refund, SettlementStatus::Lost)
+            };
+            self.state.record_entitlement(&bet, amount, kind).await;
+            settled.push((bet, status, amount));
+        }
+        self.notify_settled(settled);
+    }
+
+    /// Settle a resolved fixed-odds market: winners are owed their stake at the odds they
+    /// locked in, and the stakes left over go to the bankroll with the market's reservation
+    ///
+    /// With `half_stakes`, only half of each stake was in play: the other half is refunded in full.
+    async fn settle_fixed_odds(
+        &mut self,
+        market_id: MarketId,
+        winning_outcome: Outcome,
+        half_stakes: bool,
+    ) {
+        let total_pool = self.state.get_total_pool(market_id).await;
+        let winning_pool = self
+            .state
+            .get_pool_for_outcome(market_id, &winning_outcome)
+            .await;
+        let bets = self.state.get_bets(market_id).await;
+
+        let mut owed = Amount::ZERO;
+        let mut settled = Vec::with_capacity(bets.len());
+        for bet in bets {
+            let pushed = if half_stakes {
+                half_of(bet.amount)
+            } else {
+                Amount::ZERO
+            };
+            let (kind, status, amount) = if bet.outcome == winning_outcome {
+                let odds_bps = self
+                    .state
+                    .get_locked_odds(market_id, &bet.key())
+                    .await
+                    .expect("Fixed-odds bet without locked odds");
+                let in_play = bet.amount.saturating_sub(pushed);
+                let payout = flashbet_shared::fixed_odds_payout(in_play, odds_bps);
+                (
+                    EntitlementKind::Payout,
+                    SettlementStatus::Won,
+                    payout.saturating_add(pushed),
+                )
+            } else {
+                // Losing bets are only owed the half of their stake that was not in play
+                (EntitlementKind::Refund, SettlementStatus::Lost, pushed)
+            };
+            self.state.record_entitlement(&bet, amount, kind).await;
+            owed.saturating_add_assign(amount);
+            settled.push((bet, status, amount));
+        }
+        self.state.release_bankroll(market_id, owed).await;
+
+        let num_winners = settled
+            .iter()
+            .filter(|(_, status, _)| *status == SettlementStatus::Won)
+            .count() as u64;
+        self.runtime.emit(
+            StreamName::from(b"market_events".to_vec()),
+            &MarketEvent::MarketResolved {
+                market_id,
+                winning_outcome,
+                total_pool,
+                winning_pool,
+                num_winners,
+                rake: Amount::ZERO,
+            },
+        );
+
+        self.notify_settled(settled);
+    }
+
+    /// Fix what each share of an LMSR market pays out and return what the market maker keeps
+    /// to the bankroll
+    ///
+    /// A winning share pays 1 BET. Without a winner (cancelled or pushed), each share pays its
+    /// last price; on a half win, half of each share is settled that way and the other half
+    /// pays 1 BET if it won. Shares are redeemed with RedeemShares.
+    async fn settle_shares(&mut self, market_info: &MarketInfo, winner: Option<(Outcome, bool)>) {
+        let market_id = market_info.market_id;
+        let liquidity = market_info
+            .liquidity
+            .expect("LMSR market without liquidity");
+        let supply = self.state.get_share_supply(market_info).await;
+        let prices =
+            flashbet_shared::lmsr_prices(liquidity, &supply).expect("Failed to price shares");
+
+        let mut owed = Amount::ZERO;
+        for (index, (shares, price)) in supply.iter().zip(prices).enumerate() {
+            let outcome = Outcome::from_index(index as u32);
+            let won = |winning_outcome: Outcome| {
+                if outcome == winning_outcome {
+                    Amount::ONE
+                } else {
+                    Amount::ZERO
+                }
+            };
+            let value = match winner {
+                None => price,
+                Some((winning_outcome, false)) => won(winning_outcome),
+                Some((winning_outcome, true)) => {
+                    half_of(price).saturating_add(half_of(won(winning_outcome)))
+                }
+            };
+            self.state.set_share_value(market_id, outcome, value);
+            owed.saturating_add_assign(shares_value(*shares, value));
+        }
+        self.state.release_bankroll(market_id, owed).await;
+
+        if let Some((winning_outcome, _)) = winner {
+            let num_winners = self
+                .state
+                .get_positions(market_id)
+                .await
+                .iter()
+                .filter(|(_, outcome, _)| *outcome == winning_outcome)
+                .count() as u64;
+            self.runtime.emit(
+                StreamName::from(b"market_events".to_vec()),
+                &MarketEvent::MarketResolved {
+                    market_id,
+                    winning_outcome,
+                    total_pool: self.state.get_total_pool(market_id).await,
+                    winning_pool: supply[winning_outcome.index() as usize],
+                    num_winners,
+                    rake: Amount::ZERO,
+                },
+            );
+        }
+    }
+
+    /// Check shares of an outcome of an LMSR market can be traded now, returning the market's
+    /// liquidity and outstanding shares
+    async fn tradable_shares(
+        &mut self,
+        market_id: MarketId,
+        outcome: Outcome,
+        shares: Amount,
+    ) -> (Amount, Vec<Amount>) {
+        let market_info = self.assert_tradable(market_id, outcome, shares).await;
+        let liquidity = market_info
+            .liquidity
+            .expect("LMSR market without liquidity");
+        (liquidity, self.state.get_share_supply(&market_info).await)
+    }
+
+    /// Check shares of an outcome of an LMSR market can be traded now, with the market maker
+    /// or on the order book
+    async fn assert_tradable(
+        &mut self,
+        market_id: MarketId,
+        outcome: Outcome,
+        shares: Amount,
+    ) -> MarketInfo {
+        let market_info = self
+            .state
+            .get_market(market_id)
+            .await
+            .unwrap_or_else(|| panic!("Market {} does not exist", market_id));
+        assert!(
+            market_info.pricing == PricingMode::Lmsr,
+            "Market {} does not trade shares",
+            market_id
+        );
+        assert!(!*self.state.paused.get(), "Chain is paused");
+        self.lock_if_expired(market_id).await;
+        assert!(
+            self.state.is_open(market_id).await,
+            "Market {} is not open, status: {:?}",
+            market_id,
+            self.state.get_status(market_id).await
+        );
+        assert!(
+            flashbet_shared::validate_outcome_for_market(outcome, &market_info.market_type),
+            "Invalid outcome {} for market {}",
+            outcome,
+            market_id
+        );
+        assert!(shares > Amount::ZERO, "Shares must be positive");
+        market_info
+    }
+
+    /// Fill an incoming order against the other side of its book, best price first, returning
+    /// what is left of it
+    ///
+    /// Each fill trades at the resting order's price: the buyer's escrow pays the seller and
+    /// the shares go to the buyer. Resting orders filled completely leave the book.
+    async fn match_order(&mut self, mut order: Order) -> Order {
+        let makers = self
+            .state
+            .get_crossing_orders(
+                (order.market_id, order.outcome, order.side.opposite()),
+                order.price,
+                order.remaining,
+            )
+            .await;
+
+        for mut maker in makers {
+            let (price, maker_id) = (maker.price, maker.order_id);
+            let shares = order.remaining.min(maker.remaining);
+            let value = shares_value(shares, price);
+            order.remaining = order.remaining.saturating_sub(shares);
+            maker.remaining = maker.remaining.saturating_sub(shares);
+
+            let (bid, ask) = match order.side {
+                OrderSide::Bid => (&mut order, &mut maker),
+                OrderSide::Ask => (&mut maker, &mut order),
+            };
+            // A bid escrowed its shares at its own price, never below the resting price
+            bid.escrow = bid
+                .escrow
+                .try_sub(value)
+                .expect("Bid escrow cannot cover fill");
+            let (buyer, seller) = (bid.owner, ask.owner);
+
+            self.state
+                .add_shares(order.market_id, buyer, order.outcome, shares)
+                .await;
+            if value > Amount::ZERO {
+                self.transfer_to_account(seller, value);
+            }
+
+            let trade = Trade {
+                market_id: order.market_id,
+                outcome: order.outcome,
+                price,
+                shares,
+                buyer,
+                seller,
+                maker_order_id: maker_id,
+                taker_order_id: order.order_id,
+                timestamp: self.runtime.system_time(),
+            };
+            self.state.record_trade(trade.clone()).await;
+            self.runtime.emit(
+                StreamName::from(b"market_events".to_vec()),
+                &MarketEvent::OrderFilled { trade },
+            );
+
+            if maker.remaining == Amount::ZERO {
+                self.state.remove_order(&maker).await;
+                self.release_escrow(&maker).await;
+            } else {
+                self.state.update_order(maker).await;
+            }
+        }
+        order
+    }
+
+    /// Return what an order leaving the book still holds in escrow to its owner: a bid's
+    /// unspent BET, or an ask's unsold shares
+    async fn release_escrow(&mut self, order: &Order) {
+        match order.side {
+            OrderSide::Bid => {
+                if order.escrow > Amount::ZERO {
+                    self.transfer_to_account(order.owner, order.escrow);
+                }
+            }
+            OrderSide::Ask => {
+                if order.remaining > Amount::ZERO {
+                    self.state
+                        .add_shares(order.market_id, order.owner, order.outcome, order.remaining)
+                        .await;
+                }
+            }
+        }
+    }
+
+    /// Get the most a fixed-odds market would owe on any of its outcomes once a bet on
+    /// `outcome` winning `payout` is accepted
+    async fn worst_liability(
+        &self,
+        market_info: &MarketInfo,
+        outcome: Outcome,
+        payout: Amount,
+    ) -> Amount {
+        let mut worst = Amount::ZERO;
+        for info in market_info.outcomes() {
+            let candidate = Outcome::from_index(info.index);
+            let mut liability = self
+                .state
+                .get_liability(market_info.market_id, &candidate)
+                .await;
+            if candidate == outcome {
+                liability.saturating_add_assign(payout);
+            }
+            worst = worst.max(liability);
+        }
+        worst
+    }
+
+    /// Tell every participating User chain how its bets settled, winners and losers alike
+    ///
+    /// Publishes one notice per User chain with the statuses of all of its bets.
+    fn notify_settled(&mut self, settled: Vec<(Bet, SettlementStatus, Amount)>) {
+        let timestamp = self.runtime.system_time();
+        let mut notices_by_chain = BTreeMap::<ChainId, Vec<SettlementNotice>>::new();
+        for (bet, status, amount) in settled {
+            notices_by_chain
+                .entry(bet.user_chain)
+                .or_default()
+                .push(SettlementNotice {
+                    market_id: bet.market_id,
+                    event_id: bet.event_id.clone(),
+                    bet_id: bet.bet_id,
+                    status,
+                    amount,
+                    timestamp,
+                });
+        }
+
+        for (user_chain, notices) in notices_by_chain {
+            self.notify_user(user_chain, UserNotice::Settled { notices });
+        }
+    }
+
+    /// Publish a notice on a User chain's notice stream, which its User application reads
+    fn notify_user(&mut self, user_chain: ChainId, notice: UserNotice) {
+        self.runtime.emit(
+            flashbet_shared::user_notices_stream(user_chain),
+            &MarketEvent::UserNotice(notice),
+        );
+    }
+
+    /// Pay out what a settled bet is owed, once
+    ///
+    /// Bets that are owed nothing or were already paid are skipped, so claims can be retried.
+    async fn claim(&mut self, market_id: MarketId, key: &BetKey) {
+        let Some(entitlement) = self.state.get_entitlement(market_id, key).await else {
+            return;
+        };
+        if entitlement.claimed {
+            return;
+        }
+
+        let bet = entitlement.bet.clone();
+        let amount = entitlement.amount;
+        let kind = entitlement.kind;
+        self.state.mark_claimed(entitlement).await;
+
+        match kind {
+            EntitlementKind::Payout => self.pay_winnings(&bet, amount),
+            EntitlementKind::Refund => self.refund_bet(&bet, amount),
+        }
+    }
+
+    /// Pay out the next `max_bets` bets of a settled market, in settlement order
+    ///
+    /// Bets claimed in the meantime are skipped but count towards the chunk. Does nothing
+    /// before the market settles or once settlement is complete. Returns the number of bets
+    /// processed.
+    async fn settle_next(&mut self, market_id: MarketId, max_bets: u64) -> u64 {
+        let mut progress = self.state.get_settlement_progress(market_id).await;
+        if progress.is_complete() {
+            return 0;
+        }
+
+        let start = progress.settled;
+        let end = progress
+            .total
+            .min(progress.settled.saturating_add(max_bets));
+        for position in progress.settled..end {
+            let key = self
+                .state
+                .get_queued_bet(market_id, position)
+                .await
+                .expect("Settlement queue out of sync");
+            self.claim(market_id, &key).await;
+        }
+
+        progress.settled = end;
+        self.state.set_settlement_progress(market_id, progress);
+
+        if progress.is_complete() {
+            let event_id = self.event_of(market_id).await;
+            self.runtime.emit(
+                StreamName::from(b"market_events".to_vec()),
+                &MarketEvent::SettlementCompleted {
+                    market_id,
+                    event_id,
+                    num_settled: progress.total,
+                },
+            );
+        }
+
+        end - start
+    }
+
+    /// Transfer winnings to a bettor's User chain and notify the bettor
+    fn pay_winnings(&mut self, bet: &Bet, amount: Amount) {
+        let payout = Payout {
+            market_id: bet.market_id,
+            bet_id: bet.bet_id,
+            amount,
+            timestamp: self.runtime.system_time(),
+        };
+
+        // Transfer BET tokens from Market chain to winner's chain
+        self.transfer_to_bettor(bet, amount);
+
+        // Notify the User chain to update its state tracking
+        self.notify_user(bet.user_chain, UserNotice::Payout(payout));
+
+        // Emit event for logging
+        self.runtime.emit(
+            StreamName::from(b"payout_events".to_vec()),
+            &MarketEvent::PayoutDistributed {
+                market_id: bet.market_id,
+                bet_id: bet.bet_id,
+                user_chain: bet.user_chain,
+                amount,
+            },
+        );
+    }
+}
+
+/// Half of an amount, rounded down
+fn half_of(amount: Amount) -> Amount {
+    flashbet_shared::basis_points_of(amount, flashbet_shared::BASIS_POINTS / 2)
+}
+
+/// What `shares` pay out at `value` BET per share, rounded down
+fn shares_value(shares: Amount, value: Amount) -> Amount {
+    flashbet_shared::mul_div(shares.into(), value.into(), Amount::ONE.into())
+        .map_or(Amount::MAX, |(attos, _)| Amount::from_attos(attos))
+}
+
+/// Whether a market in this status can still be cancelled
+fn is_cancellable(status: &MarketStatus) -> bool {
+    matches!(
+        status,
+        MarketStatus::Open | MarketStatus::Locked | MarketStatus::Suspended
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use flashbet_market::{CreateMarketInput, InstantiationArgument, Operation, OperationResponse};
+    use flashbet_shared::{
+        Bet, BetRejection, EntitlementKind, EventId, EventResult, MarketEvent, MarketId, MarketRef,
+        MarketRole, MarketStatus, MarketTypeInput, NoWinnerPolicyInput, OracleEvent, OrderSide,
+        Outcome, OutcomeOdds, Payout, PricingMode, RakeBase, Score, SettlementProgress, UserNotice,
+    };
+    use futures::FutureExt as _;
+    use linera_sdk::{
+        abis::fungible::FungibleResponse,
+        linera_base_types::{
+            Account, AccountOwner, Amount, ApplicationId, ChainId, CryptoHash,
+            GenericApplicationId, StreamId, StreamName, StreamUpdate, Timestamp,
+        },
+        util::BlockingWait,
+        views::View,
+        Contract, ContractRuntime,
+    };
+
+    use super::{shares_value, FlashbetMarketContract, FlashbetMarketState};
+
+    /// Kickoff time of the test market, in seconds
+    const EVENT_TIME_SECS: u64 = 10_000;
+    /// Betting closes this many seconds before kickoff
+    const CUTOFF_SECS: u64 = 60;
+
+    #[test]
+    fn bet_accepted_before_cutoff() {
+        let mut app = create_app_with_market();
+        app.runtime
+            .set_system_time(secs(EVENT_TIME_SECS - CUTOFF_SECS - 1));
+
+        place_bet(&mut app, bet(0, Outcome::Home, 10));
+
+        assert_eq!(
+            app.state.get_status(market_id()).blocking_wait(),
+            MarketStatus::Open
+        );
+        assert_eq!(
+            app.state.get_total_pool(market_id()).blocking_wait(),
+            Amount::from_tokens(10)
+        );
+    }
+
+    #[test]
+    fn bet_rejected_after_cutoff() {
+        let mut app = create_app_with_market();
+        let transfers = mock_token_transfers(&mut app);
+        app.runtime
+            .set_system_time(secs(EVENT_TIME_SECS - CUTOFF_SECS));
+
+        let response = place_bet(&mut app, bet(0, Outcome::Home, 10));
+
+        assert_eq!(app.state.get_bet_count(market_id()).blocking_wait(), 0);
+        assert_eq!(
+            app.state.get_total_pool(market_id()).blocking_wait(),
+            Amount::ZERO
+        );
+        assert_stake_returned(
+            response,
+            &transfers,
+            BetRejection::MarketNotOpen(MarketStatus::Locked),
+        );
+    }
+
+    #[test]
+    fn stake_below_minimum_rejected() {
+        let mut app = create_app_with(CreateMarketInput {
+            min_stake: Some(Amount::from_tokens(20)),
+            ..market_input()
+        });
+        let transfers = mock_token_transfers(&mut app);
+
+        let response = place_bet(&mut app, bet(0, Outcome::Home, 10));
+
+        assert_eq!(app.state.get_bet_count(market_id()).blocking_wait(), 0);
+        assert_stake_returned(
+            response,
+            &transfers,
+            BetRejection::StakeBelowMinimum(Amount::from_tokens(20)),
+        );
+    }
+
+    #[test]
+    fn exposure_and_liability_limits_reject_bets() {
+        let mut app = create_app_with(CreateMarketInput {
+            max_user_exposure: Some(Amount::from_tokens(15)),
+            max_liability: Some(Amount::from_tokens(22)),
+            ..market_input()
+        });
+        mock_token_transfers(&mut app);
+        let other_user = AccountOwner::from([2; 32]);
+
+        let responses = [
+            place_bet(&mut app, bet(0, Outcome::Home, 10)),
+            place_bet(&mut app, bet(1, Outcome::Away, 10)),
+            place_bet(
+                &mut app,
+                Bet {
+                    user: other_user,
+                    ..bet(2, Outcome::Away, 10)
+                },
+            ),
+            place_bet(
+                &mut app,
+                Bet {
+                    user: other_user,
+                    ..bet(3, Outcome::Away, 5)
+                },
+            ),
+        ];
+
+        assert_eq!(app.state.get_bet_count(market_id()).blocking_wait(), 2);
+        assert_eq!(
+            app.state
+                .get_exposure(market_id(), &bet(0, Outcome::Home, 0).user)
+                .blocking_wait(),
+            Amount::from_tokens(10)
+        );
+        assert_eq!(
+            app.state.get_total_pool(market_id()).blocking_wait(),
+            Amount::from_tokens(20)
+        );
+
+        let reasons = responses
+            .into_iter()
+            .enumerate()
+            .filter_map(|(bet_id, response)| match response {
+                OperationResponse::BetRejected(reason) => Some((bet_id, reason)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            reasons,
+            vec![
+                (
+                    1,
+                    BetRejection::ExposureLimitReached(Amount::from_tokens(15))
+                ),
+                (
+                    3,
+                    BetRejection::LiabilityLimitReached(Amount::from_tokens(22))
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn bet_below_minimum_odds_rejected() {
+        let mut app = create_app_with_market();
+        mock_token_transfers(&mut app);
+        place_bet(&mut app, bet(0, Outcome::Home, 10));
+        place_bet(&mut app, bet(1, Outcome::Away, 10));
+
+        // Another 10 on Home pays 30 / 20 = 1.5x
+        let rejections = [(2, 20_000), (3, 15_000)]
+            .into_iter()
+            .filter_map(|(bet_id, min_payout_bps)| {
+                match register_bet(
+                    &mut app,
+                    bet(bet_id, Outcome::Home, 10),
+                    Some(min_payout_bps),
+                ) {
+                    OperationResponse::BetRejected(reason) => Some((bet_id, reason)),
+                    _ => None,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(app.state.get_bet_count(market_id()).blocking_wait(), 3);
+        assert_eq!(
+            rejections,
+            vec![(
+                2,
+                BetRejection::OddsBelowMinimum {
+                    offered_bps: 15_000,
+                    min_bps: 20_000
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn fixed_odds_bets_pay_locked_price() {
+        let (mut app, transfers) = create_fixed_odds_app(100);
+
+        // 10 on Home at 2.5 reserves the 15 the house could lose
+        set_odds(
+            &mut app,
+            &[(Outcome::Home, 25_000), (Outcome::Away, 16_000)],
+        );
+        place_bet(&mut app, bet(0, Outcome::Home, 10));
+        assert_eq!(*app.state.bankroll.get(), Amount::from_tokens(85));
+
+        // The next Home bet gets the new price, the first keeps its own
+        set_odds(&mut app, &[(Outcome::Home, 30_000)]);
+        place_bet(&mut app, bet(1, Outcome::Home, 10));
+        place_bet(&mut app, bet(2, Outcome::Away, 10));
+        assert_eq!(
+            app.state
+                .get_liability(market_id(), &Outcome::Home)
+                .blocking_wait(),
+            Amount::from_tokens(55)
+        );
+        assert_eq!(
+            app.state.get_reserved(market_id()).blocking_wait(),
+            Amount::from_tokens(25)
+        );
+        assert_eq!(*app.state.bankroll.get(), Amount::from_tokens(75));
+
+        // Home pays 25 + 30 out of the 30 staked and the 25 reserved
+        process_oracle_result(&mut app, Outcome::Home);
+        assert_eq!(*app.state.bankroll.get(), Amount::from_tokens(75));
+        assert_eq!(
+            app.state.get_reserved(market_id()).blocking_wait(),
+            Amount::ZERO
+        );
+
+        claim_all(&mut app);
+        let paid = transfers
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|operation| match operation {
+                flashbet_token::Operation::TransferFromEscrow { to, amount }
+                    if to.chain_id != market_chain() =>
+                {
+                    Some(*amount)
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(paid, vec![Amount::from_tokens(25), Amount::from_tokens(30)]);
+    }
+
+    #[test]
+    fn fixed_odds_bet_without_odds_rejected() {
+        let (mut app, transfers) = create_fixed_odds_app(100);
+        set_odds(&mut app, &[(Outcome::Away, 16_000)]);
+
+        let response = place_bet(&mut app, bet(0, Outcome::Home, 10));
+
+        assert_eq!(app.state.get_bet_count(market_id()).blocking_wait(), 0);
+        assert_stake_returned(response, &transfers, BetRejection::OddsUnavailable);
+    }
+
+    #[test]
+    fn fixed_odds_bet_beyond_bankroll_rejected() {
+        // Winning 25 on a 10 stake could cost the house 15
+        let (mut app, transfers) = create_fixed_odds_app(14);
+        set_odds(&mut app, &[(Outcome::Home, 25_000)]);
+
+        let response = place_bet(&mut app, bet(0, Outcome::Home, 10));
+
+        assert_eq!(app.state.get_bet_count(market_id()).blocking_wait(), 0);
+        assert_eq!(*app.state.bankroll.get(), Amount::from_tokens(14));
+        assert_stake_returned(response, &transfers, BetRejection::BankrollExhausted);
+    }
+
+    #[test]
+    fn cancelling_fixed_odds_market_releases_bankroll() {
+        let (mut app, transfers) = create_fixed_odds_app(100);
+        set_odds(&mut app, &[(Outcome::Home, 25_000)]);
+        place_bet(&mut app, bet(0, Outcome::Home, 10));
+        assert_eq!(*app.state.bankroll.get(), Amount::from_tokens(85));
+
+        app.runtime.set_authenticated_signer(Some(owner()));
+        app.execute_operation(Operation::CancelMarket {
+            market: MarketRef::MarketId(market_id()),
+        })
+        .blocking_wait();
+        assert_eq!(*app.state.bankroll.get(), Amount::from_tokens(100));
+
+        // The whole bankroll is free to withdraw again
+        app.execute_operation(Operation::WithdrawBankroll {
+            to: treasury(),
+            amount: Amount::from_tokens(100),
+        })
+        .blocking_wait();
+        assert_eq!(*app.state.bankroll.get(), Amount::ZERO);
+        assert!(matches!(
+            transfers.lock().unwrap().as_slice(),
+            [flashbet_token::Operation::TransferFromEscrow { to, amount }]
+                if to.owner == treasury() && *amount == Amount::from_tokens(100)
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "Withdrawal exceeds the free bankroll")]
+    fn reserved_bankroll_cannot_be_withdrawn() {
+        let (mut app, _transfers) = create_fixed_odds_app(100);
+        set_odds(&mut app, &[(Outcome::Home, 25_000)]);
+        place_bet(&mut app, bet(0, Outcome::Home, 10));
+
+        app.runtime.set_authenticated_signer(Some(owner()));
+        app.execute_operation(Operation::WithdrawBankroll {
+            to: treasury(),
+            amount: Amount::from_tokens(100),
+        })
+        .blocking_wait();
+    }
+
+    #[test]
+    #[should_panic(expected = "Missing role PriceSetter")]
+    fn set_odds_requires_price_setter() {
+        let (mut app, _transfers) = create_fixed_odds_app(100);
+        app.runtime.set_authenticated_signer(Some(settler()));
+        app.execute_operation(Operation::SetOdds {
+            market_id: market_id(),
+            odds: vec![OutcomeOdds {
+                outcome: Outcome::Home,
+                odds_bps: 25_000,
+            }],
+        })
+        .blocking_wait();
+    }
+
+    #[test]
+    fn lmsr_shares_pay_one_bet_per_winning_share() {
+        let (mut app, transfers) = create_funded_app(lmsr_input(), 100);
+        // The market maker can lose at most b·ln(3)
+        let reserved = app.state.get_reserved(market_id()).blocking_wait();
+        assert!(reserved > Amount::from_tokens(54) && reserved < Amount::from_tokens(55));
+
+        trade_shares(&mut app, trader(1), Outcome::Home, 30, true);
+        trade_shares(&mut app, trader(2), Outcome::Away, 10, true);
+        trade_shares(&mut app, trader(1), Outcome::Home, 10, false);
+        assert_eq!(
+            app.state
+                .get_position(market_id(), &trader(1), &Outcome::Home)
+                .blocking_wait(),
+            Amount::from_tokens(20)
+        );
+        assert!(matches!(
+            transfers.lock().unwrap().last(),
+            Some(flashbet_token::Operation::TransferFromEscrow { to, .. }) if to.owner == trader(1)
+        ));
+
+        // The market maker pays the 20 winning shares from what it collected and its reservation
+        let free = *app.state.bankroll.get();
+        let collected = app.state.get_total_pool(market_id()).blocking_wait();
+        process_oracle_result(&mut app, Outcome::Home);
+        assert_eq!(
+            *app.state.bankroll.get(),
+            free.saturating_add(reserved)
+                .saturating_add(collected)
+                .saturating_sub(Amount::from_tokens(20))
+        );
+
+        transfers.lock().unwrap().clear();
+        for holder in [trader(1), trader(2), trader(1)] {
+            app.runtime.set_authenticated_signer(Some(holder));
+            app.execute_operation(Operation::RedeemShares {
+                market_id: market_id(),
+            })
+            .blocking_wait();
+        }
+        assert!(matches!(
+            transfers.lock().unwrap().as_slice(),
+            [flashbet_token::Operation::TransferFromEscrow { to, amount }]
+                if to.owner == trader(1) && *amount == Amount::from_tokens(20)
+        ));
+    }
+
+    #[test]
+    fn share_trades_pay_from_market_escrow() {
+        let (mut app, transfers) = create_funded_app(lmsr_input(), 100);
+        trade_shares(&mut app, trader(1), Outcome::Home, 30, true);
+        trade_shares(&mut app, trader(1), Outcome::Home, 10, false);
+        process_oracle_result(&mut app, Outcome::Home);
+        app.runtime.set_authenticated_signer(Some(trader(1)));
+        app.execute_operation(Operation::RedeemShares {
+            market_id: market_id(),
+        })
+        .blocking_wait();
+
+        // The mock token refuses unauthenticated calls and transfers out of the signer's
+        // account, so each payout is a debit of the market's own escrow
+        let trader_account = Account {
+            chain_id: market_chain(),
+            owner: trader(1),
+        };
+        assert!(matches!(
+            transfers.lock().unwrap().as_slice(),
+            [
+                flashbet_token::Operation::TransferToEscrow { .. },
+                flashbet_token::Operation::TransferFromEscrow { to: sold, .. },
+                flashbet_token::Operation::TransferFromEscrow { to: redeemed, amount },
+            ] if *sold == trader_account
+                && *redeemed == trader_account
+                && *amount == Amount::from_tokens(20)
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "BET token called without authentication")]
+    fn token_mock_rejects_unauthenticated_payouts() {
+        let (mut app, _transfers) = create_funded_app(lmsr_input(), 100);
+        app.runtime
+            .call_application::<flashbet_token::FlashbetTokenAbi>(
+                false,
+                bet_token_id().with_abi(),
+                &flashbet_token::Operation::TransferFromEscrow {
+                    to: Account {
+                        chain_id: market_chain(),
+                        owner: trader(1),
+                    },
+                    amount: Amount::ONE,
+                },
+            );
+    }
+
+    #[test]
+    #[should_panic(expected = "Market moved tokens outside its escrow")]
+    fn token_mock_rejects_payouts_from_signer() {
+        let (mut app, _transfers) = create_funded_app(lmsr_input(), 100);
+        app.runtime
+            .call_application::<flashbet_token::FlashbetTokenAbi>(
+                true,
+                bet_token_id().with_abi(),
+                &flashbet_token::Operation::Transfer {
+                    to: trader(1),
+                    amount: Amount::ONE,
+                },
+            );
+    }
+
+    #[test]
+    fn cancelled_lmsr_market_pays_last_price() {
+        let (mut app, transfers) = create_funded_app(lmsr_input(), 100);
+        trade_shares(&mut app, trader(1), Outcome::Home, 30, true);
+        let supply = app
+            .state
+            .get_share_supply(&app.state.get_market(market_id()).blocking_wait().unwrap())
+            .blocking_wait();
+        let price = flashbet_shared::lmsr_prices(Amount::from_tokens(50), &supply).unwrap()[0];
+        let value = shares_value(Amount::from_tokens(30), price);
+
+        app.runtime.set_authenticated_signer(Some(owner()));
+        app.execute_operation(Operation::CancelMarket {
+            market: MarketRef::MarketId(market_id()),
+        })
+        .blocking_wait();
+
+        transfers.lock().unwrap().clear();
+        app.runtime.set_authenticated_signer(Some(trader(1)));
+        app.execute_operation(Operation::RedeemShares {
+            market_id: market_id(),
+        })
+        .blocking_wait();
+        assert!(matches!(
+            transfers.lock().unwrap().as_slice(),
+            [flashbet_token::Operation::TransferFromEscrow { to, amount }]
+                if to.owner == trader(1) && *amount == value
+        ));
+    }
+
+    #[test]
+    fn bet_on_lmsr_market_rejected() {
+        let (mut app, transfers) = create_funded_app(lmsr_input(), 100);
+
+        let response = place_bet(&mut app, bet(0, Outcome::Home, 10));
+
+        assert_stake_returned(response, &transfers, BetRejection::SharesOnly);
+    }
+
+    #[test]
+    #[should_panic(expected = "exceeds the maximum")]
+    fn buy_shares_above_max_cost_rejected() {
+        let (mut app, _transfers) = create_funded_app(lmsr_input(), 100);
+        app.runtime.set_authenticated_signer(Some(trader(1)));
+        app.execute_operation(Operation::BuyShares {
+            market_id: market_id(),
+            outcome: Outcome::Home,
+            shares: Amount::from_tokens(10),
+            max_cost: Amount::from_tokens(3),
+        })
+        .blocking_wait();
+    }
+
+    #[test]
+    #[should_panic(expected = "Bankroll cannot cover the market maker's worst loss")]
+    fn lmsr_market_requires_bankroll() {
+        create_funded_app(lmsr_input(), 54);
+    }
+
+    #[test]
+    fn order_book_matches_at_resting_price_with_partial_fills() {
+        let (mut app, transfers) = create_funded_app(lmsr_input(), 100);
+        trade_shares(&mut app, trader(1), Outcome::Home, 30, true);
+        place_order(&mut app, trader(1), OrderSide::Ask, 600, 20);
+        assert_eq!(
+            app.state
+                .get_position(market_id(), &trader(1), &Outcome::Home)
+                .blocking_wait(),
+            Amount::from_tokens(10)
+        );
+
+        // A bid above the ask fills at the ask's price and refunds the difference
+        transfers.lock().unwrap().clear();
+        place_order(&mut app, trader(2), OrderSide::Bid, 700, 5);
+        assert!(matches!(
+            transfers.lock().unwrap().as_slice(),
+            [
+                flashbet_token::Operation::TransferToEscrow { amount: escrow },
+                flashbet_token::Operation::TransferFromEscrow { to: seller, amount: paid },
+                flashbet_token::Operation::TransferFromEscrow { to: buyer, amount: refund },
+            ] if *escrow == Amount::from_millis(3500)
+                && seller.owner == trader(1) && *paid == Amount::from_tokens(3)
+                && buyer.owner == trader(2) && *refund == Amount::from_millis(500)
+        ));
+
+        // The rest of the ask fills and the bid's remainder rests on the book, keeping the
+        // escrow it saved by filling below its price until it leaves the book
+        place_order(&mut app, trader(2), OrderSide::Bid, 650, 20);
+        assert_eq!(
+            app.state
+                .get_position(market_id(), &trader(2), &Outcome::Home)
+                .blocking_wait(),
+            Amount::from_tokens(20)
+        );
+        assert!(app
+            .state
+            .get_book(market_id(), Outcome::Home, OrderSide::Ask)
+            .blocking_wait()
+            .is_empty());
+        let bids = app
+            .state
+            .get_book(market_id(), Outcome::Home, OrderSide::Bid)
+            .blocking_wait();
+        assert_eq!(bids.len(), 1);
+        let bid = app
+            .state
+            .get_order(bids[0].order_id)
+            .blocking_wait()
+            .unwrap();
+        assert_eq!(bid, bids[0]);
+        assert_eq!(bid.remaining, Amount::from_tokens(5));
+        assert_eq!(bid.escrow, Amount::from_tokens(4));
+        assert_eq!(app.state.get_trade_count(market_id()).blocking_wait(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "does not trade shares")]
+    fn order_on_parimutuel_market_rejected() {
+        let mut app = create_app_with_market();
+        mock_token_transfers(&mut app);
+        place_order(&mut app, trader(1), OrderSide::Bid, 400, 1);
+    }
+
+    #[test]
+    fn order_books_keep_price_time_priority() {
+        let (mut app, _transfers) = create_funded_app(lmsr_input(), 100);
+        trade_shares(&mut app, trader(1), Outcome::Home, 10, true);
+        place_order(&mut app, trader(1), OrderSide::Ask, 700, 5);
+        place_order(&mut app, trader(1), OrderSide::Ask, 600, 5);
+        place_order(&mut app, trader(2), OrderSide::Bid, 400, 1);
+        place_order(&mut app, trader(3), OrderSide::Bid, 500, 1);
+        place_order(&mut app, trader(3), OrderSide::Bid, 400, 1);
+
+        let book = |app: &FlashbetMarketContract, side| {
+            app.state
+                .get_book(market_id(), Outcome::Home, side)
+                .blocking_wait()
+                .iter()
+                .map(|order| order.order_id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(book(&app, OrderSide::Ask), vec![1, 0]);
+        assert_eq!(book(&app, OrderSide::Bid), vec![3, 2, 4]);
+
+        let owned = app.state.get_owner_orders(&trader(3)).blocking_wait();
+        assert_eq!(
+            owned.iter().map(|order| order.order_id).collect::<Vec<_>>(),
+            vec![3, 4]
+        );
+    }
+
+    #[test]
+    fn cancel_order_returns_escrow() {
+        let (mut app, transfers) = create_funded_app(lmsr_input(), 100);
+        trade_shares(&mut app, trader(1), Outcome::Home, 10, true);
+        place_order(&mut app, trader(1), OrderSide::Ask, 800, 10);
+        place_order(&mut app, trader(2), OrderSide::Bid, 400, 10);
+
+        transfers.lock().unwrap().clear();
+        for (index, order_id) in [(1, 0), (2, 1)] {
+            app.runtime.set_authenticated_signer(Some(trader(index)));
+            app.execute_operation(Operation::CancelOrder { order_id })
+                .blocking_wait();
+        }
+
+        assert_eq!(
+            app.state
+                .get_position(market_id(), &trader(1), &Outcome::Home)
+                .blocking_wait(),
+            Amount::from_tokens(10)
+        );
+        assert!(matches!(
+            transfers.lock().unwrap().as_slice(),
+            [flashbet_token::Operation::TransferFromEscrow { to, amount }]
+                if to.owner == trader(2) && *amount == Amount::from_tokens(4)
+        ));
+        let market = app.state.get_market(market_id()).blocking_wait().unwrap();
+        assert!(app.state.get_orders(&market).blocking_wait().is_empty());
+        assert!(app
+            .state
+            .get_owner_orders(&trader(2))
+            .blocking_wait()
+            .is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "belongs to another account")]
+    fn cancel_requires_order_owner() {
+        let (mut app, _transfers) = create_funded_app(lmsr_input(), 100);
+        place_order(&mut app, trader(2), OrderSide::Bid, 400, 10);
+
+        app.runtime.set_authenticated_signer(Some(trader(1)));
+        app.execute_operation(Operation::CancelOrder { order_id: 0 })
+            .blocking_wait();
+    }
+
+    #[test]
+    #[should_panic(expected = "Price must be between 0 and 1 BET")]
+    fn order_price_must_be_below_one() {
+        let (mut app, _transfers) = create_funded_app(lmsr_input(), 100);
+        place_order(&mut app, trader(2), OrderSide::Bid, 1000, 10);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid stake limits")]
+    fn max_stake_below_minimum_rejected() {
+        create_app_with(CreateMarketInput {
+            min_stake: Some(Amount::from_tokens(20)),
+            max_stake: Some(Amount::from_tokens(10)),
+            ..market_input()
+        });
+    }
+
+    #[test]
+    fn bet_for_unknown_market_rejected() {
+        let mut app = create_app_with_market();
+        let transfers = mock_token_transfers(&mut app);
+
+        let response = place_bet(
+            &mut app,
+            Bet {
+                event_id: EventId::new("nba_game_404"),
+                ..bet(0, Outcome::Home, 10)
+            },
+        );
+
+        assert_eq!(app.state.get_bet_count(market_id()).blocking_wait(), 0);
+        assert_stake_returned(response, &transfers, BetRejection::MarketNotFound);
+    }
+
+    #[test]
+    fn late_bet_message_locks_market() {
+        let mut app = create_app_with_market();
+        mock_token_transfers(&mut app);
+        app.runtime.set_system_time(secs(EVENT_TIME_SECS + 1));
+
+        place_bet(&mut app, bet(0, Outcome::Away, 10));
+
+        assert_eq!(
+            app.state.get_status(market_id()).blocking_wait(),
+            MarketStatus::Locked
+        );
+        assert_eq!(
+            app.state.get_total_pool(market_id()).blocking_wait(),
+            Amount::ZERO
+        );
+    }
+
+    #[test]
+    fn lock_market_after_cutoff() {
+        let mut app = create_app_with_market();
+        app.runtime
+            .set_system_time(secs(EVENT_TIME_SECS - CUTOFF_SECS));
+
+        app.execute_operation(Operation::LockMarket {
+            market: MarketRef::MarketId(market_id()),
+        })
+        .blocking_wait();
+
+        assert_eq!(
+            app.state.get_status(market_id()).blocking_wait(),
+            MarketStatus::Locked
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "has not closed yet")]
+    fn lock_market_before_cutoff() {
+        let mut app = create_app_with_market();
+        app.runtime.set_system_time(secs(0));
+
+        app.execute_operation(Operation::LockMarket {
+            market: MarketRef::MarketId(market_id()),
+        })
+        .blocking_wait();
+    }
+
+    #[test]
+    fn cancel_market_refunds_every_bet() {
+        let mut app = create_app_with_market();
+        let transfers = mock_token_transfers(&mut app);
+
+        for (bet_id, outcome, tokens) in [(0, Outcome::Home, 10), (1, Outcome::Away, 25)] {
+            place_bet(&mut app, bet(bet_id, outcome, tokens));
+        }
+
+        app.runtime.set_authenticated_signer(Some(settler()));
+        app.execute_operation(Operation::CancelMarket {
+            market: MarketRef::MarketId(market_id()),
+        })
+        .blocking_wait();
+        claim_all(&mut app);
+
+        assert_eq!(
+            app.state.get_status(market_id()).blocking_wait(),
+            MarketStatus::Cancelled
+        );
+
+        let mut refunded = transfers
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|operation| match operation {
+                flashbet_token::Operation::TransferFromEscrow { to, amount } => {
+                    (to.chain_id, *amount)
+                }
+                _ => panic!("Unexpected BET token operation"),
+            })
+            .collect::<Vec<_>>();
+        refunded.sort_by_key(|(_, amount)| *amount);
+        assert_eq!(
+            refunded,
+            vec![
+                (user_chain(), Amount::from_tokens(10)),
+                (user_chain(), Amount::from_tokens(25)),
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot be cancelled")]
+    fn cancel_market_twice() {
+        let mut app = create_app_with_market();
+
+        for _ in 0..2 {
+            app.execute_operation(Operation::CancelMarket {
+                market: MarketRef::MarketId(market_id()),
+            })
+            .blocking_wait();
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Missing role Settler")]
+    fn cancel_market_requires_settler() {
+        let mut app = create_app_with_market();
+
+        app.runtime.set_authenticated_signer(Some(oracle_key()));
+        app.execute_operation(Operation::CancelMarket {
+            market: MarketRef::MarketId(market_id()),
+        })
+        .blocking_wait();
+    }
+
+    #[test]
+    fn owner_grants_and_revokes_roles() {
+        let mut app = create_app_with_market();
+        let creator = AccountOwner::from([2; 32]);
+
+        app.execute_operation(Operation::GrantRole {
+            role: MarketRole::MarketCreator,
+            account: creator,
+        })
+        .blocking_wait();
+        assert!(app
+            .state
+            .has_role(MarketRole::MarketCreator, &creator)
+            .blocking_wait());
+        assert!(!app
+            .state
+            .has_role(MarketRole::Pauser, &creator)
+            .blocking_wait());
+
+        app.runtime.set_authenticated_signer(Some(creator));
+        app.execute_operation(Operation::CreateMarket {
+            input: Box::new(CreateMarketInput {
+                market_type: MarketTypeInput::OverUnder,
+                line: Some(850),
+                ..market_input()
+            }),
+        })
+        .blocking_wait();
+        assert!(app.state.market_exists(MarketId(1)).blocking_wait());
+
+        app.runtime.set_authenticated_signer(Some(owner()));
+        app.execute_operation(Operation::RevokeRole {
+            role: MarketRole::MarketCreator,
+            account: creator,
+        })
+        .blocking_wait();
+        assert!(!app
+            .state
+            .has_role(MarketRole::MarketCreator, &creator)
+            .blocking_wait());
+    }
+
+    #[test]
+    #[should_panic(expected = "Missing role MarketCreator")]
+    fn create_market_requires_creator() {
+        let mut app = create_app_with_market();
+
+        app.runtime.set_authenticated_signer(Some(settler()));
+        app.execute_operation(Operation::CreateMarket {
+            input: Box::new(market_input()),
+        })
+        .blocking_wait();
+    }
+
+    #[test]
+    #[should_panic(expected = "Only owner can manage roles")]
+    fn only_owner_grants_roles() {
+        let mut app = create_app_with_market();
+
+        app.runtime.set_authenticated_signer(Some(settler()));
+        app.execute_operation(Operation::GrantRole {
+            role: MarketRole::MarketCreator,
+            account: settler(),
+        })
+        .blocking_wait();
+    }
+
+    #[test]
+    fn paused_chain_rejects_bets() {
+        let mut app = create_app_with_market();
+        let transfers = mock_token_transfers(&mut app);
+
+        app.execute_operation(Operation::PauseChain).blocking_wait();
+        let response = place_bet(&mut app, bet(0, Outcome::Home, 10));
+
+        assert_eq!(app.state.get_bet_count(market_id()).blocking_wait(), 0);
+        assert_stake_returned(
+            response,
+            &transfers,
+            BetRejection::MarketNotOpen(MarketStatus::Suspended),
+        );
+    }
+
+    #[test]
+    fn paused_chain_settles_held_result_on_resume() {
+        let mut app = create_app_with_market();
+        mock_token_transfers(&mut app);
+        place_bet(&mut app, bet(0, Outcome::Home, 10));
+
+        app.runtime.set_authenticated_signer(Some(owner()));
+        app.execute_operation(Operation::PauseChain).blocking_wait();
+        process_oracle_result(&mut app, Outcome::Home);
+        assert_eq!(
+            app.state.get_status(market_id()).blocking_wait(),
+            MarketStatus::Open
+        );
+
+        app.runtime.set_authenticated_signer(Some(owner()));
+        app.execute_operation(Operation::ResumeChain)
+            .blocking_wait();
+        assert_eq!(
+            app.state.get_status(market_id()).blocking_wait(),
+            MarketStatus::Resolved(Outcome::Home)
+        );
+    }
+
+    #[test]
+    fn suspended_market_settles_held_result_on_resume() {
+        let mut app = create_app_with_market();
+        mock_token_transfers(&mut app);
+        app.runtime.set_system_time(secs(EVENT_TIME_SECS));
+        app.execute_operation(Operation::LockMarket {
+            market: MarketRef::MarketId(market_id()),
+        })
+        .blocking_wait();
+
+        app.execute_operation(Operation::PauseMarket {
+            market: MarketRef::EventId(event_id()),
+        })
+        .blocking_wait();
+        assert_eq!(
+            app.state.get_status(market_id()).blocking_wait(),
+            MarketStatus::Suspended
+        );
+
+        // Settlement waits for the market to resume
+        process_oracle_result(&mut app, Outcome::Away);
+        assert_eq!(
+            app.state.get_status(market_id()).blocking_wait(),
+            MarketStatus::Suspended
+        );
+
+        app.runtime.set_authenticated_signer(Some(owner()));
+        app.execute_operation(Operation::ResumeMarket {
+            market: MarketRef::MarketId(market_id()),
+        })
+        .blocking_wait();
+        assert_eq!(
+            app.state.get_status(market_id()).blocking_wait(),
+            MarketStatus::Resolved(Outcome::Away)
+        );
+    }
+
+    #[test]
+    fn cancelling_suspended_market_discards_held_result() {
+        let mut app = create_app_with_market();
+        app.execute_operation(Operation::PauseMarket {
+            market: MarketRef::MarketId(market_id()),
+        })
+        .blocking_wait();
+        process_oracle_result(&mut app, Outcome::Home);
+
+        app.runtime.set_authenticated_signer(Some(owner()));
+        app.execute_operation(Operation::CancelMarket {
+            market: MarketRef::MarketId(market_id()),
+        })
+        .blocking_wait();
+
+        assert_eq!(
+            app.state.get_status(market_id()).blocking_wait(),
+            MarketStatus::Cancelled
+        );
+        assert!(app.state.get_deferred_markets().blocking_wait().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "Missing role Pauser")]
+    fn pause_requires_pauser() {
+        let mut app = create_app_with_market();
+
+        app.runtime.set_authenticated_signer(Some(settler()));
+        app.execute_operation(Operation::PauseChain).blocking_wait();
+    }
+
+    #[test]
+    fn no_winners_refund_minus_fee() {
+        let mut app = create_app_with(CreateMarketInput {
+            no_winner_fee_bps: Some(500),
+            ..market_input()
+        });
+        let transfers = mock_token_transfers(&mut app);
+
+        for (bet_id, tokens) in [(0, 10), (1, 30)] {
+            place_bet(&mut app, bet(bet_id, Outcome::Home, tokens));
+        }
+
+        process_oracle_result(&mut app, Outcome::Away);
+        claim_all(&mut app);
+
+        let transfers = transfers.lock().unwrap();
+        let mut refunds = transfers
+            .iter()
+            .filter_map(|operation| match operation {
+                flashbet_token::Operation::TransferFromEscrow { to, amount }
+                    if to.chain_id != market_chain() =>
+                {
+                    Some(*amount)
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        refunds.sort();
+        assert_eq!(
+            refunds,
+            vec![Amount::from_millis(9_500), Amount::from_millis(28_500)]
+        );
+        assert!(transfers.iter().any(|operation| matches!(
+            operation,
+            flashbet_token::Operation::TransferFromEscrow { to, amount }
+                if to.owner == treasury() && *amount == Amount::from_tokens(2)
+        )));
+    }
+
+    #[test]
+    fn no_winners_pool_to_treasury() {
+        let mut app = create_app_with(CreateMarketInput {
+            no_winner_policy: Some(NoWinnerPolicyInput::Treasury),
+            ..market_input()
+        });
+        let transfers = mock_token_transfers(&mut app);
+
+        place_bet(&mut app, bet(0, Outcome::Home, 10));
+
+        process_oracle_result(&mut app, Outcome::Draw);
+
+        assert!(matches!(
+            transfers.lock().unwrap().as_slice(),
+            [flashbet_token::Operation::TransferFromEscrow { to, amount }]
+                if to.owner == treasury() && *amount == Amount::from_tokens(10)
+        ));
+
+        // Nothing is left to claim
+        assert!(app
+            .state
+            .get_entitlements(market_id())
+            .blocking_wait()
+            .is_empty());
+    }
+
+    #[test]
+    fn same_bet_id_from_two_user_chains() {
+        let mut app = create_app_with_market();
+        let transfers = mock_token_transfers(&mut app);
+        let other_chain = ChainId(CryptoHash::test_hash("other_user_chain"));
+
+        for (bet_id, user_chain, outcome, millis) in [
+            (0, user_chain(), Outcome::Home, 10),
+            (0, other_chain, Outcome::Home, 30),
+            (1, user_chain(), Outcome::Away, 40),
+        ] {
+            let bet = Bet {
+                user_chain,
+                amount: Amount::from_millis(millis),
+                ..bet(bet_id, outcome, 0)
+            };
+            place_bet(&mut app, bet);
+        }
+
+        assert_eq!(app.state.get_bets(market_id()).blocking_wait().len(), 3);
+        assert_eq!(
+            app.state
+                .get_pool_for_outcome(market_id(), &Outcome::Home)
+                .blocking_wait(),
+            Amount::from_millis(40)
+        );
+
+        process_oracle_result(&mut app, Outcome::Home);
+        claim_all(&mut app);
+
+        let mut paid = transfers
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|operation| match operation {
+                flashbet_token::Operation::TransferFromEscrow { to, amount } => {
+                    (to.chain_id, *amount)
+                }
+                _ => panic!("Unexpected BET token operation"),
+            })
+            .collect::<Vec<_>>();
+        paid.sort_by_key(|(_, amount)| *amount);
+        assert_eq!(
+            paid,
+            vec![
+                (user_chain(), Amount::from_millis(20)),
+                (other_chain, Amount::from_millis(60)),
+            ]
+        );
+    }
+
+    #[test]
+    fn replayed_bet_message_refunded() {
+        let mut app = create_app_with_market();
+        let transfers = mock_token_transfers(&mut app);
+
+        place_bet(&mut app, bet(0, Outcome::Home, 10));
+        assert!(transfers.lock().unwrap().is_empty());
+        let response = place_bet(&mut app, bet(0, Outcome::Home, 10));
+
+        assert_eq!(app.state.get_bet_count(market_id()).blocking_wait(), 1);
+        assert_eq!(
+            app.state.get_total_pool(market_id()).blocking_wait(),
+            Amount::from_tokens(10)
+        );
+        // The replay's stake goes back to the bettor
+        assert_stake_returned(response, &transfers, BetRejection::DuplicateBet);
+    }
+
+    #[test]
+    #[should_panic(expected = "Bets must be registered by the User application")]
+    fn bet_from_other_application_rejected() {
+        let mut app = create_app_with_market();
+
+        app.runtime
+            .set_authenticated_caller_id(ApplicationId::new(CryptoHash::test_hash("other_app")));
+        app.runtime
+            .set_authenticated_signer(Some(AccountOwner::from([1; 32])));
+        app.execute_operation(Operation::RegisterBet {
+            bet: bet(0, Outcome::Home, 10),
+            min_payout_bps: None,
+        })
+        .blocking_wait();
+    }
+
+    #[test]
+    #[should_panic(expected = "Bet must be signed by its bettor")]
+    fn bet_signed_by_someone_else_rejected() {
+        let mut app = create_app_with_market();
+
+        app.runtime.set_authenticated_caller_id(user_app_id());
+        app.runtime.set_authenticated_signer(Some(owner()));
+        app.execute_operation(Operation::RegisterBet {
+            bet: bet(0, Outcome::Home, 10),
+            min_payout_bps: None,
+        })
+        .blocking_wait();
+    }
+
+    #[test]
+    fn oracle_stream_resolves_market_once() {
+        let mut app = create_app_with_market();
+        place_bet(&mut app, bet(0, Outcome::Home, 10));
+        place_bet(&mut app, bet(1, Outcome::Away, 10));
+
+        let reads = Arc::new(Mutex::new(Vec::new()));
+        let recorded = reads.clone();
+        app.runtime.set_call_application_handler(
+            move |_authenticated, application_id, operation| {
+                if application_id != oracle_app_id() {
+                    return linera_sdk::bcs::to_bytes(&FungibleResponse::Ok).unwrap();
+                }
+                let operation: flashbet_oracle::Operation = linera_sdk::bcs::from_bytes(&operation)
+                    .expect("Failed to deserialize Oracle operation");
+                let flashbet_oracle::Operation::ReadResultEvent { index, .. } = operation else {
+                    panic!("Unexpected Oracle operation");
+                };
+                recorded.lock().unwrap().push(index);
+                linera_sdk::bcs::to_bytes(&flashbet_oracle::OperationResponse::ResultEvent(
+                    OracleEvent::ResultPublished {
+                        result: result(Outcome::Home),
+                    },
+                ))
+                .unwrap()
+            },
+        );
+
+        let update = |previous_index, next_index, stream_name: &[u8]| StreamUpdate {
+            chain_id: oracle_chain(),
+            stream_id: StreamId {
+                application_id: GenericApplicationId::User(oracle_app_id()),
+                stream_name: StreamName::from(stream_name.to_vec()),
+            },
+            previous_index,
+            next_index,
+        };
+
+        // Other Oracle streams are not settlement input
+        app.process_streams(vec![update(0, 1, b"oracle_events")])
+            .blocking_wait();
+        assert!(reads.lock().unwrap().is_empty());
+
+        app.process_streams(vec![update(0, 1, b"oracle_results")])
+            .blocking_wait();
+        assert_eq!(
+            app.state.get_status(market_id()).blocking_wait(),
+            MarketStatus::Resolved(Outcome::Home)
+        );
+
+        // An overlapping update only reads the new event
+        app.process_streams(vec![update(0, 2, b"oracle_results")])
+            .blocking_wait();
+        assert_eq!(*reads.lock().unwrap(), vec![0, 1]);
+        claim_all(&mut app);
+        let entitlements = app.state.get_entitlements(market_id()).blocking_wait();
+        assert!(matches!(entitlements.as_slice(), [entitlement]
+            if entitlement.bet.bet_id == 0 && entitlement.claimed));
+    }
+
+    #[test]
+    fn user_reads_its_own_notices() {
+        let mut app = instantiate_app();
+        let notice = UserNotice::Payout(Payout {
+            market_id: market_id(),
+            bet_id: 0,
+            amount: Amount::from_tokens(20),
+            timestamp: Timestamp::from(0),
+        });
+        let event = linera_sdk::bcs::to_bytes(&MarketEvent::UserNotice(notice.clone())).unwrap();
+        app.runtime.add_event(
+            market_chain(),
+            flashbet_shared::user_notices_stream(user_chain()),
+            0,
+            &event,
+        );
+
+        // Called by the User application on the bettor's chain
+        app.runtime.set_chain_id(user_chain());
+        let response = app
+            .execute_operation(Operation::ReadNotice {
+                chain_id: market_chain(),
+                index: 0,
+            })
+            .blocking_wait();
+
+        assert!(matches!(response, OperationResponse::Notice(read) if read == notice));
+    }
+
+    #[test]
+    #[should_panic(expected = "Unauthorized oracle")]
+    fn oracle_result_from_unknown_key() {
+        let mut app = create_app_with_market();
+        app.runtime
+            .set_authenticated_signer(Some(AccountOwner::from([2; 32])));
+
+        app.execute_operation(Operation::ProcessOracleResult {
+            result: result(Outcome::Home),
+        })
+        .blocking_wait();
+    }
+
+    #[test]
+    fn over_under_settles_from_score() {
+        let mut app = create_app_with(CreateMarketInput {
+            market_type: MarketTypeInput::OverUnder,
+            line: Some(850),
+            ..market_input()
+        });
+        mock_token_transfers(&mut app);
+        place_bet(&mut app, bet(0, Outcome::Home, 10));
+        place_bet(&mut app, bet(1, Outcome::Away, 10));
+
+        // The total (9) beats the 8.5 line, whatever outcome the oracle reported
+        app.runtime.set_authenticated_signer(Some(oracle_key()));
+        app.execute_operation(Operation::ProcessOracleResult {
+            result: EventResult {
+                score: Some(Score { home: 5, away: 4 }),
+                ..result(Outcome::Away)
+            },
+        })
+        .blocking_wait();
+
+        assert_eq!(
+            app.state.get_status(market_id()).blocking_wait(),
+            MarketStatus::Resolved(Outcome::Home)
+        );
+    }
+
+    #[test]
+    fn markets_on_one_event_get_their_own_ids() {
+        let mut app = create_app_with_market();
+        app.execute_operation(Operation::CreateMarket {
+            input: Box::new(CreateMarketInput {
+                market_type: MarketTypeInput::OverUnder,
+                line: Some(850),
+                ..market_input()
+            }),
+        })
+        .blocking_wait();
+        mock_token_transfers(&mut app);
+
+        assert_eq!(
+            app.state.get_event_markets(&event_id()).blocking_wait(),
+            vec![MarketId(0), MarketId(1)]
+        );
+
+        place_bet(&mut app, bet(0, Outcome::Away, 10));
+        place_bet(
+            &mut app,
+            Bet {
+                market_id: MarketId(1),
+                ..bet(1, Outcome::Home, 10)
+            },
+        );
+        assert_eq!(app.state.get_bets(MarketId(0)).blocking_wait().len(), 1);
+        assert_eq!(app.state.get_bets(MarketId(1)).blocking_wait().len(), 1);
+
+        // One oracle result settles every market on the event
+        app.runtime.set_authenticated_signer(Some(oracle_key()));
+        app.execute_operation(Operation::ProcessOracleResult {
+            result: EventResult {
+                score: Some(Score { home: 5, away: 4 }),
+                ..result(Outcome::Away)
+            },
+        })
+        .blocking_wait();
+
+        assert_eq!(
+            app.state.get_status(MarketId(0)).blocking_wait(),
+            MarketStatus::Resolved(Outcome::Away)
+        );
+        assert_eq!(
+            app.state.get_status(MarketId(1)).blocking_wait(),
+            MarketStatus::Resolved(Outcome::Home)
+        );
+    }
+
+    #[test]
+    fn event_id_refers_to_every_market_on_the_event() {
+        let mut app = create_app_with_second_market();
+
+        app.execute_operation(Operation::CancelMarket {
+            market: MarketRef::EventId(event_id()),
+        })
+        .blocking_wait();
+
+        for market_id in [MarketId(0), MarketId(1)] {
+            assert_eq!(
+                app.state.get_status(market_id).blocking_wait(),
+                MarketStatus::Cancelled
+            );
+        }
+    }
+
+    #[test]
+    fn lock_by_event_skips_markets_already_locked() {
+        let mut app = create_app_with_second_market();
+        app.runtime
+            .set_system_time(secs(EVENT_TIME_SECS - CUTOFF_SECS));
+        app.execute_operation(Operation::LockMarket {
+            market: MarketRef::MarketId(MarketId(0)),
+        })
+        .blocking_wait();
+
+        app.execute_operation(Operation::LockMarket {
+            market: MarketRef::EventId(event_id()),
+        })
+        .blocking_wait();
+
+        assert_eq!(
+            app.state.get_status(MarketId(1)).blocking_wait(),
+            MarketStatus::Locked
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "No open market on event")]
+    fn lock_by_event_with_no_market_due() {
+        let mut app = create_app_with_second_market();
+        app.runtime.set_system_time(secs(0));
+
+        app.execute_operation(Operation::LockMarket {
+            market: MarketRef::EventId(event_id()),
+        })
+        .blocking_wait();
+    }
+
+    #[test]
+    fn cancel_by_event_skips_markets_already_cancelled() {
+        let mut app = create_app_with_second_market();
+        app.execute_operation(Operation::CancelMarket {
+            market: MarketRef::MarketId(MarketId(0)),
+        })
+        .blocking_wait();
+
+        app.execute_operation(Operation::CancelMarket {
+            market: MarketRef::EventId(event_id()),
+        })
+        .blocking_wait();
+
+        assert_eq!(
+            app.state.get_status(MarketId(1)).blocking_wait(),
+            MarketStatus::Cancelled
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "No market on event")]
+    fn cancel_by_event_with_no_market_left() {
+        let mut app = create_app_with_second_market();
+
+        for _ in 0..2 {
+            app.execute_operation(Operation::CancelMarket {
+                market: MarketRef::EventId(event_id()),
+            })
+            .blocking_wait();
+        }
+    }
+
+    #[test]
+    fn over_under_push_refunds_every_bet() {
+        let mut app = create_app_with(CreateMarketInput {
+            market_type: MarketTypeInput::OverUnder,
+            line: Some(800),
+            ..market_input()
+        });
+        let transfers = mock_token_transfers(&mut app);
+        place_bet(&mut app, bet(0, Outcome::Home, 10));
+        place_bet(&mut app, bet(1, Outcome::Away, 25));
+
+        app.runtime.set_authenticated_signer(Some(oracle_key()));
+        app.execute_operation(Operation::ProcessOracleResult {
+            result: EventResult {
+                score: Some(Score { home: 5, away: 3 }),
+                ..result(Outcome::Home)
+            },
+        })
+        .blocking_wait();
+        claim_all(&mut app);
+
+        assert_eq!(
+            app.state.get_status(market_id()).blocking_wait(),
+            MarketStatus::Push
+        );
+        let mut refunded = transfers
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|operation| match operation {
+                flashbet_token::Operation::TransferFromEscrow { amount, .. } => *amount,
+                _ => panic!("Unexpected BET token operation"),
+            })
+            .collect::<Vec<_>>();
+        refunded.sort();
+        assert_eq!(
+            refunded,
+            vec![Amount::from_tokens(10), Amount::from_tokens(25)]
+        );
+    }
+
+    #[test]
+    fn spread_quarter_line_half_win() {
+        let mut app = create_app_with(CreateMarketInput {
+            market_type: MarketTypeInput::Spread,
+            line: Some(-25),
+            ..market_input()
+        });
+        let transfers = mock_token_transfers(&mut app);
+        for (bet_id, outcome, millis) in [(0, Outcome::Home, 10), (1, Outcome::Away, 30)] {
+            place_bet(
+                &mut app,
+                Bet {
+                    amount: Amount::from_millis(millis),
+                    ..bet(bet_id, outcome, 0)
+                },
+            );
+        }
+
+        // A draw against -0.25: the 0 half pushes, the -0.5 half goes to Away
+        app.runtime.set_authenticated_signer(Some(oracle_key()));
+        app.execute_operation(Operation::ProcessOracleResult {
+            result: EventResult {
+                score: Some(Score { home: 1, away: 1 }),
+                ..result(Outcome::Draw)
+            },
+        })
+        .blocking_wait();
+        claim_all(&mut app);
+
+        assert_eq!(
+            app.state.get_status(market_id()).blocking_wait(),
+            MarketStatus::Resolved(Outcome::Away)
+        );
+        let mut paid = transfers
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|operation| match operation {
+                flashbet_token::Operation::TransferFromEscrow { amount, .. } => *amount,
+                _ => panic!("Unexpected BET token operation"),
+            })
+            .collect::<Vec<_>>();
+        paid.sort();
+        // Home gets half its stake back; Away gets its refunded half plus the whole in-play half
+        assert_eq!(paid, vec![Amount::from_millis(5), Amount::from_millis(35)]);
+    }
+
+    #[test]
+    fn payouts_are_claimed_once() {
+        let mut app = create_app_with_market();
+        let transfers = mock_token_transfers(&mut app);
+        place_bet(&mut app, bet(0, Outcome::Home, 10));
+        place_bet(&mut app, bet(1, Outcome::Away, 10));
+
+        process_oracle_result(&mut app, Outcome::Home);
+
+        // Resolution only records what each bet is owed
+        assert!(transfers.lock().unwrap().is_empty());
+        let winner = bet(0, Outcome::Home, 10).key();
+        let entitlement = app
+            .state
+            .get_entitlement(market_id(), &winner)
+            .blocking_wait()
+            .expect("Winner should be owed a payout");
+        assert_eq!(entitlement.amount, Amount::from_tokens(20));
+        assert_eq!(entitlement.kind, EntitlementKind::Payout);
+        assert!(!entitlement.claimed);
+
+        // Claiming again, or claiming a losing bet, pays nothing more
+        for key in [winner, winner, bet(1, Outcome::Away, 10).key()] {
+            app.execute_operation(Operation::ClaimPayout {
+                market: MarketRef::MarketId(market_id()),
+                bet: key,
+            })
+            .blocking_wait();
+        }
+
+        assert!(matches!(
+            transfers.lock().unwrap().as_slice(),
+            [flashbet_token::Operation::TransferFromEscrow { amount, .. }]
+                if *amount == Amount::from_tokens(20)
+        ));
+        assert!(app
+            .state
+            .get_entitlement(market_id(), &winner)
+            .blocking_wait()
+            .is_some_and(|entitlement| entitlement.claimed));
+    }
+
+    #[test]
+    fn settle_next_pays_in_chunks() {
+        let mut app = create_app_with_market();
+        let transfers = mock_token_transfers(&mut app);
+        for bet_id in 0..3 {
+            place_bet(&mut app, bet(bet_id, Outcome::Home, 10));
+        }
+        place_bet(&mut app, bet(3, Outcome::Away, 30));
+
+        // Nothing to settle before the result
+        let settle_next = |app: &mut FlashbetMarketContract| {
+            app.execute_operation(Operation::SettleNext {
+                market: MarketRef::MarketId(market_id()),
+                max_bets: 2,
+            })
+            .blocking_wait();
+        };
+        settle_next(&mut app);
+        assert!(transfers.lock().unwrap().is_empty());
+
+        process_oracle_result(&mut app, Outcome::Home);
+
+        settle_next(&mut app);
+        assert_eq!(transfers.lock().unwrap().len(), 2);
+        assert_eq!(
+            app.state
+                .get_settlement_progress(market_id())
+                .blocking_wait(),
+            SettlementProgress {
+                total: 3,
+                settled: 2
+            }
+        );
+
+        // A bettor claiming in between is not paid twice
+        app.execute_operation(Operation::ClaimPayout {
+            market: MarketRef::MarketId(market_id()),
+            bet: bet(2, Outcome::Home, 10).key(),
+        })
+        .blocking_wait();
+        settle_next(&mut app);
+        settle_next(&mut app);
+
+        let paid = transfers
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|operation| match operation {
+                flashbet_token::Operation::TransferFromEscrow { amount, .. } => *amount,
+                _ => panic!("Unexpected BET token operation"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(paid, vec![Amount::from_tokens(20); 3]);
+        assert!(app
+            .state
+            .get_settlement_progress(market_id())
+            .blocking_wait()
+            .is_complete());
+    }
+
+    #[test]
+    fn huge_pool_pays_out_exactly() {
+        let mut app = create_app_with(market_input());
+        let transfers = mock_token_transfers(&mut app);
+        for bet_id in 0..3 {
+            place_bet(&mut app, bet(bet_id, Outcome::Home, 1_000_000_000));
+        }
+        place_bet(
+            &mut app,
+            Bet {
+                amount: Amount::from_attos(1),
+                ..bet(3, Outcome::Away, 0)
+            },
+        );
+
+        process_oracle_result(&mut app, Outcome::Home);
+        claim_all(&mut app);
+
+        // Stake times pool overflows u128, and the odd atto goes to the first winner
+        let stake = Amount::from_tokens(1_000_000_000);
+        let paid = transfers
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|operation| match operation {
+                flashbet_token::Operation::TransferFromEscrow { amount, .. } => *amount,
+                _ => panic!("Unexpected BET token operation"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paid,
+            vec![stake.saturating_add(Amount::from_attos(1)), stake, stake]
+        );
+    }
+
+    #[test]
+    fn rake_goes_to_treasury_before_payouts() {
+        let mut app = create_app_with(CreateMarketInput {
+            rake_bps: Some(1_000),
+            ..market_input()
+        });
+        let transfers = mock_token_transfers(&mut app);
+        for (bet_id, outcome, millis) in [(0, Outcome::Home, 10), (1, Outcome::Away, 30)] {
+            place_bet(
+                &mut app,
+                Bet {
+                    amount: Amount::from_millis(millis),
+                    ..bet(bet_id, outcome, 0)
+                },
+            );
+        }
+
+        process_oracle_result(&mut app, Outcome::Home);
+        claim_all(&mut app);
+
+        // 10% of the 30 losing millis goes to the treasury, the winner takes the rest
+        assert!(matches!(
+            transfers.lock().unwrap().as_slice(),
+            [
+                flashbet_token::Operation::TransferFromEscrow { to, amount: rake },
+                flashbet_token::Operation::TransferFromEscrow { amount: payout, .. },
+            ] if to.owner == treasury()
+                && *rake == Amount::from_millis(3)
+                && *payout == Amount::from_millis(37)
+        ));
+    }
+
+    #[test]
+    fn rake_on_total_pool() {
+        let mut app = create_app_with(CreateMarketInput {
+            rake_bps: Some(1_000),
+            rake_base: Some(RakeBase::TotalPool),
+            ..market_input()
+        });
+        let transfers = mock_token_transfers(&mut app);
+        for (bet_id, outcome, millis) in [(0, Outcome::Home, 10), (1, Outcome::Away, 30)] {
+            place_bet(
+                &mut app,
+                Bet {
+                    amount: Amount::from_millis(millis),
+                    ..bet(bet_id, outcome, 0)
+                },
+            );
+        }
+
+        process_oracle_result(&mut app, Outcome::Home);
+        claim_all(&mut app);
+
+        assert!(matches!(
+            transfers.lock().unwrap().as_slice(),
+            [
+                flashbet_token::Operation::TransferFromEscrow { amount: rake, .. },
+                flashbet_token::Operation::TransferFromEscrow { amount: payout, .. },
+            ] if *rake == Amount::from_millis(4) && *payout == Amount::from_millis(36)
+        ));
+    }
+
+    #[test]
+    fn multi_outcome_market_pays_named_outcome() {
+        let mut app = create_app_with(multi_outcome_input());
+        let transfers = mock_token_transfers(&mut app);
+        for (bet_id, outcome, millis) in [
+            (0, Outcome::Home, 10),
+            (1, Outcome::Other(3), 30),
+            (2, Outcome::Draw, 20),
+        ] {
+            place_bet(
+                &mut app,
+                Bet {
+                    amount: Amount::from_millis(millis),
+                    ..bet(bet_id, outcome, 0)
+                },
+            );
+        }
+        assert_eq!(
+            app.state
+                .get_pool_for_outcome(market_id(), &Outcome::Other(3))
+                .blocking_wait(),
+            Amount::from_millis(30)
+        );
+
+        process_oracle_result(&mut app, Outcome::Other(3));
+        claim_all(&mut app);
+
+        assert_eq!(
+            app.state.get_status(market_id()).blocking_wait(),
+            MarketStatus::Resolved(Outcome::Other(3))
+        );
+        // The only backer of "Dave" takes the whole pool
+        assert!(matches!(
+            transfers.lock().unwrap().as_slice(),
+            [flashbet_token::Operation::TransferFromEscrow { amount, .. }]
+                if *amount == Amount::from_millis(60)
+        ));
+    }
+
+    #[test]
+    fn multi_outcome_rejects_unknown_outcome() {
+        let mut app = create_app_with(multi_outcome_input());
+        let transfers = mock_token_transfers(&mut app);
+
+        let response = place_bet(&mut app, bet(0, Outcome::Other(4), 10));
+
+        assert_stake_returned(response, &transfers, BetRejection::InvalidOutcome);
+        assert_eq!(app.state.get_bet_count(market_id()).blocking_wait(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid market type")]
+    fn multi_outcome_rejects_duplicate_labels() {
+        create_app_with(CreateMarketInput {
+            outcomes: Some(vec!["Alice".to_string(), "Alice".to_string()]),
+            ..multi_outcome_input()
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid market type")]
+    fn over_under_rejects_quarter_line() {
+        create_app_with(CreateMarketInput {
+            market_type: MarketTypeInput::OverUnder,
+            line: Some(825),
+            ..market_input()
+        });
+    }
+
+    /// Checks that the only effects were returning the whole stake and telling the User
+    /// application why
+    fn assert_stake_returned(
+        response: OperationResponse,
+        transfers: &Mutex<Vec<flashbet_token::Operation>>,
+        expected_reason: BetRejection,
+    ) {
+        assert!(matches!(
+            transfers.lock().unwrap().as_slice(),
+            [flashbet_token::Operation::TransferFromEscrow { to, amount }]
+                if to.chain_id == user_chain() && *amount == Amount::from_tokens(10)
+        ));
+        assert!(
+            matches!(response, OperationResponse::BetRejected(reason) if reason == expected_reason)
+        );
+    }
+
+    /// Registers a bet the way the User application does: as its caller, signed by `bet.user`
+    fn place_bet(app: &mut FlashbetMarketContract, bet: Bet) -> OperationResponse {
+        register_bet(app, bet, None)
+    }
+
+    fn register_bet(
+        app: &mut FlashbetMarketContract,
+        bet: Bet,
+        min_payout_bps: Option<u32>,
+    ) -> OperationResponse {
+        app.runtime.set_authenticated_caller_id(user_app_id());
+        app.runtime.set_authenticated_signer(Some(bet.user));
+        app.execute_operation(Operation::RegisterBet {
+            bet,
+            min_payout_bps,
+        })
+        .blocking_wait()
+    }
+
+    /// Claims what every bet of the test market is owed, as a keeper would
+    fn claim_all(app: &mut FlashbetMarketContract) {
+        let bets = app
+            .state
+            .get_bets(market_id())
+            .blocking_wait()
+            .iter()
+            .map(Bet::key)
+            .collect();
+        app.execute_operation(Operation::ClaimBatch {
+            market: MarketRef::MarketId(market_id()),
+            bets,
+        })
+        .blocking_wait();
+    }
+
+    /// Submits a result signed by the market's oracle key
+    fn process_oracle_result(app: &mut FlashbetMarketContract, outcome: Outcome) {
+        app.runtime.set_authenticated_signer(Some(oracle_key()));
+        app.execute_operation(Operation::ProcessOracleResult {
+            result: result(outcome),
+        })
+        .blocking_wait();
+    }
+
+    fn result(outcome: Outcome) -> EventResult {
+        EventResult {
+            event_id: event_id(),
+            outcome,
+            score: None,
+            timestamp: Timestamp::from(0),
+        }
+    }
+
+    /// Records the BET token operations the market calls and answers them with `Ok`
+    ///
+    /// Like the BET token, it only accepts authenticated calls, and the market may only move
+    /// tokens into or out of its own escrow: a plain transfer would debit the signer instead.
+    fn mock_token_transfers(
+        app: &mut FlashbetMarketContract,
+    ) -> Arc<Mutex<Vec<flashbet_token::Operation>>> {
+        let transfers = Arc::new(Mutex::new(Vec::new()));
+        let recorded = transfers.clone();
+        app.runtime.set_call_application_handler(
+            move |authenticated, application_id, operation| {
+                assert_eq!(
+                    application_id,
+                    bet_token_id().forget_abi(),
+                    "Not the BET token"
+                );
+                assert!(authenticated, "BET token called without authentication");
+                let operation = linera_sdk::bcs::from_bytes(&operation)
+                    .expect("Failed to deserialize BET token operation");
+                assert!(
+                    matches!(
+                        operation,
+                        flashbet_token::Operation::TransferToEscrow { .. }
+                            | flashbet_token::Operation::TransferFromEscrow { .. }
+                    ),
+                    "Market moved tokens outside its escrow: {:?}",
+                    operation
+                );
+                recorded.lock().unwrap().push(operation);
+                linera_sdk::bcs::to_bytes(&FungibleResponse::Ok).unwrap()
+            },
+        );
+        transfers
+    }
+
+    fn secs(secs: u64) -> Timestamp {
+        Timestamp::from(secs * 1_000_000)
+    }
+
+    fn event_id() -> EventId {
+        EventId::new("mlb_game_001")
+    }
+
+    fn bet_token_id() -> ApplicationId {
+        ApplicationId::new(CryptoHash::test_hash("bet_token"))
+    }
+
+    fn market_chain() -> ChainId {
+        ChainId(CryptoHash::test_hash("market_chain"))
+    }
+
+    /// ID of the test market (the first market created on the chain)
+    fn market_id() -> MarketId {
+        MarketId(0)
+    }
+
+    fn user_chain() -> ChainId {
+        ChainId(CryptoHash::test_hash("user_chain"))
+    }
+
+    fn bet(bet_id: u64, outcome: Outcome, tokens: u128) -> Bet {
+        Bet {
+            bet_id,
+            market_id: market_id(),
+            event_id: event_id(),
+            user: AccountOwner::from([1; 32]),
+            outcome,
+            amount: Amount::from_tokens(tokens),
+            timestamp: Timestamp::from(0),
+            user_chain: user_chain(),
+        }
+    }
+
+    fn oracle_chain() -> ChainId {
+        ChainId(CryptoHash::test_hash("oracle_chain"))
+    }
+
+    fn oracle_app_id() -> ApplicationId {
+        ApplicationId::new(CryptoHash::test_hash("oracle_app"))
+    }
+
+    fn user_app_id() -> ApplicationId {
+        ApplicationId::new(CryptoHash::test_hash("user_app"))
+    }
+
+    fn oracle_key() -> AccountOwner {
+        AccountOwner::from([7; 32])
+    }
+
+    fn treasury() -> AccountOwner {
+        AccountOwner::from([9; 32])
+    }
+
+    fn owner() -> AccountOwner {
+        AccountOwner::from([5; 32])
+    }
+
+    fn settler() -> AccountOwner {
+        AccountOwner::from([6; 32])
+    }
+
+    fn trader(index: u8) -> AccountOwner {
+        AccountOwner::from([index; 32])
+    }
+
+    fn market_input() -> CreateMarketInput {
+        CreateMarketInput {
+            event_id: event_id().0,
+            description: "Yankees vs Red Sox".to_string(),
+            event_time: secs(EVENT_TIME_SECS),
+            market_type: MarketTypeInput::MatchWinner,
+            line: None,
+            outcomes: None,
+            home_team: "Yankees".to_string(),
+            away_team: "Red Sox".to_string(),
+            no_winner_policy: None,
+            no_winner_fee_bps: None,
+            rake_bps: None,
+            rake_base: None,
+            min_stake: None,
+            max_stake: None,
+            max_user_exposure: None,
+            max_liability: None,
+            pricing_mode: None,
+            liquidity: None,
+        }
+    }
+
+    fn multi_outcome_input() -> CreateMarketInput {
+        CreateMarketInput {
+            market_type: MarketTypeInput::MultiOutcome,
+            outcomes: Some(["Alice", "Bob", "Carol", "Dave"].map(String::from).to_vec()),
+            ..market_input()
+        }
+    }
+
+    /// Creates a fixed-odds test market, with `bankroll` tokens funded by the owner
+    fn create_fixed_odds_app(
+        bankroll: u128,
+    ) -> (
+        FlashbetMarketContract,
+        Arc<Mutex<Vec<flashbet_token::Operation>>>,
+    ) {
+        create_funded_app(
+            CreateMarketInput {
+                pricing_mode: Some(PricingMode::FixedOdds),
+                ..market_input()
+            },
+            bankroll,
+        )
+    }
+
+    /// Creates the test market once the owner has funded the bankroll with `bankroll` tokens
+    ///
+    /// The funding transfer is not kept among the recorded BET token operations.
+    fn create_funded_app(
+        input: CreateMarketInput,
+        bankroll: u128,
+    ) -> (
+        FlashbetMarketContract,
+        Arc<Mutex<Vec<flashbet_token::Operation>>>,
+    ) {
+        let mut app = instantiate_app();
+        let transfers = mock_token_transfers(&mut app);
+        app.execute_operation(Operation::FundBankroll {
+            amount: Amount::from_tokens(bankroll),
+        })
+        .blocking_wait();
+        app.execute_operation(Operation::CreateMarket {
+            input: Box::new(input),
+        })
+        .blocking_wait();
+        transfers.lock().unwrap().clear();
+        (app, transfers)
+    }
+
+    /// An LMSR market on the test event, with a liquidity of 50 tokens
+    fn lmsr_input() -> CreateMarketInput {
+        CreateMarketInput {
+            pricing_mode: Some(PricingMode::Lmsr),
+            liquidity: Some(Amount::from_tokens(50)),
+            ..market_input()
+        }
+    }
+
+    /// Buys or sells whole shares of the test market as `trader`, at any price
+    fn trade_shares(
+        app: &mut FlashbetMarketContract,
+        trader: AccountOwner,
+        outcome: Outcome,
+        shares: u128,
+        buy: bool,
+    ) {
+        app.runtime.set_authenticated_signer(Some(trader));
+        let shares = Amount::from_tokens(shares);
+        let operation = if buy {
+            Operation::BuyShares {
+                market_id: market_id(),
+                outcome,
+                shares,
+                max_cost: Amount::MAX,
+            }
+        } else {
+            Operation::SellShares {
+                market_id: market_id(),
+                outcome,
+                shares,
+                min_proceeds: Amount::ZERO,
+            }
+        };
+        app.execute_operation(operation).blocking_wait();
+    }
+
+    /// Places an order for whole Home shares of the test market as `trader`, priced in
+    /// thousandths of a BET
+    fn place_order(
+        app: &mut FlashbetMarketContract,
+        trader: AccountOwner,
+        side: OrderSide,
+        price_millis: u128,
+        shares: u128,
+    ) {
+        app.runtime.set_authenticated_signer(Some(trader));
+        app.execute_operation(Operation::PlaceOrder {
+            market_id: market_id(),
+            outcome: Outcome::Home,
+            side,
+            price: Amount::from_millis(price_millis),
+            shares: Amount::from_tokens(shares),
+        })
+        .blocking_wait();
+    }
+
+    /// Publishes odds as the owner, who holds the PriceSetter role
+    fn set_odds(app: &mut FlashbetMarketContract, odds: &[(Outcome, u32)]) {
+        app.runtime.set_authenticated_signer(Some(owner()));
+        app.execute_operation(Operation::SetOdds {
+            market_id: market_id(),
+            odds: odds
+                .iter()
+                .map(|&(outcome, odds_bps)| OutcomeOdds { outcome, odds_bps })
+                .collect(),
+        })
+        .blocking_wait();
+    }
+
+    fn create_app_with_market() -> FlashbetMarketContract {
+        create_app_with(market_input())
+    }
+
+    /// Creates the test market, then an over/under market on the same event
+    fn create_app_with_second_market() -> FlashbetMarketContract {
+        let mut app = create_app_with_market();
+        app.execute_operation(Operation::CreateMarket {
+            input: Box::new(CreateMarketInput {
+                market_type: MarketTypeInput::OverUnder,
+                line: Some(850),
+                ..market_input()
+            }),
+        })
+        .blocking_wait();
+        app
+    }
+
+    fn create_app_with(input: CreateMarketInput) -> FlashbetMarketContract {
+        let mut app = instantiate_app();
+        app.execute_operation(Operation::CreateMarket {
+            input: Box::new(input),
+        })
+        .blocking_wait();
+        app
+    }
+
+    /// Instantiates the application, leaving the owner as the authenticated signer
+    fn instantiate_app() -> FlashbetMarketContract {
+        let runtime = ContractRuntime::new()
+            .with_application_parameters(())
+            .with_chain_id(market_chain())
+            .with_authenticated_signer(owner())
+            .with_system_time(Timestamp::from(0));
+        let mut app = FlashbetMarketContract {
+            state: FlashbetMarketState::load(runtime.root_view_storage_context())
+                .blocking_wait()
+                .expect("Failed to read from mock key value store"),
+            runtime,
+        };
+
+        app.instantiate(InstantiationArgument {
+            oracle_chain: oracle_chain(),
+            oracle_app_id: oracle_app_id().to_string(),
+            bet_token_id: bet_token_id(),
+            betting_cutoff_secs: CUTOFF_SECS,
+            treasury: Some(treasury()),
+            oracle_keys: vec![oracle_key()],
+            rake_bps: 0,
+            rake_base: RakeBase::default(),
+            market_creators: vec![],
+            settlers: vec![settler()],
+            pausers: vec![],
+            price_setters: vec![],
+        })
+        .now_or_never()
+        .expect("Initialization of application state should not await anything");
+        app.execute_operation(Operation::SetUserApp {
+            user_app_id: user_app_id(),
+        })
+        .blocking_wait();
+
+        app
+    }
 }
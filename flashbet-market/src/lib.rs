@@ -3,10 +3,15 @@
 //! Handles bet collection, pool management, and payout distribution.
 
 use async_graphql::{InputObject, Request, Response, SimpleObject};
-use flashbet_shared::{Bet, EventId, EventResult, MarketTypeInput};
+use flashbet_shared::{
+    Bet, BetKey, BetRejection, MarketId, MarketRef, MarketRole, MarketTypeInput,
+    NoWinnerPolicyInput, OrderSide, Outcome, OutcomeOdds, PricingMode, RakeBase, UserNotice,
+};
 use linera_sdk::{
     graphql::GraphQLMutationRoot,
-    linera_base_types::{ChainId, ContractAbi, ServiceAbi, Timestamp},
+    linera_base_types::{
+        AccountOwner, Amount, ApplicationId, ChainId, ContractAbi, ServiceAbi, Timestamp,
+    },
 };
 use serde::{Deserialize, Serialize};
 
@@ -14,7 +19,7 @@ pub struct FlashbetMarketAbi;
 
 impl ContractAbi for FlashbetMarketAbi {
     type Operation = Operation;
-    type Response = ();
+    type Response = OperationResponse;
 }
 
 impl ServiceAbi for FlashbetMarketAbi {
@@ -22,6 +27,17 @@ impl ServiceAbi for FlashbetMarketAbi {
     type QueryResponse = Response;
 }
 
+/// Response types for Market operations
+#[derive(Debug, Serialize, Deserialize)]
+pub enum OperationResponse {
+    /// Bet turned down by RegisterBet, with its stake returned
+    BetRejected(BetRejection),
+    /// Notice read from a `user_notices` stream
+    Notice(UserNotice),
+    /// Generic success response
+    Ok,
+}
+
 /// Input for creating a new market
 #[derive(Debug, Clone, Deserialize, Serialize, InputObject)]
 pub struct CreateMarketInput {
@@ -31,34 +47,70 @@ pub struct CreateMarketInput {
     pub description: String,
     /// Scheduled event start time
     pub event_time: Timestamp,
-    /// Type of market (Wave 1: only MatchWinner)
+    /// Type of market
     pub market_type: MarketTypeInput,
+    /// Line in hundredths of a point, required for OverUnder and Spread (850 = 8.5, -25 = -0.25)
+    pub line: Option<i32>,
+    /// Outcome labels by index, required for MultiOutcome (e.g. ["Alice", "Bob", "Carol"])
+    pub outcomes: Option<Vec<String>>,
     /// Home team name
     pub home_team: String,
     /// Away team name
     pub away_team: String,
+    /// What happens to the pool if nobody backed the winning outcome (defaults to Refund)
+    pub no_winner_policy: Option<NoWinnerPolicyInput>,
+    /// Fee in basis points kept from each refund under the Refund policy (defaults to 0)
+    pub no_winner_fee_bps: Option<u16>,
+    /// House fee in basis points taken before paying winners (defaults to the chain's rake)
+    pub rake_bps: Option<u16>,
+    /// Part of the pool the rake is taken from (defaults to the chain's rake base)
+    pub rake_base: Option<RakeBase>,
+    /// Smallest stake accepted for a single bet (defaults to no minimum)
+    pub min_stake: Option<Amount>,
+    /// Largest stake accepted for a single bet (defaults to no maximum)
+    pub max_stake: Option<Amount>,
+    /// Most a single user may stake on the market in total (defaults to no limit)
+    pub max_user_exposure: Option<Amount>,
+    /// Cap on what the market may owe bettors (defaults to no cap): its total pool, or for
+    /// fixed odds the largest total payout any outcome would owe
+    pub max_liability: Option<Amount>,
+    /// How bets are priced (defaults to Parimutuel)
+    /// Fixed-odds and LMSR markets take no rake and ignore the no-winner policy: the house
+    /// keeps what it collects and pays winners from its bankroll
+    pub pricing_mode: Option<PricingMode>,
+    /// Liquidity parameter `b`, required for LMSR markets (the bankroll must cover the market
+    /// maker's worst loss, b·ln(number of outcomes))
+    pub liquidity: Option<Amount>,
 }
 
 /// Operations that can be performed on a Market Chain
 #[derive(Debug, Deserialize, Serialize, GraphQLMutationRoot)]
 pub enum Operation {
-    /// Create a new market (only callable by market creator/admin)
+    /// Create a new market (requires the MarketCreator role)
     CreateMarket {
         /// Market information (event, teams, etc.)
-        input: CreateMarketInput,
+        input: Box<CreateMarketInput>,
     },
 
-    /// Register a bet from a User chain
-    /// Wave 1: Called by frontend after user places bet
-    /// Wave 2+: Will be triggered automatically via cross-app events
+    /// Register a bet placed on a User chain
+    /// Called by the User application on this chain, which receives the bet from the bettor's
+    /// User chain along with its stake, forwarding the bettor's signature
+    /// Bets the market turns down have their stake returned, and the reason is the response
     RegisterBet {
-        /// The bet to register
-        bet: flashbet_shared::Bet,
+        bet: Bet,
+        /// Smallest payout multiplier the bettor accepts, in basis points (25_000 = 2.5x)
+        min_payout_bps: Option<u32>,
     },
 
+    /// Set the User application allowed to register bets (only the owner can call this)
+    SetUserApp { user_app_id: ApplicationId },
+
+    /// Read a notice from a Market chain's notice stream for this chain
+    /// Called by User applications, which can only read their own application's events
+    ReadNotice { chain_id: ChainId, index: u32 },
+
     /// Process an oracle result and resolve the market
-    /// Wave 1: Called by frontend after oracle publishes result
-    /// Wave 2+: Will be triggered automatically via Oracle event subscription
+    /// Manual fallback for the Oracle event subscription; must be signed by a known oracle key
     ProcessOracleResult {
         /// The event result from Oracle
         result: flashbet_shared::EventResult,
@@ -73,25 +125,147 @@ pub enum Operation {
         user_app_id: String,
     },
 
-    /// Manually lock the market (no more bets accepted)
-    LockMarket,
+    /// Lock a market whose betting window has closed (no more bets accepted)
+    /// Markets are also locked automatically when a late bet arrives,
+    /// so anyone may call this to update the status after the cutoff
+    LockMarket {
+        /// Market to lock (an event ID locks every open market on the event past its cutoff)
+        market: MarketRef,
+    },
 
-    /// Manually cancel the market (refund all bets)
-    CancelMarket,
-}
+    /// Cancel a market and refund every bet's stake
+    /// Called by a Settler (the admin or the Oracle Worker) when an event is postponed or abandoned
+    CancelMarket {
+        /// Market to cancel (an event ID cancels every market on the event not yet settled)
+        market: MarketRef,
+    },
 
-/// Messages sent/received by the Market Chain
-#[derive(Debug, Deserialize, Serialize)]
-pub enum Message {
-    /// Bet placement from a User Chain
-    PlaceBet { bet: Bet },
+    /// Pay out what a settled bet is owed
+    /// Anyone may claim (the bettor or a keeper): funds always go to the bettor,
+    /// and claiming an already paid bet does nothing
+    ClaimPayout {
+        /// The bet's market (an event ID searches every market on the event)
+        market: MarketRef,
+        /// The bet to pay out
+        bet: BetKey,
+    },
+
+    /// Pay out several settled bets of a market, skipping those already paid
+    ClaimBatch {
+        /// The bets' market (an event ID searches every market on the event)
+        market: MarketRef,
+        /// The bets to pay out
+        bets: Vec<BetKey>,
+    },
+
+    /// Push payouts of a settled market in chunks, resuming where the last call stopped
+    /// Bets already claimed are skipped; anyone may call this until settlement completes
+    SettleNext {
+        /// Market to settle (an event ID settles the markets on the event in turn)
+        market: MarketRef,
+        /// Maximum number of bets to process in this call
+        max_bets: u32,
+    },
+
+    /// Grant a role to an account (only the owner can call this)
+    GrantRole {
+        role: MarketRole,
+        account: AccountOwner,
+    },
+
+    /// Revoke a role from an account (only the owner can call this)
+    RevokeRole {
+        role: MarketRole,
+        account: AccountOwner,
+    },
 
-    /// Oracle event result (received via event stream)
-    OracleResult(EventResult),
+    /// Halt betting and settlement on every market of the chain (requires the Pauser role)
+    /// Oracle results arriving meanwhile are kept and settled on resume
+    PauseChain,
+
+    /// Restart betting and settlement on the chain, settling results held while paused
+    /// (requires the Pauser role)
+    ResumeChain,
+
+    /// Suspend an open or locked market (requires the Pauser role)
+    PauseMarket {
+        /// Market to suspend (an event ID suspends every market on the event)
+        market: MarketRef,
+    },
+
+    /// Restore a suspended market to its previous status, settling any result held meanwhile
+    /// (requires the Pauser role)
+    ResumeMarket {
+        /// Market to resume (an event ID resumes every market on the event)
+        market: MarketRef,
+    },
+
+    /// Publish the odds of an open fixed-odds market (requires the PriceSetter role)
+    /// Outcomes left out keep their current odds; bets already placed keep the odds they got
+    SetOdds {
+        /// Fixed-odds market to price
+        market_id: MarketId,
+        /// Decimal odds per outcome, in basis points above 10_000 (25_000 = 2.5)
+        odds: Vec<OutcomeOdds>,
+    },
+
+    /// Add BET tokens from the signer's account to the bankroll backing fixed-odds markets
+    FundBankroll { amount: Amount },
+
+    /// Withdraw BET tokens from the bankroll not reserved for open bets
+    /// (only the owner can call this)
+    WithdrawBankroll {
+        /// Account on this chain receiving the tokens
+        to: AccountOwner,
+        amount: Amount,
+    },
+
+    /// Buy outcome shares of an open LMSR market from its market maker, paying from the
+    /// signer's account; each share pays 1 BET if its outcome wins
+    BuyShares {
+        market_id: MarketId,
+        outcome: Outcome,
+        /// Number of shares, as an amount (1 share pays 1 BET)
+        shares: Amount,
+        /// Most the signer is willing to pay, in case prices moved
+        max_cost: Amount,
+    },
+
+    /// Sell shares the signer holds back to an open LMSR market's market maker
+    SellShares {
+        market_id: MarketId,
+        outcome: Outcome,
+        /// Number of shares, as an amount (1 share pays 1 BET)
+        shares: Amount,
+        /// Least the signer is willing to receive, in case prices moved
+        min_proceeds: Amount,
+    },
+
+    /// Pay out the signer's shares of a settled LMSR market: 1 BET per winning share, or
+    /// each share's last price if the market was cancelled or pushed
+    /// Redeeming again, or without shares, does nothing
+    RedeemShares { market_id: MarketId },
+
+    /// Post a limit order on the shares of an outcome of an open LMSR market
+    /// Order books only exist on LMSR markets, whose outcome shares they trade: orders on
+    /// parimutuel or fixed-odds markets are rejected
+    /// A bid escrows `shares` × `price` BET from the signer's account and an ask the signer's
+    /// shares; the order fills against the other side of the book at the resting orders'
+    /// prices, best price first, and whatever is left rests on the book
+    PlaceOrder {
+        market_id: MarketId,
+        outcome: Outcome,
+        side: OrderSide,
+        /// Limit price of one share, in BET (between 0 and 1)
+        price: Amount,
+        /// Number of shares, as an amount (1 share pays 1 BET)
+        shares: Amount,
+    },
 
-    /// Payout notification sent to User Chain
-    /// Accompanies native token transfer for state tracking
-    Payout(flashbet_shared::Payout),
+    /// Cancel the rest of one of the signer's orders on an LMSR market's order book,
+    /// returning what it holds in escrow
+    /// Orders can still be cancelled once their market has closed
+    CancelOrder { order_id: u64 },
 }
 
 /// Instantiation argument for Market Chain
@@ -105,4 +279,31 @@ pub struct InstantiationArgument {
     /// BET token application ID for token operations
     /// Stored without type parameter for GraphQL compatibility
     pub bet_token_id: linera_sdk::linera_base_types::ApplicationId,
+    /// Seconds before `event_time` at which betting closes (0 = at event start)
+    #[serde(default)]
+    pub betting_cutoff_secs: u64,
+    /// Account on the Market chain that receives fees and unclaimed pools
+    #[serde(default)]
+    pub treasury: Option<linera_sdk::linera_base_types::AccountOwner>,
+    /// Oracle keys allowed to submit results with ProcessOracleResult
+    #[serde(default)]
+    pub oracle_keys: Vec<linera_sdk::linera_base_types::AccountOwner>,
+    /// Default house fee in basis points for new markets (0 = no rake)
+    #[serde(default)]
+    pub rake_bps: u16,
+    /// Default part of the pool the rake is taken from
+    #[serde(default)]
+    pub rake_base: RakeBase,
+    /// Accounts allowed to create markets, besides the owner
+    #[serde(default)]
+    pub market_creators: Vec<AccountOwner>,
+    /// Accounts allowed to cancel markets, besides the owner
+    #[serde(default)]
+    pub settlers: Vec<AccountOwner>,
+    /// Accounts allowed to pause and resume the chain, besides the owner
+    #[serde(default)]
+    pub pausers: Vec<AccountOwner>,
+    /// Accounts allowed to publish fixed odds, besides the owner
+    #[serde(default)]
+    pub price_setters: Vec<AccountOwner>,
 }
@@ -4,12 +4,15 @@ mod state;
 
 use std::sync::Arc;
 
-use async_graphql::{EmptySubscription, Object, Schema};
+use async_graphql::{EmptySubscription, Object, Schema, SimpleObject};
 use flashbet_market::Operation;
-use flashbet_shared::{Bet, EventId, MarketInfo, MarketStatus, MarketType, Outcome};
+use flashbet_shared::{
+    Bet, Entitlement, EventId, MarketId, MarketInfo, MarketRole, MarketStatus, MarketType, Order,
+    OrderSide, Outcome, PricingMode, SettlementProgress, StakeLimits, Trade,
+};
 use linera_sdk::{
     graphql::GraphQLMutationRoot,
-    linera_base_types::{Amount, Timestamp, WithServiceAbi},
+    linera_base_types::{AccountOwner, Amount, TimeDelta, Timestamp, WithServiceAbi},
     views::View,
     Service, ServiceRuntime,
 };
@@ -17,7 +20,7 @@ use linera_sdk::{
 use self::state::FlashbetMarketState;
 
 pub struct FlashbetMarketService {
-    state: FlashbetMarketState,
+    state: Arc<FlashbetMarketState>,
     runtime: Arc<ServiceRuntime<Self>>,
 }
 
@@ -35,39 +38,40 @@ impl Service for FlashbetMarketService {
             .await
             .expect("Failed to load state");
         FlashbetMarketService {
-            state,
+            state: Arc::new(state),
             runtime: Arc::new(runtime),
         }
     }
 
     async fn handle_query(&self, query: Self::Query) -> Self::QueryResponse {
-        // Get all market IDs
-        let all_market_ids = self.get_all_market_ids().await;
-
-        // Pre-fetch data for all markets
-        let mut markets_data = std::collections::HashMap::new();
-        for event_id_str in &all_market_ids {
-            let event_id = EventId::new(event_id_str.clone());
-            let data = self.get_market_data(&event_id).await;
-            let bets = self.get_market_bets(&event_id).await;
-            markets_data.insert(event_id_str.clone(), (data, bets));
-        }
+        // Market data is read by the resolvers that need it, so a query only loads the
+        // markets it asks about
+
+        // Get the BET this application holds in escrow with the BET token application
+        let escrow_balance = self.get_escrow_balance();
 
-        // Get latest market event ID for default queries
-        let latest_event_id = self.get_latest_market_event_id().await;
+        // Betting closes this long before each market's event time
+        let betting_cutoff = *self.state.betting_cutoff.get();
+        let now = self.runtime.system_time();
 
-        // Get Market chain's native token balance (escrow holding)
-        // This follows the native-fungible example pattern
-        let escrow_balance = self.runtime.owner_balance(
-            linera_sdk::linera_base_types::AccountOwner::CHAIN
-        );
+        // Admin roles
+        let owner = *self.state.owner.get();
+        let roles = self.state.get_roles().await;
+        let paused = *self.state.paused.get();
+
+        // House funds backing fixed-odds markets
+        let bankroll = *self.state.bankroll.get();
 
         Schema::build(
             QueryRoot {
-                all_market_ids,
-                markets_data,
-                latest_event_id,
                 escrow_balance,
+                betting_cutoff,
+                now,
+                owner,
+                roles,
+                paused,
+                bankroll,
+                state: self.state.clone(),
             },
             Operation::mutation_root(self.runtime.clone()),
             EmptySubscription,
@@ -79,288 +83,816 @@ impl Service for FlashbetMarketService {
 }
 
 impl FlashbetMarketService {
-    /// Get the latest market's event ID (most recently created)
-    async fn get_latest_market_event_id(&self) -> Option<EventId> {
-        let mut latest_id: Option<EventId> = None;
-        self.state
-            .markets
-            .for_each_index_value(|event_id, _info| {
-                latest_id = Some(event_id.to_owned());
-                Ok(())
-            })
-            .await
-            .ok()?;
-        latest_id
+    /// Get the BET held in escrow for this application by the BET token application
+    fn get_escrow_balance(&self) -> Amount {
+        let Some(bet_token_id) = *self.state.bet_token_id.get() else {
+            return Amount::ZERO;
+        };
+        let application = self.runtime.application_id().forget_abi();
+        let request = async_graphql::Request::new(format!(
+            "query {{ escrowBalance(application: \"{}\") }}",
+            application
+        ));
+        let response = self.runtime.query_application(bet_token_id, &request);
+        response
+            .data
+            .into_json()
+            .ok()
+            .and_then(|data| data["escrowBalance"].as_str()?.parse().ok())
+            .unwrap_or(Amount::ZERO)
     }
+}
 
-    /// Get all market event IDs
-    async fn get_all_market_ids(&self) -> Vec<String> {
-        let mut ids = Vec::new();
-        self.state
-            .markets
-            .for_each_index_value(|event_id, _info| {
-                ids.push(event_id.0.clone());
-                Ok(())
-            })
-            .await
-            .expect("Failed to iterate markets");
-        ids
+/// Parimutuel odds of a pool ((total - rake) / pool), 1.0 while nobody has backed it
+fn pool_odds(info: &MarketInfo, total_pool: Amount, pool: Amount) -> f64 {
+    if pool > Amount::ZERO {
+        let total: u128 = total_pool
+            .saturating_sub(info.rake.fee_on(total_pool, pool))
+            .into();
+        let pool: u128 = pool.into();
+        (total as f64) / (pool as f64)
+    } else {
+        1.0
     }
+}
 
-    /// Get market data for a specific event
-    async fn get_market_data(
-        &self,
-        event_id: &EventId,
-    ) -> (MarketInfo, MarketStatus, Amount, Amount, Amount, Amount, u64) {
-        let info = self.state.get_market(event_id).await.unwrap_or_else(|| MarketInfo {
-            event_id: event_id.clone(),
-            description: "Market not found".to_string(),
-            event_time: Timestamp::from(0),
-            market_type: MarketType::MatchWinner,
-            home_team: "N/A".to_string(),
-            away_team: "N/A".to_string(),
-        });
-
-        let status = self.state.get_status(event_id).await;
-        let total_pool = self.state.get_total_pool(event_id).await;
-        let home_pool = self.state.get_pool_for_outcome(event_id, &Outcome::Home).await;
-        let away_pool = self.state.get_pool_for_outcome(event_id, &Outcome::Away).await;
-        let draw_pool = self.state.get_pool_for_outcome(event_id, &Outcome::Draw).await;
-        let bet_count = self.state.get_bet_count(event_id).await;
-
-        (info, status, total_pool, home_pool, away_pool, draw_pool, bet_count)
-    }
+/// Pool and odds of one outcome of a market
+#[derive(Clone, SimpleObject)]
+struct OutcomePool {
+    /// Index of the outcome (pass as OUTCOME_<index> when betting, or HOME/AWAY/DRAW)
+    index: u32,
+    /// Display label of the outcome
+    label: String,
+    /// Amount staked on this outcome
+    pool: Amount,
+    /// Current odds: for parimutuel markets after the house rake ((total pool - rake) / outcome
+    /// pool), for fixed-odds markets the published price (1.0 until one is published), for
+    /// LMSR markets what a share pays per BET spent (1 / price)
+    odds: f64,
+    /// Published odds in basis points (fixed-odds markets only)
+    odds_bps: Option<u32>,
+    /// Total payout owed to this outcome's bets, or outstanding shares, if it wins
+    /// (fixed-odds and LMSR markets only)
+    liability: Amount,
+    /// Current price of one share in BET (LMSR markets only)
+    price: Option<Amount>,
+}
 
-    /// Get all bets for a specific market
-    async fn get_market_bets(&self, event_id: &EventId) -> Vec<Bet> {
-        let mut bets = Vec::new();
-        self.state
-            .bets
-            .for_each_index_value(|(bet_event_id, _bet_id), bet| {
-                if bet_event_id == *event_id {
-                    bets.push(bet.into_owned());
-                }
-                Ok(())
-            })
-            .await
-            .expect("Failed to iterate bets");
-        bets
-    }
+/// Most trades per market `recentTrades` can return
+const MAX_RECENT_TRADES: u64 = 100;
+
+/// Resting orders at one price on one side of an order book
+#[derive(Clone, SimpleObject)]
+struct PriceLevel {
+    /// Price of one share in BET
+    price: Amount,
+    /// Unfilled shares resting at this price
+    shares: Amount,
+    /// Number of orders resting at this price
+    orders: u32,
 }
 
-type MarketData = (MarketInfo, MarketStatus, Amount, Amount, Amount, Amount, u64);
+/// Both sides of the order book of an outcome, best price first
+#[derive(Clone, SimpleObject)]
+struct OrderBookDepth {
+    /// Buy orders, highest price first
+    bids: Vec<PriceLevel>,
+    /// Sell orders, lowest price first
+    asks: Vec<PriceLevel>,
+}
 
 struct QueryRoot {
-    all_market_ids: Vec<String>,
-    markets_data: std::collections::HashMap<String, (MarketData, Vec<Bet>)>,
-    latest_event_id: Option<EventId>,
     escrow_balance: Amount,
+    betting_cutoff: TimeDelta,
+    now: Timestamp,
+    owner: Option<AccountOwner>,
+    roles: Vec<(MarketRole, AccountOwner)>,
+    paused: bool,
+    bankroll: Amount,
+    state: Arc<FlashbetMarketState>,
 }
 
 #[Object]
 impl QueryRoot {
-    /// Get event ID (optionally specify eventId, defaults to latest market)
-    async fn event_id(&self, event_id: Option<String>) -> String {
-        self.resolve_event_id(event_id)
-    }
-
-    /// Get market description (optionally specify eventId, defaults to latest market)
-    async fn description(&self, event_id: Option<String>) -> String {
-        let target_id = self.resolve_event_id(event_id);
-        if let Some(((info, _, _, _, _, _, _), _)) = self.markets_data.get(&target_id) {
-            info.description.clone()
-        } else {
-            "Market not found".to_string()
+    /// Get event ID (optionally specify marketId or eventId, defaults to latest market)
+    async fn event_id(&self, event_id: Option<String>, market_id: Option<MarketId>) -> String {
+        match self.market(event_id, market_id).await {
+            Some(info) => info.event_id.0,
+            None => "none".to_string(),
+        }
+    }
+
+    /// Get market ID (optionally specify marketId or eventId, defaults to latest market)
+    /// An event with several markets resolves to its first market
+    async fn market_id(
+        &self,
+        event_id: Option<String>,
+        market_id: Option<MarketId>,
+    ) -> Option<MarketId> {
+        let target_id = self.resolve_market(event_id, market_id).await?;
+        self.state
+            .market_exists(target_id)
+            .await
+            .then_some(target_id)
+    }
+
+    /// Get IDs of every market on an event, in creation order
+    async fn event_markets(&self, event_id: String) -> Vec<MarketId> {
+        self.state.get_event_markets(&EventId(event_id)).await
+    }
+
+    /// Get market description (optionally specify marketId or eventId, defaults to latest market)
+    async fn description(&self, event_id: Option<String>, market_id: Option<MarketId>) -> String {
+        match self.market(event_id, market_id).await {
+            Some(info) => info.description,
+            None => "Market not found".to_string(),
+        }
+    }
+
+    /// Get home team name (optionally specify marketId or eventId, defaults to latest market)
+    async fn home_team(&self, event_id: Option<String>, market_id: Option<MarketId>) -> String {
+        match self.market(event_id, market_id).await {
+            Some(info) => info.home_team,
+            None => "N/A".to_string(),
         }
     }
 
-    /// Get home team name (optionally specify eventId, defaults to latest market)
-    async fn home_team(&self, event_id: Option<String>) -> String {
-        let target_id = self.resolve_event_id(event_id);
-        if let Some(((info, _, _, _, _, _, _), _)) = self.markets_data.get(&target_id) {
-            info.home_team.clone()
-        } else {
-            "N/A".to_string()
+    /// Get away team name (optionally specify marketId or eventId, defaults to latest market)
+    async fn away_team(&self, event_id: Option<String>, market_id: Option<MarketId>) -> String {
+        match self.market(event_id, market_id).await {
+            Some(info) => info.away_team,
+            None => "N/A".to_string(),
         }
     }
 
-    /// Get away team name (optionally specify eventId, defaults to latest market)
-    async fn away_team(&self, event_id: Option<String>) -> String {
-        let target_id = self.resolve_event_id(event_id);
-        if let Some(((info, _, _, _, _, _, _), _)) = self.markets_data.get(&target_id) {
-            info.away_team.clone()
-        } else {
-            "N/A".to_string()
+    /// Get event time (as microseconds since epoch, optionally specify marketId or eventId, defaults to latest market)
+    async fn event_time(&self, event_id: Option<String>, market_id: Option<MarketId>) -> u64 {
+        self.market(event_id, market_id)
+            .await
+            .map_or(0, |info| info.event_time.micros())
+    }
+
+    /// Get the time betting closes (as microseconds since epoch, optionally specify marketId or eventId, defaults to latest market)
+    async fn lock_time(&self, event_id: Option<String>, market_id: Option<MarketId>) -> u64 {
+        self.market(event_id, market_id).await.map_or(0, |info| {
+            info.event_time.saturating_sub(self.betting_cutoff).micros()
+        })
+    }
+
+    /// Get current market status (as string, optionally specify marketId or eventId, defaults to latest market)
+    /// Open and locked markets report Suspended while the chain is paused
+    async fn status(&self, event_id: Option<String>, market_id: Option<MarketId>) -> String {
+        match self.market_status(event_id, market_id).await {
+            Some((_, status)) => {
+                if self.paused && matches!(status, MarketStatus::Open | MarketStatus::Locked) {
+                    return format!("{:?}", MarketStatus::Suspended);
+                }
+                format!("{:?}", status)
+            }
+            None => "Open".to_string(),
         }
     }
 
-    /// Get event time (as microseconds since epoch, optionally specify eventId, defaults to latest market)
-    async fn event_time(&self, event_id: Option<String>) -> u64 {
-        let target_id = self.resolve_event_id(event_id);
-        if let Some(((info, _, _, _, _, _, _), _)) = self.markets_data.get(&target_id) {
-            info.event_time.micros()
-        } else {
-            0
+    /// Get what happens to the pool if nobody wins (as string, optionally specify marketId or eventId, defaults to latest market)
+    async fn no_winner_policy(
+        &self,
+        event_id: Option<String>,
+        market_id: Option<MarketId>,
+    ) -> String {
+        let policy = self
+            .market(event_id, market_id)
+            .await
+            .map(|info| info.no_winner_policy)
+            .unwrap_or_default();
+        format!("{:?}", policy)
+    }
+
+    /// Get how bets are priced (optionally specify marketId or eventId, defaults to latest market)
+    async fn pricing_mode(
+        &self,
+        event_id: Option<String>,
+        market_id: Option<MarketId>,
+    ) -> PricingMode {
+        self.market(event_id, market_id)
+            .await
+            .map(|info| info.pricing)
+            .unwrap_or_default()
+    }
+
+    /// Get house rake in basis points (optionally specify marketId or eventId, defaults to latest market)
+    async fn rake_bps(&self, event_id: Option<String>, market_id: Option<MarketId>) -> u16 {
+        self.market(event_id, market_id)
+            .await
+            .map_or(0, |info| info.rake.fee_bps)
+    }
+
+    /// Get market type (as string, optionally specify marketId or eventId, defaults to latest market)
+    async fn market_type(&self, event_id: Option<String>, market_id: Option<MarketId>) -> String {
+        let market_type = self
+            .market(event_id, market_id)
+            .await
+            .map_or(MarketType::MatchWinner, |info| info.market_type);
+        match market_type {
+            MarketType::MatchWinner => "MatchWinner".to_string(),
+            MarketType::OverUnder { .. } => "OverUnder".to_string(),
+            MarketType::Spread { .. } => "Spread".to_string(),
+            MarketType::MultiOutcome { .. } => "MultiOutcome".to_string(),
         }
     }
 
-    /// Get current market status (as string, optionally specify eventId, defaults to latest market)
-    async fn status(&self, event_id: Option<String>) -> String {
-        let target_id = self.resolve_event_id(event_id);
-        if let Some(((_, status, _, _, _, _, _), _)) = self.markets_data.get(&target_id) {
-            format!("{:?}", status)
-        } else {
-            "Open".to_string()
+    /// Get market line in points, e.g. 8.5 (optionally specify marketId or eventId, defaults to latest market)
+    async fn line(&self, event_id: Option<String>, market_id: Option<MarketId>) -> Option<f64> {
+        self.market(event_id, market_id)
+            .await?
+            .market_type
+            .line_points()
+    }
+
+    /// Check if market is resolved (optionally specify marketId or eventId, defaults to latest market)
+    async fn is_resolved(&self, event_id: Option<String>, market_id: Option<MarketId>) -> bool {
+        matches!(
+            self.market_status(event_id, market_id).await,
+            Some((_, MarketStatus::Resolved(_)))
+        )
+    }
+
+    /// Check if market is open for betting (optionally specify marketId or eventId, defaults to latest market)
+    /// A market past its betting cutoff is reported closed even before it is locked on-chain
+    async fn is_open(&self, event_id: Option<String>, market_id: Option<MarketId>) -> bool {
+        match self.market_status(event_id, market_id).await {
+            Some((info, status)) => {
+                matches!(status, MarketStatus::Open)
+                    && !self.paused
+                    && self.now < info.event_time.saturating_sub(self.betting_cutoff)
+            }
+            None => false,
         }
     }
 
-    /// Check if market is resolved (optionally specify eventId, defaults to latest market)
-    async fn is_resolved(&self, event_id: Option<String>) -> bool {
-        let target_id = self.resolve_event_id(event_id);
-        if let Some(((_, status, _, _, _, _, _), _)) = self.markets_data.get(&target_id) {
-            matches!(status, MarketStatus::Resolved(_))
-        } else {
-            false
+    /// Get total pool across all outcomes (optionally specify marketId or eventId, defaults to latest market)
+    async fn total_pool(&self, event_id: Option<String>, market_id: Option<MarketId>) -> Amount {
+        match self.resolve_market(event_id, market_id).await {
+            Some(target_id) => self.state.get_total_pool(target_id).await,
+            None => Amount::ZERO,
         }
     }
 
-    /// Check if market is open for betting (optionally specify eventId, defaults to latest market)
-    async fn is_open(&self, event_id: Option<String>) -> bool {
-        let target_id = self.resolve_event_id(event_id);
-        if let Some(((_, status, _, _, _, _, _), _)) = self.markets_data.get(&target_id) {
-            matches!(status, MarketStatus::Open)
-        } else {
-            false
+    /// Get betting pool for Home outcome (optionally specify marketId or eventId, defaults to latest market)
+    async fn home_pool(&self, event_id: Option<String>, market_id: Option<MarketId>) -> Amount {
+        self.outcome_pool(event_id, market_id, Outcome::Home).await
+    }
+
+    /// Get betting pool for Away outcome (optionally specify marketId or eventId, defaults to latest market)
+    async fn away_pool(&self, event_id: Option<String>, market_id: Option<MarketId>) -> Amount {
+        self.outcome_pool(event_id, market_id, Outcome::Away).await
+    }
+
+    /// Get betting pool for Draw outcome (optionally specify marketId or eventId, defaults to latest market)
+    async fn draw_pool(&self, event_id: Option<String>, market_id: Option<MarketId>) -> Amount {
+        self.outcome_pool(event_id, market_id, Outcome::Draw).await
+    }
+
+    /// Get every outcome with its label, pool and odds (optionally specify marketId or eventId, defaults to latest market)
+    async fn outcomes(
+        &self,
+        event_id: Option<String>,
+        market_id: Option<MarketId>,
+    ) -> Vec<OutcomePool> {
+        match self.market(event_id, market_id).await {
+            Some(info) => self.outcome_pools(&info).await,
+            None => Vec::new(),
         }
     }
 
-    /// Get total pool across all outcomes (optionally specify eventId, defaults to latest market)
-    async fn total_pool(&self, event_id: Option<String>) -> Amount {
-        let target_id = self.resolve_event_id(event_id);
-        if let Some(((_, _, total_pool, _, _, _, _), _)) = self.markets_data.get(&target_id) {
-            *total_pool
-        } else {
-            Amount::ZERO
+    /// Quote the payout multiplier a new bet would get, in basis points, counting its own stake
+    /// (optionally specify marketId or eventId, defaults to latest market)
+    /// Pass a value at or below it as `minPayoutBps` when placing the bet to cap slippage
+    /// Fixed-odds markets quote their published odds, or nothing before any are published;
+    /// LMSR markets quote nothing (see quoteBuyShares)
+    async fn quote_payout_bps(
+        &self,
+        outcome: Outcome,
+        amount: Amount,
+        event_id: Option<String>,
+        market_id: Option<MarketId>,
+    ) -> Option<u64> {
+        let info = self.market(event_id, market_id).await?;
+        let outcome_pool = self
+            .outcome_pools(&info)
+            .await
+            .into_iter()
+            .find(|pool| pool.index == outcome.index())?;
+        match info.pricing {
+            PricingMode::Parimutuel => {}
+            PricingMode::FixedOdds => return outcome_pool.odds_bps.map(u64::from),
+            PricingMode::Lmsr => return None,
         }
+        let total_pool = self.state.get_total_pool(info.market_id).await;
+        let outcome_pool = outcome_pool.pool.saturating_add(amount);
+        (outcome_pool > Amount::ZERO)
+            .then(|| info.payout_multiplier_bps(total_pool.saturating_add(amount), outcome_pool))
     }
 
-    /// Get betting pool for Home outcome (optionally specify eventId, defaults to latest market)
-    async fn home_pool(&self, event_id: Option<String>) -> Amount {
-        let target_id = self.resolve_event_id(event_id);
-        if let Some(((_, _, _, home_pool, _, _, _), _)) = self.markets_data.get(&target_id) {
-            *home_pool
-        } else {
-            Amount::ZERO
+    /// Get all bets placed on this market (optionally specify marketId or eventId, defaults to latest market)
+    async fn all_bets(&self, event_id: Option<String>, market_id: Option<MarketId>) -> Vec<Bet> {
+        match self.resolve_market(event_id, market_id).await {
+            Some(target_id) => self.state.get_bets(target_id).await,
+            None => Vec::new(),
         }
     }
 
-    /// Get betting pool for Away outcome (optionally specify eventId, defaults to latest market)
-    async fn away_pool(&self, event_id: Option<String>) -> Amount {
-        let target_id = self.resolve_event_id(event_id);
-        if let Some(((_, _, _, _, away_pool, _, _), _)) = self.markets_data.get(&target_id) {
-            *away_pool
-        } else {
-            Amount::ZERO
+    /// Get what each settled bet is owed and whether it was claimed (optionally specify marketId or eventId, defaults to latest market)
+    async fn entitlements(
+        &self,
+        event_id: Option<String>,
+        market_id: Option<MarketId>,
+    ) -> Vec<Entitlement> {
+        match self.resolve_market(event_id, market_id).await {
+            Some(target_id) => self.state.get_entitlements(target_id).await,
+            None => Vec::new(),
         }
     }
 
-    /// Get betting pool for Draw outcome (optionally specify eventId, defaults to latest market)
-    async fn draw_pool(&self, event_id: Option<String>) -> Amount {
-        let target_id = self.resolve_event_id(event_id);
-        if let Some(((_, _, _, _, _, draw_pool, _), _)) = self.markets_data.get(&target_id) {
-            *draw_pool
-        } else {
-            Amount::ZERO
+    /// Get total amount settled but not yet claimed (optionally specify marketId or eventId, defaults to latest market)
+    async fn unclaimed_total(
+        &self,
+        event_id: Option<String>,
+        market_id: Option<MarketId>,
+    ) -> Amount {
+        let Some(target_id) = self.resolve_market(event_id, market_id).await else {
+            return Amount::ZERO;
+        };
+        let mut total = Amount::ZERO;
+        for entitlement in self.state.get_entitlements(target_id).await {
+            if !entitlement.claimed {
+                total.saturating_add_assign(entitlement.amount);
+            }
         }
+        total
     }
 
-    /// Get all bets placed on this market (optionally specify eventId, defaults to latest market)
-    async fn all_bets(&self, event_id: Option<String>) -> Vec<Bet> {
-        let target_id = self.resolve_event_id(event_id);
-        if let Some((_, bets)) = self.markets_data.get(&target_id) {
-            bets.clone()
-        } else {
-            Vec::new()
+    /// Get how many settled bets SettleNext has processed so far (optionally specify marketId or eventId, defaults to latest market)
+    async fn settlement_progress(
+        &self,
+        event_id: Option<String>,
+        market_id: Option<MarketId>,
+    ) -> SettlementProgress {
+        match self.resolve_market(event_id, market_id).await {
+            Some(target_id) => self.state.get_settlement_progress(target_id).await,
+            None => SettlementProgress::default(),
         }
     }
 
-    /// Get total number of bets placed (optionally specify eventId, defaults to latest market)
-    async fn bet_count(&self, event_id: Option<String>) -> u64 {
-        let target_id = self.resolve_event_id(event_id);
-        if let Some(((_, _, _, _, _, _, bet_count), _)) = self.markets_data.get(&target_id) {
-            *bet_count
-        } else {
-            0
+    /// Check if every settled bet has been processed by SettleNext (optionally specify marketId or eventId, defaults to latest market)
+    async fn settlement_complete(
+        &self,
+        event_id: Option<String>,
+        market_id: Option<MarketId>,
+    ) -> bool {
+        let Some(target_id) = self.resolve_market(event_id, market_id).await else {
+            return false;
+        };
+        let progress = self.state.get_settlement_progress(target_id).await;
+        progress.total > 0 && progress.is_complete()
+    }
+
+    /// Get total number of bets placed (optionally specify marketId or eventId, defaults to latest market)
+    async fn bet_count(&self, event_id: Option<String>, market_id: Option<MarketId>) -> u64 {
+        match self.resolve_market(event_id, market_id).await {
+            Some(target_id) => self.state.get_bet_count(target_id).await,
+            None => 0,
         }
     }
 
-    /// Get odds for Home outcome (optionally specify eventId, defaults to latest market)
-    async fn home_odds(&self, event_id: Option<String>) -> f64 {
-        let target_id = self.resolve_event_id(event_id);
-        if let Some(((_, _, total_pool, home_pool, _, _, _), _)) = self.markets_data.get(&target_id) {
-            if *home_pool > Amount::ZERO {
-                let total: u128 = (*total_pool).into();
-                let home: u128 = (*home_pool).into();
-                (total as f64) / (home as f64)
-            } else {
-                1.0
+    /// Get odds for Home outcome (optionally specify marketId or eventId, defaults to latest market)
+    async fn home_odds(&self, event_id: Option<String>, market_id: Option<MarketId>) -> f64 {
+        self.outcome_odds(event_id, market_id, Outcome::Home).await
+    }
+
+    /// Get odds for Away outcome (optionally specify marketId or eventId, defaults to latest market)
+    async fn away_odds(&self, event_id: Option<String>, market_id: Option<MarketId>) -> f64 {
+        self.outcome_odds(event_id, market_id, Outcome::Away).await
+    }
+
+    /// Get odds for Draw outcome (optionally specify marketId or eventId, defaults to latest market)
+    async fn draw_odds(&self, event_id: Option<String>, market_id: Option<MarketId>) -> f64 {
+        self.outcome_odds(event_id, market_id, Outcome::Draw).await
+    }
+
+    /// Get list of all market event IDs (each listed once, in creation order)
+    async fn all_markets(&self) -> Vec<String> {
+        let mut event_ids = Vec::new();
+        for market_id in self.get_all_market_ids().await {
+            if let Some(info) = self.state.get_market(market_id).await {
+                if !event_ids.contains(&info.event_id.0) {
+                    event_ids.push(info.event_id.0);
+                }
             }
-        } else {
-            1.0
         }
+        event_ids
+    }
+
+    /// Get list of all market IDs, in creation order
+    async fn all_market_ids(&self) -> Vec<MarketId> {
+        self.get_all_market_ids().await
     }
 
-    /// Get odds for Away outcome (optionally specify eventId, defaults to latest market)
-    async fn away_odds(&self, event_id: Option<String>) -> f64 {
-        let target_id = self.resolve_event_id(event_id);
-        if let Some(((_, _, total_pool, _, away_pool, _, _), _)) = self.markets_data.get(&target_id) {
-            if *away_pool > Amount::ZERO {
-                let total: u128 = (*total_pool).into();
-                let away: u128 = (*away_pool).into();
-                (total as f64) / (away as f64)
-            } else {
-                1.0
+    /// Get stake limits (optionally specify marketId or eventId, defaults to latest market)
+    async fn stake_limits(
+        &self,
+        event_id: Option<String>,
+        market_id: Option<MarketId>,
+    ) -> StakeLimits {
+        self.market(event_id, market_id)
+            .await
+            .map(|info| info.limits)
+            .unwrap_or_default()
+    }
+
+    /// Get a user's total stake (optionally specify marketId or eventId, defaults to latest market)
+    async fn user_exposure(
+        &self,
+        user: AccountOwner,
+        event_id: Option<String>,
+        market_id: Option<MarketId>,
+    ) -> Amount {
+        match self.resolve_market(event_id, market_id).await {
+            Some(target_id) => self.state.get_exposure(target_id, &user).await,
+            None => Amount::ZERO,
+        }
+    }
+
+    /// Get the LMSR liquidity parameter `b`
+    /// (optionally specify marketId or eventId, defaults to latest market)
+    async fn liquidity(
+        &self,
+        event_id: Option<String>,
+        market_id: Option<MarketId>,
+    ) -> Option<Amount> {
+        self.market(event_id, market_id).await?.liquidity
+    }
+
+    /// Get the current price of one share of an outcome of an LMSR market, in BET
+    /// (optionally specify marketId or eventId, defaults to latest market)
+    async fn share_price(
+        &self,
+        outcome: Outcome,
+        event_id: Option<String>,
+        market_id: Option<MarketId>,
+    ) -> Option<Amount> {
+        let info = self.market(event_id, market_id).await?;
+        self.outcome_pools(&info)
+            .await
+            .into_iter()
+            .find(|pool| pool.index == outcome.index())?
+            .price
+    }
+
+    /// Quote what buying shares of an outcome of an LMSR market costs now
+    /// (optionally specify marketId or eventId, defaults to latest market)
+    /// Pass a value at or above it as `maxCost` when buying to cap slippage
+    async fn quote_buy_shares(
+        &self,
+        outcome: Outcome,
+        shares: Amount,
+        event_id: Option<String>,
+        market_id: Option<MarketId>,
+    ) -> Option<Amount> {
+        let (liquidity, supply) = self.share_book(event_id, market_id, outcome).await?;
+        let mut after = supply.clone();
+        let index = outcome.index() as usize;
+        after[index] = after[index].try_add(shares).ok()?;
+        let cost = flashbet_shared::lmsr_cost(liquidity, &after)?;
+        Some(cost.saturating_sub(flashbet_shared::lmsr_cost(liquidity, &supply)?))
+    }
+
+    /// Quote what selling shares of an outcome back to an LMSR market pays now
+    /// (optionally specify marketId or eventId, defaults to latest market)
+    /// Pass a value at or below it as `minProceeds` when selling to cap slippage
+    async fn quote_sell_shares(
+        &self,
+        outcome: Outcome,
+        shares: Amount,
+        event_id: Option<String>,
+        market_id: Option<MarketId>,
+    ) -> Option<Amount> {
+        let (liquidity, supply) = self.share_book(event_id, market_id, outcome).await?;
+        let mut after = supply.clone();
+        let index = outcome.index() as usize;
+        after[index] = after[index].try_sub(shares).ok()?;
+        let cost = flashbet_shared::lmsr_cost(liquidity, &supply)?;
+        Some(cost.saturating_sub(flashbet_shared::lmsr_cost(liquidity, &after)?))
+    }
+
+    /// Get the shares of an outcome of an LMSR market a trader holds
+    /// (optionally specify marketId or eventId, defaults to latest market)
+    async fn shares_held(
+        &self,
+        holder: AccountOwner,
+        outcome: Outcome,
+        event_id: Option<String>,
+        market_id: Option<MarketId>,
+    ) -> Amount {
+        match self.resolve_market(event_id, market_id).await {
+            Some(target_id) => self.state.get_position(target_id, &holder, &outcome).await,
+            None => Amount::ZERO,
+        }
+    }
+
+    /// Get the order book of an outcome of an LMSR market, aggregated by price
+    /// (optionally specify marketId or eventId, defaults to latest market)
+    /// `levels` caps the price levels returned on each side
+    async fn order_book(
+        &self,
+        outcome: Outcome,
+        levels: Option<u32>,
+        event_id: Option<String>,
+        market_id: Option<MarketId>,
+    ) -> OrderBookDepth {
+        let orders = self.market_orders(event_id, market_id).await;
+        let depth = |side: OrderSide| {
+            let mut book: Vec<PriceLevel> = Vec::new();
+            for order in orders
+                .iter()
+                .filter(|order| order.outcome == outcome && order.side == side)
+            {
+                match book.iter_mut().find(|level| level.price == order.price) {
+                    Some(level) => {
+                        level.shares = level.shares.saturating_add(order.remaining);
+                        level.orders += 1;
+                    }
+                    None => book.push(PriceLevel {
+                        price: order.price,
+                        shares: order.remaining,
+                        orders: 1,
+                    }),
+                }
+            }
+            match side {
+                OrderSide::Bid => book.sort_by(|a, b| b.price.cmp(&a.price)),
+                OrderSide::Ask => book.sort_by(|a, b| a.price.cmp(&b.price)),
             }
-        } else {
-            1.0
+            book.truncate(levels.map_or(usize::MAX, |levels| levels as usize));
+            book
+        };
+        OrderBookDepth {
+            bids: depth(OrderSide::Bid),
+            asks: depth(OrderSide::Ask),
         }
     }
 
-    /// Get odds for Draw outcome (optionally specify eventId, defaults to latest market)
-    async fn draw_odds(&self, event_id: Option<String>) -> f64 {
-        let target_id = self.resolve_event_id(event_id);
-        if let Some(((_, _, total_pool, _, _, draw_pool, _), _)) = self.markets_data.get(&target_id) {
-            if *draw_pool > Amount::ZERO {
-                let total: u128 = (*total_pool).into();
-                let draw: u128 = (*draw_pool).into();
-                (total as f64) / (draw as f64)
-            } else {
-                1.0
+    /// Get the orders resting on a market's order books, oldest first
+    /// (optionally filter by owner; specify marketId or eventId, defaults to latest market)
+    async fn open_orders(
+        &self,
+        owner: Option<AccountOwner>,
+        event_id: Option<String>,
+        market_id: Option<MarketId>,
+    ) -> Vec<Order> {
+        match owner {
+            // An owner's orders are indexed, so the market's books are not scanned for them
+            Some(owner) => {
+                let target_id = self.resolve_market(event_id, market_id).await;
+                self.state
+                    .get_owner_orders(&owner)
+                    .await
+                    .into_iter()
+                    .filter(|order| Some(order.market_id) == target_id)
+                    .collect()
             }
-        } else {
-            1.0
+            None => self.market_orders(event_id, market_id).await,
         }
     }
 
-    /// Get list of all market event IDs
-    async fn all_markets(&self) -> Vec<String> {
-        self.all_market_ids.clone()
+    /// Get the latest trades on a market's order books, newest first (at most 100)
+    /// (optionally specify marketId or eventId, defaults to latest market)
+    async fn recent_trades(
+        &self,
+        limit: Option<u32>,
+        event_id: Option<String>,
+        market_id: Option<MarketId>,
+    ) -> Vec<Trade> {
+        let Some(target_id) = self.resolve_market(event_id, market_id).await else {
+            return Vec::new();
+        };
+        let limit = limit.map_or(MAX_RECENT_TRADES, |limit| {
+            u64::from(limit).min(MAX_RECENT_TRADES)
+        });
+        self.state.get_recent_trades(target_id, limit).await
+    }
+
+    /// Get the house funds backing fixed-odds markets that no market has reserved
+    async fn bankroll(&self) -> Amount {
+        self.bankroll
     }
 
-    /// Get Market chain's native token balance (total escrow balance)
+    /// Get the bankroll a fixed-odds market has reserved to cover its worst outcome
+    /// (optionally specify marketId or eventId, defaults to latest market)
+    async fn bankroll_reserved(
+        &self,
+        event_id: Option<String>,
+        market_id: Option<MarketId>,
+    ) -> Amount {
+        match self.resolve_market(event_id, market_id).await {
+            Some(target_id) => self.state.get_reserved(target_id).await,
+            None => Amount::ZERO,
+        }
+    }
+
+    /// Get the BET held in escrow for this application (stakes, bankroll and order escrow)
     async fn escrow_balance(&self) -> Amount {
         self.escrow_balance
     }
+
+    /// Get Market chain owner (holds every role)
+    async fn owner(&self) -> Option<AccountOwner> {
+        self.owner
+    }
+
+    /// Get accounts granted a role by the owner
+    async fn role_holders(&self, role: MarketRole) -> Vec<AccountOwner> {
+        self.roles
+            .iter()
+            .filter(|(granted, _)| *granted == role)
+            .map(|(_, account)| *account)
+            .collect()
+    }
+
+    /// Check if betting and settlement are halted on every market of the chain
+    async fn paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Check if an account holds a role (the owner holds them all)
+    async fn has_role(&self, role: MarketRole, account: AccountOwner) -> bool {
+        self.owner == Some(account) || self.roles.contains(&(role, account))
+    }
 }
 
 impl QueryRoot {
-    /// Helper: resolve eventId parameter to actual String (defaults to latest)
-    fn resolve_event_id(&self, event_id: Option<String>) -> String {
-        match event_id {
-            Some(id) => id,
-            None => self.latest_event_id.as_ref()
-                .map(|e| e.0.clone())
-                .unwrap_or_else(|| "none".to_string()),
+    /// Helper: resolve marketId/eventId parameters to a market (defaults to latest)
+    /// An event with several markets resolves to its first market
+    async fn resolve_market(
+        &self,
+        event_id: Option<String>,
+        market_id: Option<MarketId>,
+    ) -> Option<MarketId> {
+        match (market_id, event_id) {
+            (Some(market_id), _) => Some(market_id),
+            (None, Some(event_id)) => self
+                .state
+                .get_event_markets(&EventId(event_id))
+                .await
+                .first()
+                .copied(),
+            // Market IDs are assigned in creation order, so the latest is the last one
+            (None, None) => self.state.next_market_id.get().checked_sub(1).map(MarketId),
+        }
+    }
+
+    /// Helper: the market marketId/eventId parameters refer to, if it exists
+    async fn market(
+        &self,
+        event_id: Option<String>,
+        market_id: Option<MarketId>,
+    ) -> Option<MarketInfo> {
+        let target_id = self.resolve_market(event_id, market_id).await?;
+        self.state.get_market(target_id).await
+    }
+
+    /// Helper: the market marketId/eventId parameters refer to, with its status
+    async fn market_status(
+        &self,
+        event_id: Option<String>,
+        market_id: Option<MarketId>,
+    ) -> Option<(MarketInfo, MarketStatus)> {
+        let info = self.market(event_id, market_id).await?;
+        let status = self.state.get_status(info.market_id).await;
+        Some((info, status))
+    }
+
+    /// Helper: all market IDs, in creation order
+    async fn get_all_market_ids(&self) -> Vec<MarketId> {
+        let mut ids = Vec::new();
+        self.state
+            .markets
+            .for_each_index(|market_id| {
+                ids.push(market_id);
+                Ok(())
+            })
+            .await
+            .expect("Failed to iterate markets");
+        ids.sort();
+        ids
+    }
+
+    /// Helper: amount staked on an outcome of a market
+    async fn outcome_pool(
+        &self,
+        event_id: Option<String>,
+        market_id: Option<MarketId>,
+        outcome: Outcome,
+    ) -> Amount {
+        match self.resolve_market(event_id, market_id).await {
+            Some(target_id) => self.state.get_pool_for_outcome(target_id, &outcome).await,
+            None => Amount::ZERO,
+        }
+    }
+
+    /// Helper: odds of an outcome of a market (1.0 if it has none)
+    async fn outcome_odds(
+        &self,
+        event_id: Option<String>,
+        market_id: Option<MarketId>,
+        outcome: Outcome,
+    ) -> f64 {
+        let Some(info) = self.market(event_id, market_id).await else {
+            return 1.0;
+        };
+        self.outcome_pools(&info)
+            .await
+            .into_iter()
+            .find(|pool| pool.index == outcome.index())
+            .map_or(1.0, |pool| pool.odds)
+    }
+
+    /// Helper: orders resting on the order books of a market
+    async fn market_orders(
+        &self,
+        event_id: Option<String>,
+        market_id: Option<MarketId>,
+    ) -> Vec<Order> {
+        match self.market(event_id, market_id).await {
+            Some(info) if info.pricing == PricingMode::Lmsr => self.state.get_orders(&info).await,
+            _ => Vec::new(),
+        }
+    }
+
+    /// Helper: liquidity and outstanding shares of an LMSR market, if it has the outcome
+    async fn share_book(
+        &self,
+        event_id: Option<String>,
+        market_id: Option<MarketId>,
+        outcome: Outcome,
+    ) -> Option<(Amount, Vec<Amount>)> {
+        let info = self.market(event_id, market_id).await?;
+        let liquidity = info
+            .liquidity
+            .filter(|_| info.pricing == PricingMode::Lmsr)?;
+        let supply = self.state.get_share_supply(&info).await;
+        ((outcome.index() as usize) < supply.len()).then_some((liquidity, supply))
+    }
+
+    /// Helper: the pool and odds of every outcome of a market
+    async fn outcome_pools(&self, info: &MarketInfo) -> Vec<OutcomePool> {
+        let total_pool = self.state.get_total_pool(info.market_id).await;
+        // LMSR share prices follow the outstanding shares of every outcome
+        let share_supply = self.state.get_share_supply(info).await;
+        let share_prices = info
+            .liquidity
+            .filter(|_| info.pricing == PricingMode::Lmsr)
+            .and_then(|liquidity| flashbet_shared::lmsr_prices(liquidity, &share_supply));
+
+        let mut pools = Vec::new();
+        for outcome in info.outcomes() {
+            let index = Outcome::from_index(outcome.index);
+            let pool = self
+                .state
+                .get_pool_for_outcome(info.market_id, &index)
+                .await;
+            let price = share_prices
+                .as_ref()
+                .map(|prices| prices[outcome.index as usize]);
+            let (odds_bps, odds) = match info.pricing {
+                PricingMode::Parimutuel => (None, pool_odds(info, total_pool, pool)),
+                PricingMode::Lmsr => {
+                    let price: u128 = price.unwrap_or(Amount::ONE).into();
+                    (None, if price > 0 { 1e18 / price as f64 } else { 1.0 })
+                }
+                PricingMode::FixedOdds => {
+                    let odds_bps = self.state.get_fixed_odds(info.market_id, &index).await;
+                    let odds = odds_bps.map_or(1.0, |odds_bps| {
+                        f64::from(odds_bps) / f64::from(flashbet_shared::BASIS_POINTS)
+                    });
+                    (odds_bps, odds)
+                }
+            };
+            let liability = match info.pricing {
+                PricingMode::Lmsr => share_supply[outcome.index as usize],
+                PricingMode::Parimutuel | PricingMode::FixedOdds => {
+                    self.state.get_liability(info.market_id, &index).await
+                }
+            };
+            pools.push(OutcomePool {
+                index: outcome.index,
+                label: outcome.label,
+                pool,
+                odds,
+                odds_bps,
+                liability,
+                price,
+            });
         }
+        pools
     }
 }
@@ -2,38 +2,49 @@
 //!
 //! Manages multiple prediction markets' betting pools and resolution.
 
-use flashbet_shared::{Bet, EventId, MarketInfo, MarketStatus, Outcome};
+use flashbet_shared::{
+    Bet, BetKey, Entitlement, EntitlementKind, EventId, EventResult, MarketId, MarketInfo,
+    MarketRole, MarketStatus, Order, OrderSide, Outcome, Rake, SettlementProgress, Trade,
+};
 use linera_sdk::{
-    linera_base_types::{Amount, ApplicationId, ChainId},
-    views::{linera_views, MapView, RegisterView, RootView, SetView, ViewStorageContext},
+    linera_base_types::{
+        AccountOwner, Amount, ApplicationId, ChainId, StreamId, TimeDelta, Timestamp,
+    },
+    views::{
+        linera_views, CollectionView, CustomMapView, MapView, RegisterView, RootView, SetView,
+        ViewStorageContext,
+    },
 };
 
+/// An order book: the bids or the asks on an outcome of a market
+pub type BookId = (MarketId, Outcome, OrderSide);
+
+/// Market chain state
+///
+/// The layout is not compatible with versions that keyed markets by `EventId`, and there is
+/// no migration: upgrading means deploying a new application.
 #[derive(RootView)]
 #[view(context = ViewStorageContext)]
 pub struct FlashbetMarketState {
-    /// Markets indexed by EventId
-    /// Maps EventId -> MarketInfo
-    pub markets: MapView<EventId, MarketInfo>,
+    /// Markets indexed by MarketId
+    /// Maps MarketId -> MarketInfo
+    pub markets: MapView<MarketId, MarketInfo>,
 
-    /// Market status for each event
-    /// Maps EventId -> MarketStatus
-    pub statuses: MapView<EventId, MarketStatus>,
+    /// Market status for each market
+    /// Maps MarketId -> MarketStatus
+    pub statuses: MapView<MarketId, MarketStatus>,
 
     /// Betting pools for each market and outcome
-    /// Maps (EventId, Outcome) -> Amount
-    pub pools: MapView<(EventId, Outcome), Amount>,
-
-    /// All bets placed across all markets
-    /// Maps (EventId, bet_id) -> Bet
-    pub bets: MapView<(EventId, u64), Bet>,
+    /// Maps (MarketId, Outcome) -> Amount
+    pub pools: MapView<(MarketId, Outcome), Amount>,
 
     /// Total pool for each market
-    /// Maps EventId -> Amount
-    pub total_pools: MapView<EventId, Amount>,
+    /// Maps MarketId -> Amount
+    pub total_pools: MapView<MarketId, Amount>,
 
     /// Bet counter for each market
-    /// Maps EventId -> u64
-    pub bet_counts: MapView<EventId, u64>,
+    /// Maps MarketId -> u64
+    pub bet_counts: MapView<MarketId, u64>,
 
     /// Oracle chain ID (shared across all markets)
     pub oracle_chain: RegisterView<Option<ChainId>>,
@@ -48,23 +59,158 @@ pub struct FlashbetMarketState {
     /// Subscribed User applications (shared across all markets)
     /// Tracks which User chains/apps we're listening to
     pub subscribed_users: SetView<ApplicationId>,
+
+    /// How long before `event_time` betting closes (shared across all markets)
+    pub betting_cutoff: RegisterView<TimeDelta>,
+
+    /// Treasury account on this chain for fees and unclaimed pools
+    pub treasury: RegisterView<Option<AccountOwner>>,
+
+    /// Bets placed on each market
+    /// Maps MarketId -> BetKey -> Bet
+    pub bets: CollectionView<MarketId, MapView<BetKey, Bet>>,
+
+    /// Index of the last event processed from each subscribed stream
+    /// Maps (publishing chain, StreamId) -> event index
+    pub last_processed_events: MapView<(ChainId, StreamId), u32>,
+
+    /// Oracle keys allowed to submit results directly with ProcessOracleResult
+    pub oracle_keys: SetView<AccountOwner>,
+
+    /// House fee applied to markets created without their own rake
+    pub default_rake: RegisterView<Rake>,
+
+    /// What each settled bet is owed, paid out when claimed
+    /// Maps MarketId -> BetKey -> Entitlement
+    pub entitlements: CollectionView<MarketId, MapView<BetKey, Entitlement>>,
+
+    /// Bets owed something, in the order SettleNext pays them out
+    /// Maps (MarketId, position) -> BetKey
+    pub settlement_queue: MapView<(MarketId, u64), BetKey>,
+
+    /// Settlement cursor of each settled market
+    /// Maps MarketId -> SettlementProgress
+    pub settlement_progress: MapView<MarketId, SettlementProgress>,
+
+    /// Counter for assigning market IDs
+    pub next_market_id: RegisterView<u64>,
+
+    /// Markets on each event, in creation order
+    /// Maps EventId -> Vec<MarketId>
+    pub event_markets: MapView<EventId, Vec<MarketId>>,
+
+    /// Market chain owner (holds every role and can grant or revoke them)
+    pub owner: RegisterView<Option<AccountOwner>>,
+
+    /// Roles granted by the owner
+    /// Set of (MarketRole, AccountOwner)
+    pub roles: SetView<(MarketRole, AccountOwner)>,
+
+    /// Whether betting and settlement are halted on every market
+    pub paused: RegisterView<bool>,
+
+    /// Status each suspended market had before it was paused
+    /// Maps MarketId -> MarketStatus
+    pub suspended_statuses: MapView<MarketId, MarketStatus>,
+
+    /// Oracle results received while their market or the chain was paused
+    /// Maps MarketId -> EventResult
+    pub deferred_results: MapView<MarketId, EventResult>,
+
+    /// Total stake of each user on each market
+    /// Maps MarketId -> AccountOwner -> Amount
+    pub exposures: CollectionView<MarketId, MapView<AccountOwner, Amount>>,
+
+    /// Odds currently offered on each outcome of fixed-odds markets, in basis points
+    /// Maps (MarketId, Outcome) -> odds
+    pub fixed_odds: MapView<(MarketId, Outcome), u32>,
+
+    /// Odds each fixed-odds bet was accepted at, in basis points
+    /// Maps (MarketId, BetKey) -> odds
+    pub locked_odds: MapView<(MarketId, BetKey), u32>,
+
+    /// Total payout owed to the bets on each outcome of fixed-odds markets if it wins
+    /// Maps (MarketId, Outcome) -> Amount
+    pub liabilities: MapView<(MarketId, Outcome), Amount>,
+
+    /// House funds backing fixed-odds markets, not reserved by any market
+    pub bankroll: RegisterView<Amount>,
+
+    /// Bankroll reserved by each unsettled fixed-odds market to cover its worst outcome
+    /// Maps MarketId -> Amount
+    pub bankroll_reserved: MapView<MarketId, Amount>,
+
+    /// Outstanding shares of each outcome of LMSR markets
+    /// Maps (MarketId, Outcome) -> shares
+    pub share_supply: MapView<(MarketId, Outcome), Amount>,
+
+    /// Shares each trader holds in LMSR markets
+    /// Maps MarketId -> (holder, Outcome) -> shares
+    pub share_positions: CollectionView<MarketId, MapView<(AccountOwner, Outcome), Amount>>,
+
+    /// What each share of a settled LMSR market pays out, in BET
+    /// Maps (MarketId, Outcome) -> value per share
+    pub share_values: MapView<(MarketId, Outcome), Amount>,
+
+    /// Counter for assigning order IDs
+    pub next_order_id: RegisterView<u64>,
+
+    /// Where each order resting on an order book sits
+    /// Maps order ID -> (book, position on the book)
+    pub orders: MapView<u64, (BookId, u128)>,
+
+    /// Orders resting on each order book, keyed so that they are read best price first and
+    /// oldest first at each price (see `book_position`)
+    /// Maps (MarketId, Outcome, OrderSide) -> position on the book -> Order
+    pub order_books: CollectionView<BookId, CustomMapView<u128, Order>>,
+
+    /// Trades on each market's order books, in execution order
+    /// Maps (MarketId, position) -> Trade
+    pub trades: MapView<(MarketId, u64), Trade>,
+
+    /// Number of trades on each market's order books
+    /// Maps MarketId -> u64
+    pub trade_counts: MapView<MarketId, u64>,
+
+    /// Orders each account has resting on the order books
+    /// Maps owner -> order IDs
+    pub owner_orders: CollectionView<AccountOwner, SetView<u64>>,
+
+    /// User application allowed to register bets, whose PlaceBet escrows their stakes
+    pub user_app_id: RegisterView<Option<ApplicationId>>,
+}
+
+/// Key of an order on its book, which orders the book best price first and oldest first at
+/// each price
+///
+/// Prices are below 1 BET, so a price (inverted for bids) fills the high 64 bits, and the
+/// order ID, assigned in placement order, the low 64 bits.
+fn book_position(order: &Order) -> u128 {
+    let price = u64::try_from(u128::from(order.price)).expect("Order price out of range");
+    let rank = match order.side {
+        OrderSide::Bid => u64::MAX - price,
+        OrderSide::Ask => price,
+    };
+    (u128::from(rank) << 64) | u128::from(order.order_id)
 }
 
+// Compiled into both the contract and service binaries, each of which uses a subset
+#[allow(dead_code)]
 impl FlashbetMarketState {
-    /// Get market info for a specific event
-    pub async fn get_market(&self, event_id: &EventId) -> Option<MarketInfo> {
+    /// Get market info by market ID
+    pub async fn get_market(&self, market_id: MarketId) -> Option<MarketInfo> {
         self.markets
-            .get(event_id)
+            .get(&market_id)
             .await
             .ok()
             .flatten()
             .map(|info| info.to_owned())
     }
 
-    /// Get current market status for a specific event
-    pub async fn get_status(&self, event_id: &EventId) -> MarketStatus {
+    /// Get current status of a market
+    pub async fn get_status(&self, market_id: MarketId) -> MarketStatus {
         self.statuses
-            .get(event_id)
+            .get(&market_id)
             .await
             .ok()
             .flatten()
@@ -72,23 +218,86 @@ impl FlashbetMarketState {
             .unwrap_or(MarketStatus::Open)
     }
 
+    /// Check if an account is the owner
+    pub fn is_owner(&self, account: &AccountOwner) -> bool {
+        self.owner.get().as_ref() == Some(account)
+    }
+
+    /// Check if an account holds a role (the owner holds them all)
+    pub async fn has_role(&self, role: MarketRole, account: &AccountOwner) -> bool {
+        self.is_owner(account)
+            || self
+                .roles
+                .contains(&(role, *account))
+                .await
+                .unwrap_or(false)
+    }
+
+    /// Grant a role, returning whether the account did not already hold it
+    pub async fn grant_role(&mut self, role: MarketRole, account: AccountOwner) -> bool {
+        if self.roles.contains(&(role, account)).await.unwrap_or(false) {
+            return false;
+        }
+        self.roles
+            .insert(&(role, account))
+            .expect("Failed to grant role");
+        true
+    }
+
+    /// Revoke a role, returning whether the account held it
+    pub async fn revoke_role(&mut self, role: MarketRole, account: AccountOwner) -> bool {
+        if !self.roles.contains(&(role, account)).await.unwrap_or(false) {
+            return false;
+        }
+        self.roles
+            .remove(&(role, account))
+            .expect("Failed to revoke role");
+        true
+    }
+
+    /// Get the accounts granted each role
+    pub async fn get_roles(&self) -> Vec<(MarketRole, AccountOwner)> {
+        let mut roles = Vec::new();
+        self.roles
+            .for_each_index(|entry| {
+                roles.push(entry);
+                Ok(())
+            })
+            .await
+            .expect("Failed to read roles");
+        roles
+    }
+
     /// Check if market exists
-    pub async fn market_exists(&self, event_id: &EventId) -> bool {
-        self.markets.get(event_id).await.ok().flatten().is_some()
+    pub async fn market_exists(&self, market_id: MarketId) -> bool {
+        self.markets.get(&market_id).await.ok().flatten().is_some()
     }
 
     /// Check if market is open for betting
-    pub async fn is_open(&self, event_id: &EventId) -> bool {
+    pub async fn is_open(&self, market_id: MarketId) -> bool {
         matches!(
-            self.statuses.get(event_id).await.ok().flatten().map(|s| s.to_owned()),
+            self.statuses
+                .get(&market_id)
+                .await
+                .ok()
+                .flatten()
+                .map(|s| s.to_owned()),
             Some(MarketStatus::Open)
         )
     }
 
+    /// Get the time at which betting closes for a market
+    pub async fn lock_time(&self, market_id: MarketId) -> Option<Timestamp> {
+        let cutoff = *self.betting_cutoff.get();
+        self.get_market(market_id)
+            .await
+            .map(|info| info.event_time.saturating_sub(cutoff))
+    }
+
     /// Get total pool amount for a market
-    pub async fn get_total_pool(&self, event_id: &EventId) -> Amount {
+    pub async fn get_total_pool(&self, market_id: MarketId) -> Amount {
         self.total_pools
-            .get(event_id)
+            .get(&market_id)
             .await
             .ok()
             .flatten()
@@ -97,9 +306,9 @@ impl FlashbetMarketState {
     }
 
     /// Get pool amount for a specific outcome in a market
-    pub async fn get_pool_for_outcome(&self, event_id: &EventId, outcome: &Outcome) -> Amount {
+    pub async fn get_pool_for_outcome(&self, market_id: MarketId, outcome: &Outcome) -> Amount {
         self.pools
-            .get(&(event_id.clone(), *outcome))
+            .get(&(market_id, *outcome))
             .await
             .ok()
             .flatten()
@@ -107,154 +316,860 @@ impl FlashbetMarketState {
             .unwrap_or(Amount::ZERO)
     }
 
-    /// Create a new market
-    pub async fn create_market(&mut self, info: MarketInfo) -> Result<(), String> {
-        // Check if market already exists
-        if self.market_exists(&info.event_id).await {
-            return Err(format!("Market {} already exists", info.event_id));
-        }
+    /// Create a new market, assigning it the next market ID
+    pub async fn create_market(&mut self, mut info: MarketInfo) -> MarketId {
+        let next_id = self.next_market_id.get_mut();
+        let market_id = MarketId(*next_id);
+        *next_id += 1;
+        info.market_id = market_id;
+
+        // Add it to the markets of its event
+        let mut event_markets = self.get_event_markets(&info.event_id).await;
+        event_markets.push(market_id);
+        self.event_markets
+            .insert(&info.event_id, event_markets)
+            .expect("Failed to index market by event");
 
         // Store market info
         self.markets
-            .insert(&info.event_id, info.clone())
+            .insert(&market_id, info)
             .expect("Failed to insert market");
 
         // Set initial status to Open
         self.statuses
-            .insert(&info.event_id, MarketStatus::Open)
+            .insert(&market_id, MarketStatus::Open)
             .expect("Failed to set market status");
 
         // Initialize total pool
         self.total_pools
-            .insert(&info.event_id, Amount::ZERO)
+            .insert(&market_id, Amount::ZERO)
             .expect("Failed to initialize total pool");
 
         // Initialize bet count
         self.bet_counts
-            .insert(&info.event_id, 0)
+            .insert(&market_id, 0)
             .expect("Failed to initialize bet count");
 
-        Ok(())
+        market_id
     }
 
-    /// Add bet to a market
-    pub async fn add_bet(&mut self, bet: Bet) {
-        let event_id = bet.event_id.clone();
+    /// Get the markets on an event, in creation order
+    pub async fn get_event_markets(&self, event_id: &EventId) -> Vec<MarketId> {
+        self.event_markets
+            .get(event_id)
+            .await
+            .ok()
+            .flatten()
+            .map(|market_ids| market_ids.to_owned())
+            .unwrap_or_default()
+    }
+
+    /// Check if a bet was already registered in a market
+    pub async fn has_bet(&self, market_id: MarketId, key: &BetKey) -> bool {
+        let Some(bets) = self
+            .bets
+            .try_load_entry(&market_id)
+            .await
+            .expect("Failed to load market bets")
+        else {
+            return false;
+        };
+        bets.contains_key(key).await.unwrap_or(false)
+    }
+
+    /// Add bet to its market (rejects bets that were already registered)
+    pub async fn add_bet(&mut self, bet: Bet) -> Result<(), String> {
+        let market_id = bet.market_id;
+        let key = bet.key();
+
+        // Check for replayed bets
+        if self.has_bet(market_id, &key).await {
+            return Err(format!("{} already registered in {}", key, market_id));
+        }
 
         // Update pool for this outcome
-        let current_pool = self.get_pool_for_outcome(&event_id, &bet.outcome).await;
+        let current_pool = self.get_pool_for_outcome(market_id, &bet.outcome).await;
         let new_pool = current_pool
             .try_add(bet.amount)
             .expect("Pool overflow");
         self.pools
-            .insert(&(event_id.clone(), bet.outcome), new_pool)
+            .insert(&(market_id, bet.outcome), new_pool)
             .expect("Failed to insert pool");
 
         // Update total pool
-        let current_total = self.get_total_pool(&event_id).await;
+        let current_total = self.get_total_pool(market_id).await;
         let new_total = current_total
             .try_add(bet.amount)
             .expect("Total pool overflow");
         self.total_pools
-            .insert(&event_id, new_total)
+            .insert(&market_id, new_total)
             .expect("Failed to update total pool");
 
+        // Update the user's exposure on this market
+        let exposure = self
+            .get_exposure(market_id, &bet.user)
+            .await
+            .try_add(bet.amount)
+            .expect("Exposure overflow");
+        self.exposures
+            .load_entry_mut(&market_id)
+            .await
+            .expect("Failed to load market exposures")
+            .insert(&bet.user, exposure)
+            .expect("Failed to update exposure");
+
         // Store bet
-        let bet_id = bet.bet_id;
         self.bets
-            .insert(&(event_id.clone(), bet_id), bet)
+            .load_entry_mut(&market_id)
+            .await
+            .expect("Failed to load market bets")
+            .insert(&key, bet)
             .expect("Failed to insert bet");
 
         // Increment bet count
         let current_count = self.bet_counts
-            .get(&event_id)
+            .get(&market_id)
             .await
             .ok()
             .flatten()
             .map(|c| c.to_owned())
             .unwrap_or(0);
         self.bet_counts
-            .insert(&event_id, current_count + 1)
+            .insert(&market_id, current_count + 1)
             .expect("Failed to increment bet count");
+
+        Ok(())
     }
 
     /// Lock a market (no more bets accepted)
-    pub async fn lock_market(&mut self, event_id: &EventId) {
+    pub async fn lock_market(&mut self, market_id: MarketId) {
         self.statuses
-            .insert(event_id, MarketStatus::Locked)
+            .insert(&market_id, MarketStatus::Locked)
             .expect("Failed to lock market");
     }
 
     /// Resolve a market with winning outcome
-    pub async fn resolve_market(&mut self, event_id: &EventId, winning_outcome: Outcome) {
+    pub async fn resolve_market(&mut self, market_id: MarketId, winning_outcome: Outcome) {
         self.statuses
-            .insert(event_id, MarketStatus::Resolved(winning_outcome))
+            .insert(&market_id, MarketStatus::Resolved(winning_outcome))
             .expect("Failed to resolve market");
     }
 
-    /// Cancel a market
-    pub async fn cancel_market(&mut self, event_id: &EventId) {
+    /// Mark a market as pushed (result exactly on its line)
+    pub async fn push_market(&mut self, market_id: MarketId) {
+        self.statuses
+            .insert(&market_id, MarketStatus::Push)
+            .expect("Failed to push market");
+    }
+
+    /// Cancel a market, discarding any suspension and the result held meanwhile
+    pub async fn cancel_market(&mut self, market_id: MarketId) {
         self.statuses
-            .insert(event_id, MarketStatus::Cancelled)
+            .insert(&market_id, MarketStatus::Cancelled)
             .expect("Failed to cancel market");
+        self.suspended_statuses
+            .remove(&market_id)
+            .expect("Failed to clear suspended status");
+        self.deferred_results
+            .remove(&market_id)
+            .expect("Failed to clear deferred result");
     }
 
-    /// Get all bets for a specific outcome in a market
-    pub async fn get_bets_for_outcome(&self, event_id: &EventId, outcome: &Outcome) -> Vec<Bet> {
-        let mut bets = Vec::new();
-        self.bets
-            .for_each_index_value(|(bet_event_id, _bet_id), bet| {
-                if bet_event_id == *event_id && &bet.outcome == outcome {
-                    bets.push(bet.into_owned());
-                }
+    /// Get the total stake of a user on a market
+    pub async fn get_exposure(&self, market_id: MarketId, user: &AccountOwner) -> Amount {
+        let Some(exposures) = self
+            .exposures
+            .try_load_entry(&market_id)
+            .await
+            .expect("Failed to load market exposures")
+        else {
+            return Amount::ZERO;
+        };
+        exposures
+            .get(user)
+            .await
+            .ok()
+            .flatten()
+            .unwrap_or(Amount::ZERO)
+    }
+
+    /// Suspend a market, remembering its status for when it resumes
+    pub async fn suspend_market(&mut self, market_id: MarketId) {
+        let status = self.get_status(market_id).await;
+        self.suspended_statuses
+            .insert(&market_id, status)
+            .expect("Failed to store suspended status");
+        self.statuses
+            .insert(&market_id, MarketStatus::Suspended)
+            .expect("Failed to suspend market");
+    }
+
+    /// Restore a suspended market to its previous status, returning that status
+    pub async fn resume_market(&mut self, market_id: MarketId) -> MarketStatus {
+        let status = self
+            .suspended_statuses
+            .get(&market_id)
+            .await
+            .ok()
+            .flatten()
+            .unwrap_or(MarketStatus::Open);
+        self.suspended_statuses
+            .remove(&market_id)
+            .expect("Failed to clear suspended status");
+        self.statuses
+            .insert(&market_id, status.clone())
+            .expect("Failed to resume market");
+        status
+    }
+
+    /// Keep an oracle result to settle once the market and the chain are no longer paused
+    pub async fn defer_result(&mut self, market_id: MarketId, result: EventResult) {
+        self.deferred_results
+            .insert(&market_id, result)
+            .expect("Failed to defer result");
+    }
+
+    /// Take the oracle result held for a market, if any
+    pub async fn take_deferred_result(&mut self, market_id: MarketId) -> Option<EventResult> {
+        let result = self.deferred_results.get(&market_id).await.ok().flatten()?;
+        self.deferred_results
+            .remove(&market_id)
+            .expect("Failed to clear deferred result");
+        Some(result)
+    }
+
+    /// Get the markets holding a deferred oracle result
+    pub async fn get_deferred_markets(&self) -> Vec<MarketId> {
+        let mut market_ids = Vec::new();
+        self.deferred_results
+            .for_each_index(|market_id| {
+                market_ids.push(market_id);
                 Ok(())
             })
             .await
-            .expect("Failed to iterate bets");
-        bets
+            .expect("Failed to read deferred results");
+        market_ids
     }
 
-    /// Get bet count for a market
-    pub async fn get_bet_count(&self, event_id: &EventId) -> u64 {
-        self.bet_counts
-            .get(event_id)
+    /// Get the odds offered on an outcome of a fixed-odds market, if any
+    pub async fn get_fixed_odds(&self, market_id: MarketId, outcome: &Outcome) -> Option<u32> {
+        self.fixed_odds
+            .get(&(market_id, *outcome))
             .await
             .ok()
             .flatten()
-            .map(|c| c.to_owned())
-            .unwrap_or(0)
     }
 
-    /// Calculate payout for a winning bet
-    pub async fn calculate_payout(&self, bet: &Bet, winning_outcome: &Outcome) -> Amount {
-        if &bet.outcome != winning_outcome {
+    /// Offer new odds on an outcome of a fixed-odds market
+    pub fn set_fixed_odds(&mut self, market_id: MarketId, outcome: Outcome, odds_bps: u32) {
+        self.fixed_odds
+            .insert(&(market_id, outcome), odds_bps)
+            .expect("Failed to set odds");
+    }
+
+    /// Get the odds a fixed-odds bet was accepted at
+    pub async fn get_locked_odds(&self, market_id: MarketId, key: &BetKey) -> Option<u32> {
+        self.locked_odds
+            .get(&(market_id, *key))
+            .await
+            .ok()
+            .flatten()
+    }
+
+    /// Get what a fixed-odds market owes the bets on an outcome if it wins
+    pub async fn get_liability(&self, market_id: MarketId, outcome: &Outcome) -> Amount {
+        self.liabilities
+            .get(&(market_id, *outcome))
+            .await
+            .ok()
+            .flatten()
+            .unwrap_or(Amount::ZERO)
+    }
+
+    /// Record the odds a fixed-odds bet locked in and what it would win
+    pub async fn lock_odds(&mut self, bet: &Bet, odds_bps: u32, payout: Amount) {
+        self.locked_odds
+            .insert(&(bet.market_id, bet.key()), odds_bps)
+            .expect("Failed to lock odds");
+        let liability = self
+            .get_liability(bet.market_id, &bet.outcome)
+            .await
+            .try_add(payout)
+            .expect("Liability overflow");
+        self.liabilities
+            .insert(&(bet.market_id, bet.outcome), liability)
+            .expect("Failed to update liability");
+    }
+
+    /// Get the bankroll a fixed-odds market has reserved
+    pub async fn get_reserved(&self, market_id: MarketId) -> Amount {
+        self.bankroll_reserved
+            .get(&market_id)
+            .await
+            .ok()
+            .flatten()
+            .unwrap_or(Amount::ZERO)
+    }
+
+    /// Get the most a fixed-odds market could reserve: the free bankroll plus its reservation
+    pub async fn available_bankroll(&self, market_id: MarketId) -> Amount {
+        self.bankroll
+            .get()
+            .saturating_add(self.get_reserved(market_id).await)
+    }
+
+    /// Move bankroll in or out of a market's reservation so it holds `reserved`
+    pub async fn reserve_bankroll(&mut self, market_id: MarketId, reserved: Amount) {
+        let free = self
+            .available_bankroll(market_id)
+            .await
+            .try_sub(reserved)
+            .expect("Bankroll cannot cover the reservation");
+        self.bankroll.set(free);
+        self.bankroll_reserved
+            .insert(&market_id, reserved)
+            .expect("Failed to reserve bankroll");
+    }
+
+    /// Return a settled fixed-odds market's reservation to the bankroll, along with the stakes
+    /// it keeps after paying `owed` to bettors
+    pub async fn release_bankroll(&mut self, market_id: MarketId, owed: Amount) {
+        let reserved = self.get_reserved(market_id).await;
+        let kept = self
+            .get_total_pool(market_id)
+            .await
+            .saturating_add(reserved)
+            .try_sub(owed)
+            .expect("Fixed-odds payouts exceed the market's reservation");
+        self.bankroll.set(self.bankroll.get().saturating_add(kept));
+        self.bankroll_reserved
+            .remove(&market_id)
+            .expect("Failed to release bankroll");
+    }
+
+    /// Get the outstanding shares of every outcome of an LMSR market, by outcome index
+    pub async fn get_share_supply(&self, info: &MarketInfo) -> Vec<Amount> {
+        let mut supply = Vec::new();
+        for outcome in info.outcomes() {
+            let outcome = Outcome::from_index(outcome.index);
+            supply.push(
+                self.share_supply
+                    .get(&(info.market_id, outcome))
+                    .await
+                    .ok()
+                    .flatten()
+                    .unwrap_or(Amount::ZERO),
+            );
+        }
+        supply
+    }
+
+    /// Get the shares of an outcome a trader holds
+    pub async fn get_position(
+        &self,
+        market_id: MarketId,
+        holder: &AccountOwner,
+        outcome: &Outcome,
+    ) -> Amount {
+        let Some(positions) = self
+            .share_positions
+            .try_load_entry(&market_id)
+            .await
+            .expect("Failed to load share positions")
+        else {
             return Amount::ZERO;
+        };
+        positions
+            .get(&(*holder, *outcome))
+            .await
+            .ok()
+            .flatten()
+            .unwrap_or(Amount::ZERO)
+    }
+
+    /// Get every trader's shares of an LMSR market
+    pub async fn get_positions(&self, market_id: MarketId) -> Vec<(AccountOwner, Outcome, Amount)> {
+        let mut positions = Vec::new();
+        if let Some(market_positions) = self
+            .share_positions
+            .try_load_entry(&market_id)
+            .await
+            .expect("Failed to load share positions")
+        {
+            market_positions
+                .for_each_index_value(|(holder, outcome), shares| {
+                    positions.push((holder, outcome, shares.into_owned()));
+                    Ok(())
+                })
+                .await
+                .expect("Failed to read share positions");
         }
+        positions
+    }
 
-        let event_id = &bet.event_id;
-        let total_pool = self.get_total_pool(event_id).await;
-        let winning_pool = self.get_pool_for_outcome(event_id, winning_outcome).await;
+    /// Record a trade with an LMSR market maker: `bought` shares issued to the trader for
+    /// `cost`, or sold back for `cost` in proceeds
+    pub async fn trade_shares(
+        &mut self,
+        market_id: MarketId,
+        trader: AccountOwner,
+        outcome: Outcome,
+        shares: Amount,
+        cost: Amount,
+        bought: bool,
+    ) {
+        let supply = self
+            .share_supply
+            .get(&(market_id, outcome))
+            .await
+            .ok()
+            .flatten()
+            .unwrap_or(Amount::ZERO);
+        let pool = self.get_total_pool(market_id).await;
+        let (supply, pool) = if bought {
+            self.add_shares(market_id, trader, outcome, shares).await;
+            (
+                supply.try_add(shares).expect("Share supply overflow"),
+                pool.try_add(cost).expect("Total pool overflow"),
+            )
+        } else {
+            self.remove_shares(market_id, trader, outcome, shares).await;
+            (
+                supply
+                    .try_sub(shares)
+                    .expect("Selling more shares than issued"),
+                pool.saturating_sub(cost),
+            )
+        };
 
-        if winning_pool == Amount::ZERO {
-            // No winners (shouldn't happen, but handle gracefully)
-            return Amount::ZERO;
+        self.share_supply
+            .insert(&(market_id, outcome), supply)
+            .expect("Failed to update share supply");
+        self.total_pools
+            .insert(&market_id, pool)
+            .expect("Failed to update total pool");
+    }
+
+    /// Credit shares of an outcome to a trader
+    pub async fn add_shares(
+        &mut self,
+        market_id: MarketId,
+        holder: AccountOwner,
+        outcome: Outcome,
+        shares: Amount,
+    ) {
+        let position = self
+            .get_position(market_id, &holder, &outcome)
+            .await
+            .try_add(shares)
+            .expect("Position overflow");
+        self.share_positions
+            .load_entry_mut(&market_id)
+            .await
+            .expect("Failed to load share positions")
+            .insert(&(holder, outcome), position)
+            .expect("Failed to update position");
+    }
+
+    /// Debit shares of an outcome from a trader, who must hold them
+    pub async fn remove_shares(
+        &mut self,
+        market_id: MarketId,
+        holder: AccountOwner,
+        outcome: Outcome,
+        shares: Amount,
+    ) {
+        let position = self
+            .get_position(market_id, &holder, &outcome)
+            .await
+            .try_sub(shares)
+            .expect("Removing more shares than held");
+        let positions = self
+            .share_positions
+            .load_entry_mut(&market_id)
+            .await
+            .expect("Failed to load share positions");
+        if position == Amount::ZERO {
+            positions
+                .remove(&(holder, outcome))
+                .expect("Failed to clear position");
+        } else {
+            positions
+                .insert(&(holder, outcome), position)
+                .expect("Failed to update position");
         }
+    }
 
-        // Payout = (bet_amount / winning_pool) * total_pool
-        // Convert to u128 for calculation
-        let bet_amount: u128 = bet.amount.into();
-        let total_pool_u128: u128 = total_pool.into();
-        let winning_pool_u128: u128 = winning_pool.into();
+    /// Fix what each share of a settled LMSR market pays out
+    pub fn set_share_value(&mut self, market_id: MarketId, outcome: Outcome, value: Amount) {
+        self.share_values
+            .insert(&(market_id, outcome), value)
+            .expect("Failed to set share value");
+    }
 
-        if winning_pool_u128 == 0 {
-            return Amount::ZERO;
+    /// Get what each share of an outcome of a settled LMSR market pays out
+    pub async fn get_share_value(&self, market_id: MarketId, outcome: &Outcome) -> Amount {
+        self.share_values
+            .get(&(market_id, *outcome))
+            .await
+            .ok()
+            .flatten()
+            .unwrap_or(Amount::ZERO)
+    }
+
+    /// Remove and return the shares a trader holds in an LMSR market
+    pub async fn take_positions(
+        &mut self,
+        info: &MarketInfo,
+        holder: AccountOwner,
+    ) -> Vec<(Outcome, Amount)> {
+        let mut positions = Vec::new();
+        for outcome in info.outcomes() {
+            let outcome = Outcome::from_index(outcome.index);
+            let shares = self.get_position(info.market_id, &holder, &outcome).await;
+            if shares > Amount::ZERO {
+                self.share_positions
+                    .load_entry_mut(&info.market_id)
+                    .await
+                    .expect("Failed to load share positions")
+                    .remove(&(holder, outcome))
+                    .expect("Failed to clear position");
+                positions.push((outcome, shares));
+            }
+        }
+        positions
+    }
+
+    /// Assign the next order ID
+    pub fn allocate_order_id(&mut self) -> u64 {
+        let next_id = self.next_order_id.get_mut();
+        let order_id = *next_id;
+        *next_id += 1;
+        order_id
+    }
+
+    /// Get an order resting on a book
+    pub async fn get_order(&self, order_id: u64) -> Option<Order> {
+        let (book_id, position) = self.orders.get(&order_id).await.ok().flatten()?;
+        let book = self
+            .order_books
+            .try_load_entry(&book_id)
+            .await
+            .expect("Failed to load order book")?;
+        book.get(&position).await.ok().flatten()
+    }
+
+    /// Store what is left of an order resting on a book
+    pub async fn update_order(&mut self, order: Order) {
+        let book_id = (order.market_id, order.outcome, order.side);
+        self.order_books
+            .load_entry_mut(&book_id)
+            .await
+            .expect("Failed to load order book")
+            .insert(&book_position(&order), order)
+            .expect("Failed to update order");
+    }
+
+    /// Get the orders resting on a book, best price first and oldest first at each price
+    pub async fn get_book(
+        &self,
+        market_id: MarketId,
+        outcome: Outcome,
+        side: OrderSide,
+    ) -> Vec<Order> {
+        let mut orders = Vec::new();
+        if let Some(book) = self
+            .order_books
+            .try_load_entry(&(market_id, outcome, side))
+            .await
+            .expect("Failed to load order book")
+        {
+            book.for_each_index_value(|_, order| {
+                orders.push(order.into_owned());
+                Ok(())
+            })
+            .await
+            .expect("Failed to read order book");
+        }
+        orders
+    }
+
+    /// Get the orders resting on a book that an order at `price` on the other side trades
+    /// with, best price first, stopping once they hold `shares`
+    pub async fn get_crossing_orders(
+        &self,
+        book_id: BookId,
+        price: Amount,
+        shares: Amount,
+    ) -> Vec<Order> {
+        let mut orders = Vec::new();
+        let Some(book) = self
+            .order_books
+            .try_load_entry(&book_id)
+            .await
+            .expect("Failed to load order book")
+        else {
+            return orders;
+        };
+        let mut held = Amount::ZERO;
+        book.for_each_index_value_while(|_, order| {
+            let crosses = match order.side {
+                OrderSide::Bid => order.price >= price,
+                OrderSide::Ask => order.price <= price,
+            };
+            if !crosses || held >= shares {
+                return Ok(false);
+            }
+            held.saturating_add_assign(order.remaining);
+            orders.push(order.into_owned());
+            Ok(true)
+        })
+        .await
+        .expect("Failed to read order book");
+        orders
+    }
+
+    /// Put an order on its book, behind the orders at a better or equal price
+    pub async fn rest_order(&mut self, order: Order) {
+        let book_id = (order.market_id, order.outcome, order.side);
+        self.orders
+            .insert(&order.order_id, (book_id, book_position(&order)))
+            .expect("Failed to index order");
+        self.owner_orders
+            .load_entry_mut(&order.owner)
+            .await
+            .expect("Failed to load owner orders")
+            .insert(&order.order_id)
+            .expect("Failed to index owner order");
+        self.update_order(order).await;
+    }
+
+    /// Take an order off its book
+    pub async fn remove_order(&mut self, order: &Order) {
+        let book_id = (order.market_id, order.outcome, order.side);
+        self.order_books
+            .load_entry_mut(&book_id)
+            .await
+            .expect("Failed to load order book")
+            .remove(&book_position(order))
+            .expect("Failed to remove order");
+        self.orders
+            .remove(&order.order_id)
+            .expect("Failed to remove order");
+        self.owner_orders
+            .load_entry_mut(&order.owner)
+            .await
+            .expect("Failed to load owner orders")
+            .remove(&order.order_id)
+            .expect("Failed to remove owner order");
+    }
+
+    /// Get every order resting on a market's books, oldest first
+    pub async fn get_orders(&self, market_info: &MarketInfo) -> Vec<Order> {
+        let mut orders = Vec::new();
+        for info in market_info.outcomes() {
+            let outcome = Outcome::from_index(info.index);
+            for side in [OrderSide::Bid, OrderSide::Ask] {
+                orders.extend(self.get_book(market_info.market_id, outcome, side).await);
+            }
+        }
+        orders.sort_by_key(|order| order.order_id);
+        orders
+    }
+
+    /// Get every order an account has resting on the order books, oldest first
+    pub async fn get_owner_orders(&self, owner: &AccountOwner) -> Vec<Order> {
+        let order_ids = match self
+            .owner_orders
+            .try_load_entry(owner)
+            .await
+            .expect("Failed to load owner orders")
+        {
+            Some(order_ids) => order_ids
+                .indices()
+                .await
+                .expect("Failed to read owner orders"),
+            None => Vec::new(),
+        };
+        let mut orders = Vec::new();
+        for order_id in order_ids {
+            if let Some(order) = self.get_order(order_id).await {
+                orders.push(order);
+            }
+        }
+        orders.sort_by_key(|order| order.order_id);
+        orders
+    }
+
+    /// Record a trade on a market's order books
+    pub async fn record_trade(&mut self, trade: Trade) {
+        let market_id = trade.market_id;
+        let count = self.get_trade_count(market_id).await;
+        self.trades
+            .insert(&(market_id, count), trade)
+            .expect("Failed to record trade");
+        self.trade_counts
+            .insert(&market_id, count + 1)
+            .expect("Failed to count trade");
+    }
+
+    /// Get the number of trades on a market's order books
+    pub async fn get_trade_count(&self, market_id: MarketId) -> u64 {
+        self.trade_counts
+            .get(&market_id)
+            .await
+            .ok()
+            .flatten()
+            .unwrap_or(0)
+    }
+
+    /// Get the latest trades on a market's order books, newest first
+    pub async fn get_recent_trades(&self, market_id: MarketId, limit: u64) -> Vec<Trade> {
+        let count = self.get_trade_count(market_id).await;
+        let mut trades = Vec::new();
+        for position in (count.saturating_sub(limit)..count).rev() {
+            if let Some(trade) = self.trades.get(&(market_id, position)).await.ok().flatten() {
+                trades.push(trade);
+            }
         }
+        trades
+    }
 
-        // Using checked math to prevent overflow
-        let numerator = bet_amount.saturating_mul(total_pool_u128);
-        let payout_u128 = numerator / winning_pool_u128;
+    /// Get all bets placed in a market
+    pub async fn get_bets(&self, market_id: MarketId) -> Vec<Bet> {
+        let mut bets = Vec::new();
+        if let Some(market_bets) = self
+            .bets
+            .try_load_entry(&market_id)
+            .await
+            .expect("Failed to load market bets")
+        {
+            market_bets
+                .for_each_index_value(|_key, bet| {
+                    bets.push(bet.into_owned());
+                    Ok(())
+                })
+                .await
+                .expect("Failed to iterate bets");
+        }
+        bets
+    }
 
-        // Convert to Amount (from_attos takes u128)
-        Amount::from_attos(payout_u128)
+    /// Get all bets for a specific outcome in a market
+    pub async fn get_bets_for_outcome(&self, market_id: MarketId, outcome: &Outcome) -> Vec<Bet> {
+        self.get_bets(market_id)
+            .await
+            .into_iter()
+            .filter(|bet| &bet.outcome == outcome)
+            .collect()
+    }
+
+    /// Record what a settled bet is owed and queue it for SettleNext
+    /// (nothing is recorded for a zero amount)
+    pub async fn record_entitlement(&mut self, bet: &Bet, amount: Amount, kind: EntitlementKind) {
+        if amount == Amount::ZERO {
+            return;
+        }
+        let mut progress = self.get_settlement_progress(bet.market_id).await;
+        self.settlement_queue
+            .insert(&(bet.market_id, progress.total), bet.key())
+            .expect("Failed to queue entitlement");
+        progress.total += 1;
+        self.set_settlement_progress(bet.market_id, progress);
+
+        let entitlement = Entitlement {
+            bet: bet.clone(),
+            amount,
+            kind,
+            claimed: false,
+        };
+        self.entitlements
+            .load_entry_mut(&bet.market_id)
+            .await
+            .expect("Failed to load market entitlements")
+            .insert(&bet.key(), entitlement)
+            .expect("Failed to record entitlement");
+    }
+
+    /// Get how far SettleNext has got through a market
+    pub async fn get_settlement_progress(&self, market_id: MarketId) -> SettlementProgress {
+        self.settlement_progress
+            .get(&market_id)
+            .await
+            .ok()
+            .flatten()
+            .map(|progress| progress.to_owned())
+            .unwrap_or_default()
+    }
+
+    /// Store a market's settlement cursor
+    pub fn set_settlement_progress(&mut self, market_id: MarketId, progress: SettlementProgress) {
+        self.settlement_progress
+            .insert(&market_id, progress)
+            .expect("Failed to update settlement progress");
+    }
+
+    /// Get the bet at a position of a market's settlement order
+    pub async fn get_queued_bet(&self, market_id: MarketId, position: u64) -> Option<BetKey> {
+        self.settlement_queue
+            .get(&(market_id, position))
+            .await
+            .ok()
+            .flatten()
+            .map(|key| key.to_owned())
+    }
+
+    /// Get what a settled bet is owed
+    pub async fn get_entitlement(&self, market_id: MarketId, key: &BetKey) -> Option<Entitlement> {
+        self.entitlements
+            .try_load_entry(&market_id)
+            .await
+            .expect("Failed to load market entitlements")?
+            .get(key)
+            .await
+            .ok()
+            .flatten()
+    }
+
+    /// Mark a bet's entitlement as paid out
+    pub async fn mark_claimed(&mut self, mut entitlement: Entitlement) {
+        entitlement.claimed = true;
+        self.entitlements
+            .load_entry_mut(&entitlement.bet.market_id)
+            .await
+            .expect("Failed to load market entitlements")
+            .insert(&entitlement.bet.key(), entitlement)
+            .expect("Failed to mark entitlement claimed");
+    }
+
+    /// Get every entitlement recorded for a market
+    pub async fn get_entitlements(&self, market_id: MarketId) -> Vec<Entitlement> {
+        let mut entitlements = Vec::new();
+        if let Some(market_entitlements) = self
+            .entitlements
+            .try_load_entry(&market_id)
+            .await
+            .expect("Failed to load market entitlements")
+        {
+            market_entitlements
+                .for_each_index_value(|_key, entitlement| {
+                    entitlements.push(entitlement.into_owned());
+                    Ok(())
+                })
+                .await
+                .expect("Failed to iterate entitlements");
+        }
+        entitlements
+    }
+
+    /// Get bet count for a market
+    pub async fn get_bet_count(&self, market_id: MarketId) -> u64 {
+        self.bet_counts
+            .get(&market_id)
+            .await
+            .ok()
+            .flatten()
+            .map(|c| c.to_owned())
+            .unwrap_or(0)
     }
 }
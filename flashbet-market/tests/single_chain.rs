@@ -5,35 +5,563 @@
 
 #![cfg(not(target_arch = "wasm32"))]
 
-use flashbet_market::Operation;
-use linera_sdk::test::{QueryOutcome, TestValidator};
+use std::collections::BTreeMap;
 
-/// Tests setting and incrementing a counter
+use flashbet_market::{CreateMarketInput, FlashbetMarketAbi, InstantiationArgument, Operation};
+use flashbet_shared::{
+    BetKey, EventId, EventResult, MarketId, MarketRef, MarketTypeInput, Outcome, PricingMode,
+    RakeBase,
+};
+use flashbet_token::FlashbetTokenAbi;
+use flashbet_user::FlashbetUserAbi;
+use linera_sdk::{
+    abis::fungible::InitialState,
+    linera_base_types::{AccountOwner, Amount, ApplicationId, ChainId, Timestamp},
+    test::{ActiveChain, BlockBuilder, MessageAction, QueryOutcome, TestValidator},
+};
+
+/// Tests creating a market
 ///
-/// Creates the application on a `chain`, initializing it with a 10 then add 10 and obtain 20.
-/// which is then checked.
+/// Creates the oracle on its own chain, the BET token and the market application on a
+/// `chain`, then creates an over/under market and checks that it is open with an empty pool
+/// and exposes its line.
 #[tokio::test(flavor = "multi_thread")]
 async fn single_chain_test() {
-    let (validator, module_id) =
-        TestValidator::with_current_module::<flashbet_market::FlashbetMarketAbi, (), u64>().await;
-    let mut chain = validator.new_chain().await;
+    let (_validator, chain, _owner, _bet_token_id, application_id) = create_apps(0).await;
 
-    let initial_state = 10u64;
-    let application_id = chain
-        .create_application(module_id, (), initial_state, vec![])
+    chain
+        .add_block(|block| {
+            block.with_operation(
+                application_id,
+                Operation::CreateMarket {
+                    input: Box::new(market_input(MarketTypeInput::OverUnder, Some(850))),
+                },
+            );
+        })
+        .await;
+
+    let QueryOutcome { response, .. } = chain
+        .graphql_query(
+            application_id,
+            "query { allMarkets marketId(eventId: \"mlb_game_001\") isOpen(eventId: \"mlb_game_001\") totalPool marketType line outcomes { index label odds } }",
+        )
+        .await;
+
+    assert_eq!(response["allMarkets"][0].as_str(), Some("mlb_game_001"));
+    assert_eq!(response["marketId"].as_u64(), Some(0));
+    assert_eq!(response["isOpen"].as_bool(), Some(true));
+    assert_eq!(response["totalPool"].as_str(), Some("0."));
+    assert_eq!(response["marketType"].as_str(), Some("OverUnder"));
+    assert_eq!(response["line"].as_f64(), Some(8.5));
+    assert_eq!(response["outcomes"][1]["index"].as_u64(), Some(1));
+    assert_eq!(response["outcomes"][1]["label"].as_str(), Some("Under"));
+    assert_eq!(response["outcomes"][1]["odds"].as_f64(), Some(1.0));
+}
+
+/// Tests paying out of the market's escrow with the real BET token
+///
+/// The chain owner, who is also the oracle, funds the bankroll and buys Home shares of an
+/// LMSR market. Once the result is in, redeeming the shares and withdrawing the bankroll
+/// pay everything held in the market's escrow back to the owner.
+#[tokio::test(flavor = "multi_thread")]
+async fn lmsr_market_settles_through_bet_token() {
+    let (_validator, chain, owner, bet_token_id, application_id) = create_apps(5).await;
+
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::FundBankroll {
+                        amount: Amount::from_tokens(2),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        input: Box::new(CreateMarketInput {
+                            pricing_mode: Some(PricingMode::Lmsr),
+                            liquidity: Some(Amount::ONE),
+                            ..market_input(MarketTypeInput::MatchWinner, None)
+                        }),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::BuyShares {
+                        market_id: MarketId(0),
+                        outcome: Outcome::Home,
+                        shares: Amount::ONE,
+                        max_cost: Amount::ONE,
+                    },
+                );
+        })
         .await;
 
-    let increment = 10u64;
+    let balance = balance_of(&chain, bet_token_id, owner).await;
+    let cost = Amount::from_tokens(3).saturating_sub(balance);
+    assert!(cost > Amount::ZERO && cost < Amount::ONE);
+    assert_eq!(
+        escrow_balance(&chain, application_id).await,
+        Amount::from_tokens(2).saturating_add(cost)
+    );
+
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::ProcessOracleResult {
+                        result: EventResult {
+                            event_id: EventId::new("mlb_game_001"),
+                            outcome: Outcome::Home,
+                            score: None,
+                            timestamp: Timestamp::from(0),
+                        },
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::RedeemShares {
+                        market_id: MarketId(0),
+                    },
+                );
+        })
+        .await;
+
+    // The winning share paid 1 BET, and what is left in escrow is the free bankroll
+    assert_eq!(
+        balance_of(&chain, bet_token_id, owner).await,
+        balance.saturating_add(Amount::ONE)
+    );
+    let bankroll = escrow_balance(&chain, application_id).await;
+    assert_eq!(bankroll, Amount::ONE.saturating_add(cost));
+
     chain
         .add_block(|block| {
-            block.with_operation(application_id, Operation::Increment { value: increment });
+            block.with_operation(
+                application_id,
+                Operation::WithdrawBankroll {
+                    to: owner,
+                    amount: bankroll,
+                },
+            );
+        })
+        .await;
+
+    assert_eq!(escrow_balance(&chain, application_id).await, Amount::ZERO);
+    assert_eq!(
+        balance_of(&chain, bet_token_id, owner).await,
+        Amount::from_tokens(5)
+    );
+}
+
+/// Tests placing a bet from a User chain
+///
+/// A bettor claims BET on their own chain and bets through the flashbet-user application,
+/// whose instance on the market chain registers the bet with the market. The stake ends up
+/// in the market's escrow and in the pool.
+#[tokio::test(flavor = "multi_thread")]
+async fn bet_from_user_chain_enters_pool() {
+    let (validator, market_chain, _owner, bet_token_id, application_id) = create_apps(0).await;
+    let (user_chain, user_app_id) = create_user_app(&validator, bet_token_id, application_id).await;
+
+    market_chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::SetUserApp {
+                        user_app_id: user_app_id.forget_abi(),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        input: Box::new(market_input(MarketTypeInput::MatchWinner, None)),
+                    },
+                );
+        })
+        .await;
+
+    user_chain
+        .add_block(|block| place_bet(block, bet_token_id, user_app_id, market_chain.id()))
+        .await;
+    market_chain.handle_received_messages().await;
+
+    let QueryOutcome { response, .. } = market_chain
+        .graphql_query(application_id, "query { totalPool betCount }")
+        .await;
+    assert_eq!(parse_amount(&response["totalPool"]), Amount::from_tokens(2));
+    assert_eq!(response["betCount"].as_u64(), Some(1));
+    assert_eq!(
+        escrow_balance(&market_chain, application_id).await,
+        Amount::from_tokens(2)
+    );
+}
+
+/// Tests a bet the market chain fails to process
+///
+/// The market was never told which User application may register bets, so the market chain
+/// rejects the bet. The stake's transfer bounces back with it: the bettor gets their BET
+/// back, nothing is left in the market's escrow and the bet is closed as a refund.
+#[tokio::test(flavor = "multi_thread")]
+async fn bounced_bet_returns_stake() {
+    let (validator, market_chain, _owner, bet_token_id, application_id) = create_apps(0).await;
+    let (user_chain, user_app_id) = create_user_app(&validator, bet_token_id, application_id).await;
+    let bettor = AccountOwner::from(user_chain.public_key());
+
+    market_chain
+        .add_block(|block| {
+            block.with_operation(
+                application_id,
+                Operation::CreateMarket {
+                    input: Box::new(market_input(MarketTypeInput::MatchWinner, None)),
+                },
+            );
+        })
+        .await;
+
+    let (certificate, _) = user_chain
+        .add_block(|block| place_bet(block, bet_token_id, user_app_id, market_chain.id()))
+        .await;
+    assert_eq!(
+        balance_of(&user_chain, bet_token_id, bettor).await,
+        Amount::from_tokens(3)
+    );
+
+    market_chain
+        .add_block(|block| {
+            block.with_messages_from_by_action(&certificate, MessageAction::Reject);
+        })
+        .await;
+    user_chain.handle_received_messages().await;
+
+    assert_eq!(
+        balance_of(&user_chain, bet_token_id, bettor).await,
+        Amount::from_tokens(5)
+    );
+    assert_eq!(
+        escrow_balance(&market_chain, application_id).await,
+        Amount::ZERO
+    );
+    let QueryOutcome { response, .. } = user_chain
+        .graphql_query(
+            user_app_id,
+            "query { activeBets { betId } refundHistory { amount } }",
+        )
+        .await;
+    assert_eq!(response["activeBets"].as_array().map(Vec::len), Some(0));
+    assert_eq!(
+        parse_amount(&response["refundHistory"][0]["amount"]),
+        Amount::from_tokens(2)
+    );
+}
+
+/// Tests notifying a User chain of how its bets went
+///
+/// Of three bets placed through flashbet-user, the market turns down one whose minimum odds
+/// are out of reach, and its User application reports that back to the bettor's chain. Once
+/// the winning bet is claimed, the bettor's chain reads the settlement and payout notices
+/// the market published for it, and closes every bet.
+#[tokio::test(flavor = "multi_thread")]
+async fn user_chain_receives_notices() {
+    let (validator, market_chain, _owner, bet_token_id, application_id) = create_apps(0).await;
+    let (user_chain, user_app_id) = create_user_app(&validator, bet_token_id, application_id).await;
+    let bettor = AccountOwner::from(user_chain.public_key());
+
+    market_chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::SetUserApp {
+                        user_app_id: user_app_id.forget_abi(),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        input: Box::new(market_input(MarketTypeInput::MatchWinner, None)),
+                    },
+                );
+        })
+        .await;
+
+    user_chain
+        .add_block(|block| {
+            place_bet(block, bet_token_id, user_app_id, market_chain.id());
+            for min_payout_bps in [None, Some(1_000_000)] {
+                block.with_operation(
+                    user_app_id,
+                    flashbet_user::Operation::PlaceBet {
+                        market_chain: market_chain.id(),
+                        market_id: MarketId(0),
+                        event_id: EventId::new("mlb_game_001"),
+                        outcome: Outcome::Away,
+                        amount: Amount::ONE,
+                        min_payout_bps,
+                    },
+                );
+            }
+        })
+        .await;
+    market_chain.handle_received_messages().await;
+
+    market_chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::ProcessOracleResult {
+                        result: EventResult {
+                            event_id: EventId::new("mlb_game_001"),
+                            outcome: Outcome::Home,
+                            score: None,
+                            timestamp: Timestamp::from(0),
+                        },
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::ClaimBatch {
+                        market: MarketRef::MarketId(MarketId(0)),
+                        bets: vec![BetKey {
+                            user_chain: user_chain.id(),
+                            bet_id: 0,
+                        }],
+                    },
+                );
         })
         .await;
+    user_chain.handle_received_messages().await;
+    user_chain.handle_new_events().await;
 
-    let final_value = initial_state + increment;
-    let QueryOutcome { response, .. } =
-        chain.graphql_query(application_id, "query { value }").await;
-    let state_value = response["value"].as_u64().expect("Failed to get the u64");
+    // The rejected stake and the whole pool of 3 came back
+    assert_eq!(
+        balance_of(&user_chain, bet_token_id, bettor).await,
+        Amount::from_tokens(5)
+    );
+    let QueryOutcome { response, .. } = user_chain
+        .graphql_query(
+            user_app_id,
+            "query { activeBets { betId } settledBets { betId status amount } \
+             payoutHistory { betId amount } refundHistory { betId amount } }",
+        )
+        .await;
+    assert_eq!(response["activeBets"].as_array().map(Vec::len), Some(0));
+    let settled = response["settledBets"]
+        .as_array()
+        .expect("Missing settled bets")
+        .iter()
+        .map(|notice| {
+            (
+                notice["betId"].as_u64(),
+                notice["status"].as_str(),
+                parse_amount(&notice["amount"]),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        settled,
+        vec![
+            (Some(0), Some("WON"), Amount::from_tokens(3)),
+            (Some(1), Some("LOST"), Amount::ZERO),
+        ]
+    );
+    assert_eq!(response["payoutHistory"][0]["betId"].as_u64(), Some(0));
+    assert_eq!(
+        parse_amount(&response["payoutHistory"][0]["amount"]),
+        Amount::from_tokens(3)
+    );
+    assert_eq!(response["refundHistory"][0]["betId"].as_u64(), Some(2));
+    assert_eq!(
+        parse_amount(&response["refundHistory"][0]["amount"]),
+        Amount::ONE
+    );
+}
+
+/// Creates the oracle on its own chain, then the BET token and the market application on a
+/// new chain, whose owner is given `tokens` BET and may submit oracle results
+async fn create_apps(
+    tokens: u128,
+) -> (
+    TestValidator,
+    ActiveChain,
+    AccountOwner,
+    ApplicationId<FlashbetTokenAbi>,
+    ApplicationId<FlashbetMarketAbi>,
+) {
+    let (validator, module_id) =
+        TestValidator::with_current_module::<FlashbetMarketAbi, (), InstantiationArgument>().await;
+    let mut chain = validator.new_chain().await;
+    let mut oracle_chain = validator.new_chain().await;
+    let owner = AccountOwner::from(chain.public_key());
+
+    let oracle_module_id = oracle_chain
+        .publish_bytecode_files_in::<
+            flashbet_oracle::FlashbetOracleAbi,
+            (),
+            flashbet_oracle::InstantiationArgument,
+        >("../flashbet-oracle")
+        .await;
+    let oracle_app_id = oracle_chain
+        .create_application(
+            oracle_module_id,
+            (),
+            flashbet_oracle::InstantiationArgument {
+                initial_oracles: vec![AccountOwner::from(oracle_chain.public_key())],
+            },
+            vec![],
+        )
+        .await;
+
+    let token_module_id = chain
+        .publish_bytecode_files_in::<FlashbetTokenAbi, (), InitialState>("../flashbet-token")
+        .await;
+    let mut accounts = BTreeMap::new();
+    if tokens > 0 {
+        accounts.insert(owner, Amount::from_tokens(tokens));
+    }
+    let bet_token_id = chain
+        .create_application(token_module_id, (), InitialState { accounts }, vec![])
+        .await;
+
+    let application_id = chain
+        .create_application(
+            module_id,
+            (),
+            InstantiationArgument {
+                oracle_chain: oracle_chain.id(),
+                oracle_app_id: oracle_app_id.forget_abi().to_string(),
+                bet_token_id: bet_token_id.forget_abi(),
+                betting_cutoff_secs: 0,
+                treasury: None,
+                oracle_keys: vec![owner],
+                rake_bps: 0,
+                rake_base: RakeBase::default(),
+                market_creators: vec![],
+                settlers: vec![],
+                pausers: vec![],
+                price_setters: vec![],
+            },
+            vec![],
+        )
+        .await;
+
+    (validator, chain, owner, bet_token_id, application_id)
+}
+
+/// Creates the flashbet-user application on a new bettor's chain
+async fn create_user_app(
+    validator: &TestValidator,
+    bet_token_id: ApplicationId<FlashbetTokenAbi>,
+    application_id: ApplicationId<FlashbetMarketAbi>,
+) -> (ActiveChain, ApplicationId<FlashbetUserAbi>) {
+    let mut user_chain = validator.new_chain().await;
+    let user_module_id = user_chain
+        .publish_bytecode_files_in::<FlashbetUserAbi, (), flashbet_user::InstantiationArgument>(
+            "../flashbet-user",
+        )
+        .await;
+    let user_app_id = user_chain
+        .create_application(
+            user_module_id,
+            (),
+            flashbet_user::InstantiationArgument {
+                bet_token_id: bet_token_id.forget_abi(),
+                market_app_id: application_id.forget_abi(),
+            },
+            vec![],
+        )
+        .await;
+    (user_chain, user_app_id)
+}
+
+/// Has the chain owner claim 5 BET and bet 2 of them on Home in the test market
+fn place_bet(
+    block: &mut BlockBuilder,
+    bet_token_id: ApplicationId<FlashbetTokenAbi>,
+    user_app_id: ApplicationId<FlashbetUserAbi>,
+    market_chain: ChainId,
+) {
+    block
+        .with_operation(
+            bet_token_id,
+            flashbet_token::Operation::Claim {
+                amount: Amount::from_tokens(5),
+            },
+        )
+        .with_operation(
+            user_app_id,
+            flashbet_user::Operation::PlaceBet {
+                market_chain,
+                market_id: MarketId(0),
+                event_id: EventId::new("mlb_game_001"),
+                outcome: Outcome::Home,
+                amount: Amount::from_tokens(2),
+                min_payout_bps: None,
+            },
+        );
+}
+
+/// A market on the test game, which never reaches its betting cutoff
+fn market_input(market_type: MarketTypeInput, line: Option<i32>) -> CreateMarketInput {
+    CreateMarketInput {
+        event_id: "mlb_game_001".to_string(),
+        description: "Yankees vs Red Sox".to_string(),
+        event_time: Timestamp::from(u64::MAX),
+        market_type,
+        line,
+        outcomes: None,
+        home_team: "Yankees".to_string(),
+        away_team: "Red Sox".to_string(),
+        no_winner_policy: None,
+        no_winner_fee_bps: None,
+        rake_bps: None,
+        rake_base: None,
+        min_stake: None,
+        max_stake: None,
+        max_user_exposure: None,
+        max_liability: None,
+        pricing_mode: None,
+        liquidity: None,
+    }
+}
+
+/// BET token balance of `owner` on `chain`
+async fn balance_of(
+    chain: &ActiveChain,
+    bet_token_id: ApplicationId<FlashbetTokenAbi>,
+    owner: AccountOwner,
+) -> Amount {
+    let QueryOutcome { response, .. } = chain
+        .graphql_query(
+            bet_token_id,
+            format!(
+                "query {{ accounts {{ entry(key: \"{}\") {{ value }} }} }}",
+                owner
+            ),
+        )
+        .await;
+    parse_amount(&response["accounts"]["entry"]["value"])
+}
+
+/// BET held in the market's escrow on `chain`
+async fn escrow_balance(
+    chain: &ActiveChain,
+    application_id: ApplicationId<FlashbetMarketAbi>,
+) -> Amount {
+    let QueryOutcome { response, .. } = chain
+        .graphql_query(application_id, "query { escrowBalance }")
+        .await;
+    parse_amount(&response["escrowBalance"])
+}
 
-    assert_eq!(state_value, final_value);
+fn parse_amount(value: &serde_json::Value) -> Amount {
+    value
+        .as_str()
+        .unwrap_or("0")
+        .parse()
+        .expect("Failed to parse amount")
 }
@@ -2,7 +2,7 @@
 
 mod state;
 
-use flashbet_oracle::{InstantiationArgument, Message, Operation};
+use flashbet_oracle::{InstantiationArgument, Operation, OperationResponse};
 use flashbet_shared::OracleEvent;
 use linera_sdk::{
     linera_base_types::{StreamName, WithContractAbi},
@@ -24,7 +24,9 @@ impl WithContractAbi for FlashbetOracleContract {
 }
 
 impl Contract for FlashbetOracleContract {
-    type Message = Message;
+    // Wave 1: No cross-chain messages received
+    // Markets subscribe to Oracle events via event streams
+    type Message = ();
     type Parameters = ();
     type InstantiationArgument = InstantiationArgument;
     type EventValue = OracleEvent;
@@ -137,7 +139,17 @@ impl Contract for FlashbetOracleContract {
                     .remove(&oracle)
                     .expect("Failed to revoke oracle");
             }
+
+            Operation::ReadResultEvent { chain_id, index } => {
+                return OperationResponse::ResultEvent(self.runtime.read_event(
+                    chain_id,
+                    StreamName::from(b"oracle_results".to_vec()),
+                    index,
+                ));
+            }
         }
+
+        OperationResponse::Ok
     }
 
     async fn execute_message(&mut self, _message: Self::Message) {
@@ -151,30 +163,94 @@ impl Contract for FlashbetOracleContract {
 
 #[cfg(test)]
 mod tests {
+    use flashbet_shared::{EventId, EventResult, OracleEvent, Outcome};
     use futures::FutureExt as _;
-    use linera_sdk::{util::BlockingWait, views::View, Contract, ContractRuntime};
+    use linera_sdk::{
+        linera_base_types::{AccountOwner, ChainId, CryptoHash, StreamName, Timestamp},
+        util::BlockingWait,
+        views::View,
+        Contract, ContractRuntime,
+    };
 
-    use flashbet_oracle::Operation;
+    use flashbet_oracle::{InstantiationArgument, Operation, OperationResponse};
 
     use super::{FlashbetOracleContract, FlashbetOracleState};
 
     #[test]
-    fn operation() {
-        let initial_value = 10u64;
-        let mut app = create_and_instantiate_app(initial_value);
+    fn publish_result() {
+        let mut app = create_and_instantiate_app();
+
+        let result = EventResult {
+            event_id: EventId::new("mlb_game_001"),
+            outcome: Outcome::Home,
+            score: None,
+            timestamp: Timestamp::from(0),
+        };
 
-        let increment = 10u64;
+        app.execute_operation(Operation::PublishResult {
+            result: result.clone(),
+        })
+        .blocking_wait();
 
-        let _response = app
-            .execute_operation(Operation::Increment { value: increment })
-            .now_or_never()
-            .expect("Execution of application operation should not await anything");
+        assert_eq!(*app.state.result_count.get(), 1);
+        assert_eq!(
+            app.state.get_result(&result.event_id).blocking_wait(),
+            Some(result)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Unauthorized oracle")]
+    fn publish_result_from_unauthorized_oracle() {
+        let mut app = create_and_instantiate_app();
+        app.runtime
+            .set_authenticated_signer(Some(AccountOwner::from([2; 32])));
+
+        app.execute_operation(Operation::PublishResult {
+            result: EventResult {
+                event_id: EventId::new("mlb_game_001"),
+                outcome: Outcome::Away,
+                score: None,
+                timestamp: Timestamp::from(0),
+            },
+        })
+        .blocking_wait();
+    }
 
-        assert_eq!(*app.state.value.get(), initial_value + increment);
+    #[test]
+    fn read_result_event() {
+        let mut app = create_and_instantiate_app();
+        let oracle_chain = ChainId(CryptoHash::test_hash("oracle_chain"));
+        let event = OracleEvent::ResultPublished {
+            result: EventResult {
+                event_id: EventId::new("mlb_game_001"),
+                outcome: Outcome::Draw,
+                score: None,
+                timestamp: Timestamp::from(0),
+            },
+        };
+        app.runtime.add_event(
+            oracle_chain,
+            StreamName::from(b"oracle_results".to_vec()),
+            0,
+            &linera_sdk::bcs::to_bytes(&event).unwrap(),
+        );
+
+        let response = app
+            .execute_operation(Operation::ReadResultEvent {
+                chain_id: oracle_chain,
+                index: 0,
+            })
+            .blocking_wait();
+
+        assert!(matches!(response, OperationResponse::ResultEvent(read) if read == event));
     }
 
-    fn create_and_instantiate_app(initial_value: u64) -> FlashbetOracleContract {
-        let runtime = ContractRuntime::new().with_application_parameters(());
+    fn create_and_instantiate_app() -> FlashbetOracleContract {
+        let owner = AccountOwner::from([1; 32]);
+        let runtime = ContractRuntime::new()
+            .with_application_parameters(())
+            .with_authenticated_signer(owner);
         let mut contract = FlashbetOracleContract {
             state: FlashbetOracleState::load(runtime.root_view_storage_context())
                 .blocking_wait()
@@ -183,11 +259,13 @@ mod tests {
         };
 
         contract
-            .instantiate(initial_value)
+            .instantiate(InstantiationArgument {
+                initial_oracles: vec![owner],
+            })
             .now_or_never()
             .expect("Initialization of application state should not await anything");
 
-        assert_eq!(*contract.state.value.get(), initial_value);
+        assert!(contract.state.is_owner(&owner));
 
         contract
     }
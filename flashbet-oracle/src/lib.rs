@@ -3,10 +3,10 @@
 //! Publishes verified sports event results to be consumed by Market Chains.
 
 use async_graphql::{Request, Response};
-use flashbet_shared::EventResult;
+use flashbet_shared::{EventResult, OracleEvent};
 use linera_sdk::{
     graphql::GraphQLMutationRoot,
-    linera_base_types::{AccountOwner, ContractAbi, ServiceAbi},
+    linera_base_types::{AccountOwner, ChainId, ContractAbi, ServiceAbi},
 };
 use serde::{Deserialize, Serialize};
 
@@ -14,7 +14,7 @@ pub struct FlashbetOracleAbi;
 
 impl ContractAbi for FlashbetOracleAbi {
     type Operation = Operation;
-    type Response = ();
+    type Response = OperationResponse;
 }
 
 impl ServiceAbi for FlashbetOracleAbi {
@@ -22,6 +22,15 @@ impl ServiceAbi for FlashbetOracleAbi {
     type QueryResponse = Response;
 }
 
+/// Response types for Oracle operations
+#[derive(Debug, Serialize, Deserialize)]
+pub enum OperationResponse {
+    /// Event read from an `oracle_results` stream
+    ResultEvent(OracleEvent),
+    /// Generic success response
+    Ok,
+}
+
 /// Operations that can be performed on the Oracle Chain
 #[derive(Debug, Deserialize, Serialize, GraphQLMutationRoot)]
 pub enum Operation {
@@ -33,13 +42,10 @@ pub enum Operation {
 
     /// Revoke oracle authorization (only owner can call this)
     RevokeOracle { oracle: AccountOwner },
-}
 
-/// Messages received by the Oracle Chain
-#[derive(Debug, Deserialize, Serialize)]
-pub enum Message {
-    // Wave 1: No cross-chain messages received
-    // Markets subscribe to Oracle events via event streams
+    /// Read an event from an Oracle chain's `oracle_results` stream
+    /// Called by Market Chains, which can only read their own application's events
+    ReadResultEvent { chain_id: ChainId, index: u32 },
 }
 
 /// Instantiation argument for Oracle Chain
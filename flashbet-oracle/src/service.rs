@@ -6,10 +6,10 @@ use std::sync::Arc;
 
 use async_graphql::{EmptySubscription, Object, Schema};
 use flashbet_oracle::Operation;
-use flashbet_shared::{EventId, EventResult, Outcome};
+use flashbet_shared::{EventId, EventResult};
 use linera_sdk::{
     graphql::GraphQLMutationRoot,
-    linera_base_types::{AccountOwner, Timestamp, WithServiceAbi},
+    linera_base_types::{AccountOwner, WithServiceAbi},
     views::View,
     Service, ServiceRuntime,
 };
@@ -41,7 +41,7 @@ impl Service for FlashbetOracleService {
     }
 
     async fn handle_query(&self, query: Self::Query) -> Self::QueryResponse {
-        let owner = self.state.owner.get().as_ref().expect("Owner not set").clone();
+        let owner = self.state.owner.get().expect("Owner not set");
         let result_count = *self.state.result_count.get();
 
         // Get all event results
@@ -60,7 +60,7 @@ impl Service for FlashbetOracleService {
         self.state
             .authorized_oracles
             .for_each_index(|oracle| {
-                authorized_oracles.push(oracle.clone());
+                authorized_oracles.push(oracle);
                 Ok(())
             })
             .await
@@ -171,30 +171,30 @@ mod tests {
     use std::sync::Arc;
 
     use async_graphql::{Request, Response, Value};
-    use futures::FutureExt as _;
-    use linera_sdk::{util::BlockingWait, views::View, Service, ServiceRuntime};
+    use linera_sdk::{
+        linera_base_types::AccountOwner, util::BlockingWait, views::View, Service, ServiceRuntime,
+    };
     use serde_json::json;
 
     use super::{FlashbetOracleService, FlashbetOracleState};
 
     #[test]
     fn query() {
-        let value = 60u64;
         let runtime = Arc::new(ServiceRuntime::<FlashbetOracleService>::new());
         let mut state = FlashbetOracleState::load(runtime.root_view_storage_context())
             .blocking_wait()
             .expect("Failed to read from mock key value store");
-        state.value.set(value);
+        state.owner.set(Some(AccountOwner::CHAIN));
+        state.result_count.set(3);
 
         let service = FlashbetOracleService { state, runtime };
-        let request = Request::new("{ value }");
+        let request = Request::new("{ resultCount }");
 
         let response = service
             .handle_query(request)
-            .now_or_never()
-            .expect("Query should not await anything");
+            .blocking_wait();
 
-        let expected = Response::new(Value::from_json(json!({"value": 60})).unwrap());
+        let expected = Response::new(Value::from_json(json!({"resultCount": 3})).unwrap());
 
         assert_eq!(response, expected)
     }
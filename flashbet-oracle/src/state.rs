@@ -25,6 +25,8 @@ pub struct FlashbetOracleState {
     pub result_count: RegisterView<u64>,
 }
 
+// Compiled into both the contract and service binaries, each of which uses a subset
+#[allow(dead_code)]
 impl FlashbetOracleState {
     /// Check if an oracle is authorized
     pub async fn is_authorized(&self, oracle: &AccountOwner) -> bool {
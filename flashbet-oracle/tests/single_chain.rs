@@ -5,35 +5,59 @@
 
 #![cfg(not(target_arch = "wasm32"))]
 
-use flashbet_oracle::Operation;
-use linera_sdk::test::{QueryOutcome, TestValidator};
-
-/// Tests setting and incrementing a counter
+use flashbet_oracle::{InstantiationArgument, Operation};
+use flashbet_shared::{EventId, EventResult, Outcome, Score};
+use linera_sdk::{
+    linera_base_types::{AccountOwner, Timestamp},
+    test::{QueryOutcome, TestValidator},
+};
+
+/// Tests publishing an event result
 ///
-/// Creates the application on a `chain`, initializing it with a 10 then add 10 and obtain 20.
-/// which is then checked.
+/// Creates the application on a `chain` with the chain owner as the only authorized oracle,
+/// publishes a result and checks that it can be queried back.
 #[tokio::test(flavor = "multi_thread")]
 async fn single_chain_test() {
-    let (validator, module_id) =
-        TestValidator::with_current_module::<flashbet_oracle::FlashbetOracleAbi, (), u64>().await;
+    let (validator, module_id) = TestValidator::with_current_module::<
+        flashbet_oracle::FlashbetOracleAbi,
+        (),
+        InstantiationArgument,
+    >()
+    .await;
     let mut chain = validator.new_chain().await;
+    let oracle = AccountOwner::from(chain.public_key());
 
-    let initial_state = 10u64;
     let application_id = chain
-        .create_application(module_id, (), initial_state, vec![])
+        .create_application(
+            module_id,
+            (),
+            InstantiationArgument {
+                initial_oracles: vec![oracle],
+            },
+            vec![],
+        )
         .await;
 
-    let increment = 10u64;
+    let result = EventResult {
+        event_id: EventId::new("mlb_game_001"),
+        outcome: Outcome::Home,
+        score: Some(Score { home: 5, away: 3 }),
+        timestamp: Timestamp::from(0),
+    };
     chain
         .add_block(|block| {
-            block.with_operation(application_id, Operation::Increment { value: increment });
+            block.with_operation(application_id, Operation::PublishResult { result });
         })
         .await;
 
-    let final_value = initial_state + increment;
-    let QueryOutcome { response, .. } =
-        chain.graphql_query(application_id, "query { value }").await;
-    let state_value = response["value"].as_u64().expect("Failed to get the u64");
+    let QueryOutcome { response, .. } = chain
+        .graphql_query(
+            application_id,
+            "query { resultCount getResult(eventId: \"mlb_game_001\") { outcome scoreHome } }",
+        )
+        .await;
 
-    assert_eq!(state_value, final_value);
+    assert_eq!(response["resultCount"].as_u64(), Some(1));
+    assert_eq!(response["getResult"]["outcome"].as_str(), Some("Home"));
+    assert_eq!(response["getResult"]["scoreHome"].as_u64(), Some(5));
 }
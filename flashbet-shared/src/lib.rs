@@ -3,8 +3,8 @@
 //! This module contains all shared data structures, enums, and message types
 //! used across the User, Market, and Oracle chains.
 
-use async_graphql::{Enum, InputObject, Scalar, ScalarType, SimpleObject, Value};
-use linera_sdk::linera_base_types::{AccountOwner, Amount, ChainId, Timestamp};
+use async_graphql::{Enum, InputObject, OneofObject, Scalar, ScalarType, SimpleObject, Value};
+use linera_sdk::linera_base_types::{AccountOwner, Amount, ChainId, StreamName, Timestamp};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
@@ -12,8 +12,8 @@ use std::fmt;
 // Core Types
 // ============================================================================
 
-/// Unique identifier for a prediction market
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// Unique identifier for a prediction market, assigned in creation order by its Market chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct MarketId(pub u64);
 
 impl fmt::Display for MarketId {
@@ -58,6 +58,27 @@ impl fmt::Display for EventId {
     }
 }
 
+/// Reference to markets by market ID or by event ID
+///
+/// Several markets (e.g. a winner market and an over/under) can share one event, so an event ID
+/// refers to every market on that event.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, OneofObject)]
+pub enum MarketRef {
+    /// A single market
+    MarketId(MarketId),
+    /// Every market on an event
+    EventId(EventId),
+}
+
+impl fmt::Display for MarketRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MarketRef::MarketId(market_id) => write!(f, "{}", market_id),
+            MarketRef::EventId(event_id) => write!(f, "{}", event_id),
+        }
+    }
+}
+
 // GraphQL scalar implementation for EventId
 #[Scalar]
 impl ScalarType for EventId {
@@ -75,7 +96,10 @@ impl ScalarType for EventId {
 }
 
 /// Possible outcomes for a prediction market
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Enum)]
+///
+/// Outcomes are also numbered by index (Home=0, Away=1, Draw=2), so markets with named
+/// outcomes can use any index; indices from 3 up are `Other`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Outcome {
     /// Home team / Team A wins
     Home,
@@ -83,6 +107,35 @@ pub enum Outcome {
     Away,
     /// Tie/Draw outcome
     Draw,
+    /// Named outcome at this index (3 and up) of a MultiOutcome market
+    Other(u32),
+}
+
+impl Outcome {
+    /// Outcome at an index of a market's outcome list
+    pub fn from_index(index: u32) -> Self {
+        match index {
+            0 => Outcome::Home,
+            1 => Outcome::Away,
+            2 => Outcome::Draw,
+            index => Outcome::Other(index),
+        }
+    }
+
+    /// Index of this outcome in a market's outcome list
+    pub fn index(&self) -> u32 {
+        match self {
+            Outcome::Home => 0,
+            Outcome::Away => 1,
+            Outcome::Draw => 2,
+            Outcome::Other(index) => *index,
+        }
+    }
+
+    /// Check this is the canonical form of its index (`Other` is never used for 0-2)
+    pub fn is_canonical(&self) -> bool {
+        Outcome::from_index(self.index()) == *self
+    }
 }
 
 impl fmt::Display for Outcome {
@@ -91,10 +144,62 @@ impl fmt::Display for Outcome {
             Outcome::Home => write!(f, "Home Win"),
             Outcome::Away => write!(f, "Away Win"),
             Outcome::Draw => write!(f, "Draw"),
+            Outcome::Other(index) => write!(f, "Outcome #{}", index),
+        }
+    }
+}
+
+// GraphQL scalar implementation for Outcome
+// Accepts HOME/AWAY/DRAW (as enum literals or strings), OUTCOME_<index> or a bare index
+#[Scalar]
+impl ScalarType for Outcome {
+    fn parse(value: Value) -> async_graphql::InputValueResult<Self> {
+        let name = match &value {
+            Value::Enum(name) => name.as_str().to_string(),
+            Value::String(name) => name.clone(),
+            Value::Number(n) => {
+                return n
+                    .as_u64()
+                    .and_then(|index| u32::try_from(index).ok())
+                    .map(Outcome::from_index)
+                    .ok_or_else(|| async_graphql::InputValueError::expected_type(value.clone()));
+            }
+            _ => return Err(async_graphql::InputValueError::expected_type(value)),
+        };
+        match name.as_str() {
+            "HOME" => Ok(Outcome::Home),
+            "AWAY" => Ok(Outcome::Away),
+            "DRAW" => Ok(Outcome::Draw),
+            other => other
+                .strip_prefix("OUTCOME_")
+                .and_then(|index| index.parse().ok())
+                .map(Outcome::from_index)
+                .ok_or_else(|| async_graphql::InputValueError::expected_type(value)),
         }
     }
+
+    fn to_value(&self) -> Value {
+        match self {
+            Outcome::Home => Value::String("HOME".to_string()),
+            Outcome::Away => Value::String("AWAY".to_string()),
+            Outcome::Draw => Value::String("DRAW".to_string()),
+            Outcome::Other(index) => Value::String(format!("OUTCOME_{}", index)),
+        }
+    }
+}
+
+/// A market outcome with its index and display label
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, SimpleObject)]
+pub struct OutcomeInfo {
+    /// Index of the outcome (Home=0, Away=1, Draw=2 for team markets)
+    pub index: u32,
+    /// Display label (team name, "Over", a player name...)
+    pub label: String,
 }
 
+/// Maximum number of named outcomes in a MultiOutcome market
+pub const MAX_OUTCOMES: usize = 64;
+
 /// Market status lifecycle
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum MarketStatus {
@@ -106,6 +211,10 @@ pub enum MarketStatus {
     Resolved(Outcome),
     /// Market is cancelled (event cancelled, refunds issued)
     Cancelled,
+    /// Result landed exactly on the market's line, every stake refunded
+    Push,
+    /// Betting and settlement halted by a pauser until the market is resumed
+    Suspended,
 }
 
 // Note: MarketStatus cannot use Enum derive because it has a variant with data
@@ -118,39 +227,389 @@ impl fmt::Display for MarketStatus {
             MarketStatus::Locked => write!(f, "Locked"),
             MarketStatus::Resolved(outcome) => write!(f, "Resolved: {}", outcome),
             MarketStatus::Cancelled => write!(f, "Cancelled"),
+            MarketStatus::Push => write!(f, "Push"),
+            MarketStatus::Suspended => write!(f, "Suspended"),
         }
     }
 }
 
+/// Lines are stored in hundredths of a point (850 = 8.5)
+pub const LINE_SCALE: u32 = 100;
+
 /// Type of market/bet
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum MarketType {
     /// Simple winner prediction (Home/Away/Draw)
     MatchWinner,
-    /// Over/Under total points (future)
+    /// Over/Under total points (Over=Home, Under=Away); `line` in hundredths of a point
     OverUnder { line: u32 },
-    /// Point spread (future)
+    /// Point spread added to the home score (Home covers=Home, Away covers=Away);
+    /// `line` in hundredths of a point, quarter lines split the stake (Asian handicap)
     Spread { line: i32 },
+    /// Any number of named outcomes (tournament winner, first goalscorer, correct score...);
+    /// the label of outcome `i` is `outcomes[i]`
+    MultiOutcome { outcomes: Vec<String> },
 }
 
 // Note: MarketType cannot use Enum derive because it has variants with data
 // GraphQL will use SimpleObject on structs that contain this type
 
-/// GraphQL input version of MarketType (Wave 1: only MatchWinner)
+impl MarketType {
+    /// Build a market type from its GraphQL input, line (in hundredths of a point) and
+    /// outcome labels
+    pub fn from_input(
+        input: MarketTypeInput,
+        line: Option<i32>,
+        outcomes: Option<Vec<String>>,
+    ) -> Result<Self, FlashBetError> {
+        match input {
+            MarketTypeInput::MatchWinner => Ok(MarketType::MatchWinner),
+            MarketTypeInput::OverUnder => {
+                let line = line.ok_or(FlashBetError::MissingLine)?;
+                // Totals use whole or half-point lines
+                match u32::try_from(line) {
+                    Ok(total) if total % (LINE_SCALE / 2) == 0 => {
+                        Ok(MarketType::OverUnder { line: total })
+                    }
+                    _ => Err(FlashBetError::InvalidLine(line)),
+                }
+            }
+            MarketTypeInput::Spread => {
+                let line = line.ok_or(FlashBetError::MissingLine)?;
+                // Handicaps use whole, half or quarter-point lines
+                if line % (LINE_SCALE as i32 / 4) != 0 {
+                    return Err(FlashBetError::InvalidLine(line));
+                }
+                Ok(MarketType::Spread { line })
+            }
+            MarketTypeInput::MultiOutcome => {
+                let outcomes = outcomes.unwrap_or_default();
+                validate_outcome_labels(&outcomes)?;
+                Ok(MarketType::MultiOutcome { outcomes })
+            }
+        }
+    }
+
+    /// Line in points, for markets that have one
+    pub fn line_points(&self) -> Option<f64> {
+        match self {
+            MarketType::MatchWinner => None,
+            MarketType::OverUnder { line } => Some(f64::from(*line) / f64::from(LINE_SCALE)),
+            MarketType::Spread { line } => Some(f64::from(*line) / f64::from(LINE_SCALE)),
+            MarketType::MultiOutcome { .. } => None,
+        }
+    }
+
+    /// Decide how a market of this type settles for an oracle result
+    pub fn settle(&self, result: &EventResult) -> Result<Settlement, FlashBetError> {
+        match self {
+            MarketType::MatchWinner | MarketType::MultiOutcome { .. } => {
+                if !validate_outcome_for_market(result.outcome, self) {
+                    return Err(FlashBetError::InvalidOutcome);
+                }
+                Ok(Settlement::Winner(result.outcome))
+            }
+            MarketType::OverUnder { line } => {
+                let score = result
+                    .score
+                    .ok_or_else(|| FlashBetError::MissingScore(result.event_id.clone()))?;
+                let total = (u64::from(score.home) + u64::from(score.away)) * u64::from(LINE_SCALE);
+                Ok(Settlement::from_margin(total as i64 - i64::from(*line)))
+            }
+            MarketType::Spread { line } => {
+                let score = result
+                    .score
+                    .ok_or_else(|| FlashBetError::MissingScore(result.event_id.clone()))?;
+                let margin =
+                    (i64::from(score.home) - i64::from(score.away)) * i64::from(LINE_SCALE);
+                let line = i64::from(*line);
+                if line % i64::from(LINE_SCALE / 2) == 0 {
+                    return Ok(Settlement::from_margin(margin + line));
+                }
+
+                // Quarter line: half the stake on each neighbouring half/whole line
+                let quarter = i64::from(LINE_SCALE / 4);
+                let lower = Settlement::from_margin(margin + line - quarter);
+                let upper = Settlement::from_margin(margin + line + quarter);
+                Ok(match (lower, upper) {
+                    (Settlement::Winner(outcome), Settlement::Push)
+                    | (Settlement::Push, Settlement::Winner(outcome)) => {
+                        Settlement::HalfWin(outcome)
+                    }
+                    // Neighbouring lines are half a point apart, so they never disagree otherwise
+                    _ => lower,
+                })
+            }
+        }
+    }
+}
+
+/// How a market settles once its result is known
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Settlement {
+    /// Bets on this outcome share the pool
+    Winner(Outcome),
+    /// Result landed exactly on the line, every stake is refunded
+    Push,
+    /// Quarter line split: half of every stake is refunded, the other half settles on this outcome
+    HalfWin(Outcome),
+}
+
+impl Settlement {
+    /// Settle a whole or half-point line from the home side's margin over it
+    fn from_margin(margin: i64) -> Self {
+        match margin.cmp(&0) {
+            std::cmp::Ordering::Greater => Settlement::Winner(Outcome::Home),
+            std::cmp::Ordering::Less => Settlement::Winner(Outcome::Away),
+            std::cmp::Ordering::Equal => Settlement::Push,
+        }
+    }
+}
+
+/// GraphQL input version of MarketType (the line is passed separately)
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Enum)]
 pub enum MarketTypeInput {
     /// Simple winner prediction (Home/Away/Draw)
     MatchWinner,
+    /// Over/Under total points (Over=Home, Under=Away)
+    OverUnder,
+    /// Point spread on the home team (Home covers=Home, Away covers=Away)
+    Spread,
+    /// Any number of named outcomes (labels passed separately)
+    MultiOutcome,
 }
 
-impl From<MarketTypeInput> for MarketType {
-    fn from(input: MarketTypeInput) -> Self {
-        match input {
-            MarketTypeInput::MatchWinner => MarketType::MatchWinner,
+/// What happens to the pool when a market resolves with no winning bets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NoWinnerPolicy {
+    /// Refund every stake, keeping `fee_bps` basis points of each for the treasury
+    Refund { fee_bps: u16 },
+    /// Move the whole pool to the treasury
+    Treasury,
+}
+
+impl Default for NoWinnerPolicy {
+    fn default() -> Self {
+        NoWinnerPolicy::Refund { fee_bps: 0 }
+    }
+}
+
+/// House fee taken from a market's pool before winners are paid
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rake {
+    /// Fee in basis points of `base`
+    pub fee_bps: u16,
+    /// Which part of the pool the fee is taken from
+    pub base: RakeBase,
+}
+
+impl Rake {
+    /// Fee taken when `winning_pool` out of `total_pool` backed the result
+    ///
+    /// Nothing is taken when nobody backed the result (the no-winner policy applies instead).
+    pub fn fee_on(&self, total_pool: Amount, winning_pool: Amount) -> Amount {
+        if winning_pool == Amount::ZERO {
+            return Amount::ZERO;
+        }
+        let base = match self.base {
+            RakeBase::LosingPool => total_pool.saturating_sub(winning_pool),
+            RakeBase::TotalPool => total_pool,
+        };
+        basis_points_of(base, self.fee_bps)
+    }
+}
+
+/// Stake limits of a market (`None` means unlimited)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, SimpleObject)]
+pub struct StakeLimits {
+    /// Smallest stake accepted for a single bet
+    pub min_stake: Amount,
+    /// Largest stake accepted for a single bet
+    pub max_stake: Option<Amount>,
+    /// Most a single user may have staked on the market
+    pub max_user_exposure: Option<Amount>,
+    /// Most the market may owe its bettors (the whole pool, for a parimutuel market)
+    pub max_liability: Option<Amount>,
+}
+
+impl StakeLimits {
+    /// Check the limits are consistent with each other
+    pub fn validate(&self) -> Result<(), FlashBetError> {
+        for (name, max) in [
+            ("max_stake", self.max_stake),
+            ("max_user_exposure", self.max_user_exposure),
+            ("max_liability", self.max_liability),
+        ] {
+            if max.is_some_and(|max| max < self.min_stake) {
+                return Err(FlashBetError::InvalidStakeLimits(format!(
+                    "{} is below min_stake",
+                    name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check a stake, given what its user already staked and the market's liability once the
+    /// stake is accepted
+    pub fn check(
+        &self,
+        amount: Amount,
+        user_exposure: Amount,
+        liability: Amount,
+    ) -> Result<(), BetRejection> {
+        if amount < self.min_stake {
+            return Err(BetRejection::StakeBelowMinimum(self.min_stake));
+        }
+        if let Some(max) = self.max_stake.filter(|max| amount > *max) {
+            return Err(BetRejection::StakeAboveMaximum(max));
+        }
+        if let Some(max) = self
+            .max_user_exposure
+            .filter(|max| user_exposure.saturating_add(amount) > *max)
+        {
+            return Err(BetRejection::ExposureLimitReached(max));
+        }
+        if let Some(max) = self.max_liability.filter(|max| liability > *max) {
+            return Err(BetRejection::LiabilityLimitReached(max));
+        }
+        Ok(())
+    }
+}
+
+/// Part of the pool a market's rake is taken from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Enum)]
+pub enum RakeBase {
+    /// Only the losing stakes (winners always get at least their stake back)
+    #[default]
+    LosingPool,
+    /// The whole pool, winning stakes included
+    TotalPool,
+}
+
+/// What a settled bet is owed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Enum)]
+pub enum EntitlementKind {
+    /// Winnings of a bet that backed the result
+    Payout,
+    /// Stake returned (cancelled or pushed market, no winners, unplayed half of a split)
+    Refund,
+}
+
+/// Why a Market chain turned down a bet and returned its stake
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BetRejection {
+    /// No market exists for the bet's event
+    MarketNotFound,
+    /// The market no longer accepts bets
+    MarketNotOpen(MarketStatus),
+    /// The outcome is not valid for the market type
+    InvalidOutcome,
+    /// The Market chain failed to process the bet message (stake bounced back with it)
+    Bounced,
+    /// The stake is below the market's minimum
+    StakeBelowMinimum(Amount),
+    /// The stake is above the market's maximum
+    StakeAboveMaximum(Amount),
+    /// The bet would take the user's stakes on the market above its per-user limit
+    ExposureLimitReached(Amount),
+    /// The bet would take the market's liability above its cap
+    LiabilityLimitReached(Amount),
+    /// The odds had moved below the bettor's minimum payout multiplier (both in basis points)
+    OddsBelowMinimum { offered_bps: u64, min_bps: u32 },
+    /// No odds are published for the outcome of a fixed-odds market
+    OddsUnavailable,
+    /// The Market chain's bankroll cannot cover what the bet could win
+    BankrollExhausted,
+    /// The market trades outcome shares instead of taking bets
+    SharesOnly,
+    /// A bet with the same User chain and bet ID is already in the market
+    DuplicateBet,
+}
+
+impl fmt::Display for BetRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BetRejection::MarketNotFound => write!(f, "Market not found"),
+            BetRejection::MarketNotOpen(status) => write!(f, "Market is not open: {}", status),
+            BetRejection::InvalidOutcome => write!(f, "Invalid outcome for market type"),
+            BetRejection::Bounced => write!(f, "Bet message bounced"),
+            BetRejection::StakeBelowMinimum(min) => write!(f, "Stake below minimum of {}", min),
+            BetRejection::StakeAboveMaximum(max) => write!(f, "Stake above maximum of {}", max),
+            BetRejection::ExposureLimitReached(max) => {
+                write!(f, "Stakes on this market would exceed {}", max)
+            }
+            BetRejection::LiabilityLimitReached(max) => {
+                write!(f, "Market liability would exceed {}", max)
+            }
+            BetRejection::OddsBelowMinimum {
+                offered_bps,
+                min_bps,
+            } => write!(
+                f,
+                "Payout multiplier {}bps below minimum of {}bps",
+                offered_bps, min_bps
+            ),
+            BetRejection::OddsUnavailable => write!(f, "No odds offered on this outcome"),
+            BetRejection::BankrollExhausted => write!(f, "Bankroll cannot cover this bet"),
+            BetRejection::SharesOnly => write!(f, "Market trades outcome shares, not bets"),
+            BetRejection::DuplicateBet => write!(f, "Bet already registered"),
         }
     }
 }
 
+/// GraphQL input version of NoWinnerPolicy (the refund fee is passed separately)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Enum)]
+pub enum NoWinnerPolicyInput {
+    /// Refund every stake (optionally minus a fee)
+    Refund,
+    /// Move the whole pool to the treasury
+    Treasury,
+}
+
+/// Admin role on a Market chain, granted by its owner (who implicitly holds every role)
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, Enum,
+)]
+pub enum MarketRole {
+    /// May create markets
+    MarketCreator,
+    /// May cancel markets
+    Settler,
+    /// May pause and resume betting and settlement
+    Pauser,
+    /// May publish the odds of fixed-odds markets
+    PriceSetter,
+}
+
+/// How a market prices its bets
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Enum)]
+pub enum PricingMode {
+    /// Winners share the pool of all stakes (odds are only known at settlement)
+    #[default]
+    Parimutuel,
+    /// Bets lock in the decimal odds published when they are accepted,
+    /// backed by the Market chain's bankroll
+    FixedOdds,
+    /// Traders buy and sell outcome shares from an automated market maker using a logarithmic
+    /// market scoring rule, backed by the Market chain's bankroll; each winning share pays 1 BET
+    Lmsr,
+}
+
+/// Decimal odds of one outcome, in basis points (25_000 = 2.5, paying 2.5x the stake)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, SimpleObject, InputObject)]
+#[graphql(input_name = "OutcomeOddsInput")]
+pub struct OutcomeOdds {
+    pub outcome: Outcome,
+    pub odds_bps: u32,
+}
+
+/// What a stake returns at decimal odds in basis points, rounded down
+pub fn fixed_odds_payout(stake: Amount, odds_bps: u32) -> Amount {
+    mul_div(stake.into(), odds_bps.into(), BASIS_POINTS.into())
+        .map_or(Amount::MAX, |(quotient, _)| Amount::from_attos(quotient))
+}
+
 // ============================================================================
 // Structs
 // ============================================================================
@@ -180,6 +639,56 @@ pub struct MarketInfo {
     pub home_team: String,
     /// Away team name
     pub away_team: String,
+    /// What happens to the pool if nobody backed the winning outcome
+    pub no_winner_policy: NoWinnerPolicy,
+    /// House fee taken from the pool before winners are paid
+    pub rake: Rake,
+    /// Market identifier assigned by the Market chain
+    pub market_id: MarketId,
+    /// Limits on stakes, per bet, per user and for the whole market
+    pub limits: StakeLimits,
+    /// How bets are priced
+    pub pricing: PricingMode,
+    /// Liquidity parameter `b` of an LMSR market: the larger it is, the less each trade moves
+    /// prices, and the more the market maker can lose (b·ln(number of outcomes))
+    pub liquidity: Option<Amount>,
+}
+
+impl MarketInfo {
+    /// Outcomes that can be backed on this market, in index order
+    pub fn outcomes(&self) -> Vec<OutcomeInfo> {
+        let labels = match &self.market_type {
+            MarketType::MatchWinner => {
+                vec![
+                    self.home_team.clone(),
+                    self.away_team.clone(),
+                    "Draw".to_string(),
+                ]
+            }
+            MarketType::OverUnder { .. } => vec!["Over".to_string(), "Under".to_string()],
+            MarketType::Spread { .. } => vec![self.home_team.clone(), self.away_team.clone()],
+            MarketType::MultiOutcome { outcomes } => outcomes.clone(),
+        };
+        labels
+            .into_iter()
+            .enumerate()
+            .map(|(index, label)| OutcomeInfo {
+                index: index as u32,
+                label,
+            })
+            .collect()
+    }
+
+    /// Payout per unit staked on an outcome if the market settled on it now, in basis points
+    /// ((total pool - rake) / outcome pool, so 10_000 returns the stake); saturates at u64::MAX
+    ///
+    /// `outcome_pool` must include the stake being priced, so it is never zero.
+    pub fn payout_multiplier_bps(&self, total_pool: Amount, outcome_pool: Amount) -> u64 {
+        let payable = total_pool.saturating_sub(self.rake.fee_on(total_pool, outcome_pool));
+        mul_div(payable.into(), BASIS_POINTS.into(), outcome_pool.into())
+            .and_then(|(quotient, _)| u64::try_from(quotient).ok())
+            .unwrap_or(u64::MAX)
+    }
 }
 
 /// A single bet record
@@ -204,6 +713,36 @@ pub struct Bet {
     pub user_chain: ChainId,
 }
 
+impl Bet {
+    /// Key identifying this bet uniquely on the Market chain
+    pub fn key(&self) -> BetKey {
+        BetKey {
+            user_chain: self.user_chain,
+            bet_id: self.bet_id,
+        }
+    }
+}
+
+/// Globally unique identifier of a bet on a Market chain
+///
+/// Bet IDs are assigned per User chain, so the User chain is part of the key.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, SimpleObject, InputObject,
+)]
+#[graphql(input_name = "BetKeyInput")]
+pub struct BetKey {
+    /// User chain that placed the bet
+    pub user_chain: ChainId,
+    /// Bet ID assigned by the User chain
+    pub bet_id: u64,
+}
+
+impl fmt::Display for BetKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Bet#{}@{}", self.bet_id, self.user_chain)
+    }
+}
+
 /// Result from oracle for a sports event
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, SimpleObject, InputObject)]
 #[graphql(input_name = "EventResultInput")]
@@ -218,6 +757,93 @@ pub struct EventResult {
     pub timestamp: Timestamp,
 }
 
+/// Amount a bet is owed once its market has settled, paid out when claimed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, SimpleObject)]
+pub struct Entitlement {
+    /// The settled bet
+    pub bet: Bet,
+    /// Amount owed to the bettor
+    pub amount: Amount,
+    /// Whether this pays out winnings or returns stake
+    pub kind: EntitlementKind,
+    /// Whether the amount has been paid out
+    pub claimed: bool,
+}
+
+/// How far SettleNext has got through paying out a settled market
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, SimpleObject)]
+pub struct SettlementProgress {
+    /// Bets owed something when the market settled
+    pub total: u64,
+    /// Bets already processed, in settlement order
+    pub settled: u64,
+}
+
+impl SettlementProgress {
+    /// Check that every bet owed something has been processed
+    pub fn is_complete(&self) -> bool {
+        self.settled >= self.total
+    }
+}
+
+/// Side of a limit order on an order book
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Enum)]
+pub enum OrderSide {
+    /// Buy shares, paying at most the order's price for each
+    Bid,
+    /// Sell shares, receiving at least the order's price for each
+    Ask,
+}
+
+impl OrderSide {
+    /// The side orders on this side are matched against
+    pub fn opposite(self) -> Self {
+        match self {
+            OrderSide::Bid => OrderSide::Ask,
+            OrderSide::Ask => OrderSide::Bid,
+        }
+    }
+}
+
+/// Limit order on the shares of an outcome of an LMSR market
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, SimpleObject)]
+pub struct Order {
+    /// Order identifier assigned by the Market chain
+    pub order_id: u64,
+    pub market_id: MarketId,
+    /// Account on the Market chain that placed the order
+    pub owner: AccountOwner,
+    pub outcome: Outcome,
+    pub side: OrderSide,
+    /// Limit price of one share, in BET (between 0 and 1)
+    pub price: Amount,
+    /// Shares ordered
+    pub shares: Amount,
+    /// Shares not filled yet
+    pub remaining: Amount,
+    /// BET a bid still holds in escrow to pay for its remaining shares
+    /// (asks hold their remaining shares instead)
+    pub escrow: Amount,
+    pub timestamp: Timestamp,
+}
+
+/// Shares that changed hands on an order book
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, SimpleObject)]
+pub struct Trade {
+    pub market_id: MarketId,
+    pub outcome: Outcome,
+    /// Price paid for each share: the price of the order resting on the book
+    pub price: Amount,
+    pub shares: Amount,
+    pub buyer: AccountOwner,
+    pub seller: AccountOwner,
+    /// Order that was resting on the book
+    pub maker_order_id: u64,
+    /// Incoming order that filled it
+    pub taker_order_id: u64,
+    pub timestamp: Timestamp,
+}
+
 /// Payout information for a winning bet
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, SimpleObject, InputObject)]
 #[graphql(input_name = "PayoutInput")]
@@ -232,6 +858,53 @@ pub struct Payout {
     pub timestamp: Timestamp,
 }
 
+/// Final status of a bet once its market is settled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Enum)]
+pub enum SettlementStatus {
+    /// The bet backed the result
+    Won,
+    /// The bet backed another outcome (it may still get part of its stake back)
+    Lost,
+    /// The stake is returned (cancelled or pushed market, no winners)
+    Refunded,
+}
+
+/// Settlement notice sent to every participating User chain, winners and losers alike
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, SimpleObject)]
+pub struct SettlementNotice {
+    /// Market that was settled
+    pub market_id: MarketId,
+    /// Event the market was for
+    pub event_id: EventId,
+    /// Bet ID on the User chain
+    pub bet_id: u64,
+    /// Final status of the bet
+    pub status: SettlementStatus,
+    /// Amount the bet is owed (zero for a losing bet)
+    pub amount: Amount,
+    /// When the market was settled
+    pub timestamp: Timestamp,
+}
+
+/// Notice about a User chain's bets, published by the Market Chain on that chain's
+/// notice stream (see `user_notices_stream`) and read by its User application
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UserNotice {
+    /// Winnings paid out, alongside the BET token transfer
+    Payout(Payout),
+    /// Stake refunded (cancelled or pushed market, no winners), alongside the BET token transfer
+    Refund(Payout),
+    /// Final status of the chain's bets on a settled market
+    Settled { notices: Vec<SettlementNotice> },
+}
+
+/// Market Chain stream carrying the notices for a User chain's bets
+pub fn user_notices_stream(user_chain: ChainId) -> StreamName {
+    let mut name = b"user_notices:".to_vec();
+    name.extend_from_slice(&<[u8; 32]>::from(user_chain.0));
+    StreamName::from(name)
+}
+
 // ============================================================================
 // Cross-Chain Messages
 // ============================================================================
@@ -270,8 +943,27 @@ pub enum UserEvent {
         bet_id: u64,
         amount: Amount,
     },
+    /// User's stake was refunded (market cancelled)
+    RefundReceived {
+        market_id: MarketId,
+        bet_id: u64,
+        amount: Amount,
+    },
     /// User deposited funds
     Deposited { amount: Amount },
+    /// Market chain rejected a bet
+    BetRejected {
+        market_id: MarketId,
+        bet_id: u64,
+        reason: BetRejection,
+    },
+    /// User's bet was settled (won, lost or refunded)
+    BetSettled {
+        market_id: MarketId,
+        bet_id: u64,
+        status: SettlementStatus,
+        amount: Amount,
+    },
 }
 
 /// Events emitted by the Market Chain
@@ -287,6 +979,7 @@ pub enum MarketEvent {
     BetPlaced {
         market_id: MarketId,
         bet_id: u64,
+        user_chain: ChainId,
         outcome: Outcome,
         amount: Amount,
         total_pool: Amount,
@@ -298,9 +991,14 @@ pub enum MarketEvent {
         total_pool: Amount,
         winning_pool: Amount,
         num_winners: u64,
+        /// House fee sent to the treasury before paying winners
+        rake: Amount,
     },
     /// Market was locked (no more bets)
-    MarketLocked { market_id: MarketId },
+    MarketLocked {
+        market_id: MarketId,
+        event_id: EventId,
+    },
     /// Payout distributed to winner
     PayoutDistributed {
         market_id: MarketId,
@@ -308,6 +1006,126 @@ pub enum MarketEvent {
         user_chain: ChainId,
         amount: Amount,
     },
+    /// Market was cancelled and all stakes are being refunded
+    MarketCancelled {
+        market_id: MarketId,
+        event_id: EventId,
+        total_refunded: Amount,
+        num_refunds: u64,
+    },
+    /// Result landed exactly on the market's line, every stake refunded
+    MarketPushed {
+        market_id: MarketId,
+        event_id: EventId,
+        total_refunded: Amount,
+        num_refunds: u64,
+    },
+    /// Stake refunded to a bettor
+    BetRefunded {
+        market_id: MarketId,
+        bet_id: u64,
+        user_chain: ChainId,
+        amount: Amount,
+    },
+    /// Market resolved without winning bets; the pool was settled by its policy
+    NoWinners {
+        market_id: MarketId,
+        event_id: EventId,
+        policy: NoWinnerPolicy,
+        /// Total stakes returned to bettors
+        refunded: Amount,
+        /// Amount moved to the treasury (fees or the whole pool)
+        to_treasury: Amount,
+    },
+    /// Bet was rejected and its stake returned to the bettor
+    BetRejected {
+        market_id: MarketId,
+        bet_id: u64,
+        user_chain: ChainId,
+        reason: BetRejection,
+    },
+    /// Every bet of a settled market was paid out by SettleNext
+    SettlementCompleted {
+        market_id: MarketId,
+        event_id: EventId,
+        num_settled: u64,
+    },
+    /// Role granted to an account by the owner
+    RoleGranted {
+        role: MarketRole,
+        account: AccountOwner,
+    },
+    /// Role revoked from an account by the owner
+    RoleRevoked {
+        role: MarketRole,
+        account: AccountOwner,
+    },
+    /// Betting and settlement halted on every market of the chain
+    ChainPaused,
+    /// Betting and settlement restarted on the chain's markets
+    ChainResumed,
+    /// Betting and settlement halted on a market
+    MarketSuspended {
+        market_id: MarketId,
+        event_id: EventId,
+    },
+    /// Market restored to the status it had before it was suspended
+    MarketResumed {
+        market_id: MarketId,
+        event_id: EventId,
+        status: MarketStatus,
+    },
+    /// New odds published for a fixed-odds market
+    OddsUpdated {
+        market_id: MarketId,
+        odds: Vec<OutcomeOdds>,
+    },
+    /// House funds added to the bankroll backing fixed-odds markets
+    BankrollFunded {
+        funder: AccountOwner,
+        amount: Amount,
+        bankroll: Amount,
+    },
+    /// House funds withdrawn from the bankroll by the owner
+    BankrollWithdrawn {
+        to: AccountOwner,
+        amount: Amount,
+        bankroll: Amount,
+    },
+    /// Outcome shares bought from an LMSR market maker
+    SharesBought {
+        market_id: MarketId,
+        trader: AccountOwner,
+        outcome: Outcome,
+        shares: Amount,
+        cost: Amount,
+    },
+    /// Outcome shares sold back to an LMSR market maker
+    SharesSold {
+        market_id: MarketId,
+        trader: AccountOwner,
+        outcome: Outcome,
+        shares: Amount,
+        proceeds: Amount,
+    },
+    /// Shares of a settled LMSR market paid out to their holder
+    SharesRedeemed {
+        market_id: MarketId,
+        holder: AccountOwner,
+        amount: Amount,
+    },
+    /// Limit order posted on an order book, before it is matched
+    OrderPlaced { order: Order },
+    /// Rest of a limit order withdrawn by its owner
+    OrderCancelled {
+        market_id: MarketId,
+        order_id: u64,
+        owner: AccountOwner,
+    },
+    /// Limit orders matched on an order book
+    OrderFilled { trade: Trade },
+    /// Notice for a User chain, emitted on its `user_notices_stream`
+    UserNotice(UserNotice),
 }
 
 /// Events emitted by the Oracle Chain
@@ -358,6 +1176,24 @@ pub enum FlashBetError {
 
     #[error("Invalid event ID format: {0}")]
     InvalidEventId(String),
+
+    #[error("Invalid fee: {0} basis points exceeds 10000")]
+    InvalidFee(u16),
+
+    #[error("Market type requires a line")]
+    MissingLine,
+
+    #[error("Invalid line: {0} hundredths of a point")]
+    InvalidLine(i32),
+
+    #[error("Event result has no score: {0}")]
+    MissingScore(EventId),
+
+    #[error("Invalid outcomes: {0}")]
+    InvalidOutcomes(String),
+
+    #[error("Invalid stake limits: {0}")]
+    InvalidStakeLimits(String),
 }
 
 // ============================================================================
@@ -385,12 +1221,313 @@ pub fn validate_event_id(event_id: &EventId) -> Result<(), FlashBetError> {
     Ok(())
 }
 
+/// Basis points in 100%
+pub const BASIS_POINTS: u16 = 10_000;
+
+/// Validate a bet's stake before it is sent to a Market chain
+pub fn validate_bet_amount(amount: Amount) -> Result<(), FlashBetError> {
+    if amount == Amount::ZERO {
+        return Err(FlashBetError::InvalidBetAmount(amount));
+    }
+    Ok(())
+}
+
+/// Validate a fee expressed in basis points
+pub fn validate_fee_bps(fee_bps: u16) -> Result<(), FlashBetError> {
+    if fee_bps > BASIS_POINTS {
+        return Err(FlashBetError::InvalidFee(fee_bps));
+    }
+    Ok(())
+}
+
+/// Compute `fee_bps` basis points of an amount, rounding down
+///
+/// Splits the amount so that the multiplication cannot overflow.
+pub fn basis_points_of(amount: Amount, fee_bps: u16) -> Amount {
+    let attos: u128 = amount.into();
+    let bps = u128::from(fee_bps);
+    let scale = u128::from(BASIS_POINTS);
+    Amount::from_attos((attos / scale) * bps + (attos % scale) * bps / scale)
+}
+
+/// Full 256-bit product of two u128 values, as (high, low) halves
+fn widening_mul(a: u128, b: u128) -> (u128, u128) {
+    let mask = u128::from(u64::MAX);
+    let (a_high, a_low) = (a >> 64, a & mask);
+    let (b_high, b_low) = (b >> 64, b & mask);
+
+    let low_low = a_low * b_low;
+    let high_low = a_high * b_low;
+    let low_high = a_low * b_high;
+    let high_high = a_high * b_high;
+
+    let middle = (low_low >> 64) + (high_low & mask) + (low_high & mask);
+    let low = (low_low & mask) | (middle << 64);
+    let high = high_high + (high_low >> 64) + (low_high >> 64) + (middle >> 64);
+    (high, low)
+}
+
+/// Compute `value * numerator / denominator` exactly, as (quotient, remainder)
+///
+/// The product is kept in 256 bits, so it never saturates. Returns `None` if `denominator`
+/// is zero or the quotient does not fit in a u128.
+pub fn mul_div(value: u128, numerator: u128, denominator: u128) -> Option<(u128, u128)> {
+    if denominator == 0 {
+        return None;
+    }
+    let (high, low) = widening_mul(value, numerator);
+    if high >= denominator {
+        return None;
+    }
+
+    // Long division of the 256-bit product, one bit of `low` at a time
+    let mut remainder = high;
+    let mut quotient = 0u128;
+    for bit in (0..128).rev() {
+        let carry = remainder >> 127;
+        remainder = (remainder << 1) | ((low >> bit) & 1);
+        quotient <<= 1;
+        if carry == 1 || remainder >= denominator {
+            remainder = remainder.wrapping_sub(denominator);
+            quotient |= 1;
+        }
+    }
+    Some((quotient, remainder))
+}
+
+/// Split `pool` between `weights` pro rata, leaving no dust
+///
+/// Each share is rounded down, then the attos left over (fewer than the number of weights)
+/// go one each to the largest remainders, ties to the earliest weight. The shares add up to
+/// exactly `pool`, unless every weight is zero and nothing is distributed.
+pub fn distribute_pro_rata(pool: Amount, weights: &[Amount]) -> Vec<Amount> {
+    let pool: u128 = pool.into();
+    // Weights are stakes held by one chain, so their sum is bounded by the token supply
+    let total_weight = weights
+        .iter()
+        .fold(0u128, |sum, weight| sum.saturating_add((*weight).into()));
+    if total_weight == 0 {
+        return vec![Amount::ZERO; weights.len()];
+    }
+
+    let mut shares = Vec::with_capacity(weights.len());
+    let mut remainders = Vec::with_capacity(weights.len());
+    let mut distributed = 0u128;
+    for (index, weight) in weights.iter().enumerate() {
+        let (share, remainder) =
+            mul_div(pool, (*weight).into(), total_weight).expect("A share never exceeds the pool");
+        shares.push(share);
+        remainders.push((remainder, index));
+        distributed += share;
+    }
+
+    // Largest remainder first, earliest weight first among equals
+    remainders.sort_by(|(a, a_index), (b, b_index)| b.cmp(a).then(a_index.cmp(b_index)));
+    let leftover = (pool - distributed) as usize;
+    for (_, index) in remainders.into_iter().take(leftover) {
+        shares[index] += 1;
+    }
+
+    shares.into_iter().map(Amount::from_attos).collect()
+}
+
+/// Fixed-point 1.0 of the LMSR math: one token in attos, so prices are amounts per share
+const FIXED_ONE: u128 = 1_000_000_000_000_000_000;
+/// ln(2) in LMSR fixed point
+const FIXED_LN_2: u128 = 693_147_180_559_945_309;
+
+/// Cost function of a logarithmic market scoring rule: what the market maker has collected
+/// once `shares` of each outcome are outstanding, C(q) = b·ln(Σ exp(q_i / b))
+///
+/// Computed in integer fixed point so every validator gets the same result, as
+/// max(q) + b·ln(Σ exp((q_i - max(q)) / b)) to keep the exponentials in range, and rounded up.
+/// A trade costs the difference between the cost after and before it. Returns `None` if
+/// `liquidity` is zero, there are no outcomes or the cost does not fit in an Amount.
+pub fn lmsr_cost(liquidity: Amount, shares: &[Amount]) -> Option<Amount> {
+    let (max, weights) = lmsr_weights(liquidity, shares)?;
+    let sum = weights
+        .iter()
+        .try_fold(0u128, |sum, weight| sum.checked_add(*weight))?;
+    let (log_term, remainder) = mul_div(liquidity.into(), fixed_ln(sum), FIXED_ONE)?;
+    let log_term = log_term.checked_add(u128::from(remainder > 0))?;
+    max.try_add(Amount::from_attos(log_term)).ok()
+}
+
+/// Current price of one share of each outcome of an LMSR market, exp(q_i / b) / Σ exp(q_j / b)
+///
+/// Prices are in BET (a share paying 1 BET for sure would cost `Amount::ONE`), rounded down,
+/// so they add up to at most one token. Returns `None` if `liquidity` is zero or there are
+/// no outcomes.
+pub fn lmsr_prices(liquidity: Amount, shares: &[Amount]) -> Option<Vec<Amount>> {
+    let (_, weights) = lmsr_weights(liquidity, shares)?;
+    let sum = weights
+        .iter()
+        .try_fold(0u128, |sum, weight| sum.checked_add(*weight))?;
+    weights
+        .into_iter()
+        .map(|weight| mul_div(weight, FIXED_ONE, sum).map(|(price, _)| Amount::from_attos(price)))
+        .collect()
+}
+
+/// Largest share count and each outcome's weight exp((q_i - max(q)) / b) in fixed point
+///
+/// The largest outcome weighs exactly 1.0, so the weights add up to at least 1.0.
+fn lmsr_weights(liquidity: Amount, shares: &[Amount]) -> Option<(Amount, Vec<u128>)> {
+    let b: u128 = liquidity.into();
+    if b == 0 {
+        return None;
+    }
+    let max = shares.iter().copied().max()?;
+    let weights = shares
+        .iter()
+        .map(|q| {
+            // Exponents too large for a u128 have a weight of zero anyway
+            let exponent = mul_div(max.saturating_sub(*q).into(), FIXED_ONE, b)
+                .map_or(u128::MAX, |(exponent, _)| exponent);
+            fixed_exp_neg(exponent)
+        })
+        .collect();
+    Some((max, weights))
+}
+
+/// exp(-x) in fixed point, rounded down
+///
+/// Reduces x to k·ln(2) + r with r < ln(2), sums the Taylor series of exp(r) and returns
+/// 2^-k / exp(r).
+fn fixed_exp_neg(x: u128) -> u128 {
+    let k = x / FIXED_LN_2;
+    if k >= 128 {
+        return 0;
+    }
+    let r = x - k * FIXED_LN_2;
+    let mut term = FIXED_ONE;
+    let mut sum = FIXED_ONE;
+    let mut n = 1;
+    while term > 0 {
+        term = term * r / (n * FIXED_ONE);
+        sum += term;
+        n += 1;
+    }
+    (FIXED_ONE * FIXED_ONE / sum) >> k
+}
+
+/// ln(y) in fixed point, for y of at least 1.0
+///
+/// Reduces y to 2^k·m with 1 <= m < 2 and sums the series ln(m) = 2·atanh((m - 1) / (m + 1)).
+fn fixed_ln(y: u128) -> u128 {
+    let k = (y / FIXED_ONE).ilog2();
+    let m = y >> k;
+    let z = (m - FIXED_ONE) * FIXED_ONE / (m + FIXED_ONE);
+    let z_squared = z * z / FIXED_ONE;
+    let mut term = z;
+    let mut sum = 0;
+    let mut n = 1;
+    while term > 0 {
+        sum += term / n;
+        term = term * z_squared / FIXED_ONE;
+        n += 2;
+    }
+    u128::from(k) * FIXED_LN_2 + 2 * sum
+}
+
+/// How the pool of a resolved market is split between its bets and the house
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolSettlement {
+    /// Amount returned to each bet, in the order the stakes were given
+    pub payouts: Vec<Amount>,
+    /// House fee for the treasury
+    pub rake: Amount,
+}
+
+/// Split the pool of a resolved market between its bets
+///
+/// `stakes` lists each bet's stake and whether it backed the winning outcome. With
+/// `half_stakes`, half of every stake (rounded down) is returned and only the rest is in
+/// play. Winners share the in-play pool minus the rake in proportion to their in-play stakes.
+///
+/// Returns `None` if nobody backed the winning outcome. Otherwise the payouts plus the rake
+/// add up to exactly the total stake.
+pub fn settle_pool(
+    stakes: &[(Amount, bool)],
+    rake: &Rake,
+    half_stakes: bool,
+) -> Option<PoolSettlement> {
+    let pushed = |stake: Amount| {
+        if half_stakes {
+            basis_points_of(stake, BASIS_POINTS / 2)
+        } else {
+            Amount::ZERO
+        }
+    };
+
+    let mut in_play_pool = Amount::ZERO;
+    let mut winning_pool = Amount::ZERO;
+    let mut winning_stakes = Vec::new();
+    for (stake, won) in stakes {
+        let in_play = stake.saturating_sub(pushed(*stake));
+        in_play_pool.saturating_add_assign(in_play);
+        if *won {
+            winning_pool.saturating_add_assign(in_play);
+            winning_stakes.push(in_play);
+        }
+    }
+    if winning_pool == Amount::ZERO {
+        return None;
+    }
+
+    let rake = rake.fee_on(in_play_pool, winning_pool);
+    let mut winnings =
+        distribute_pro_rata(in_play_pool.saturating_sub(rake), &winning_stakes).into_iter();
+    let payouts = stakes
+        .iter()
+        .map(|(stake, won)| {
+            let returned = pushed(*stake);
+            if *won {
+                returned.saturating_add(winnings.next().expect("One share per winning stake"))
+            } else {
+                returned
+            }
+        })
+        .collect();
+
+    Some(PoolSettlement { payouts, rake })
+}
+
+/// Validate the outcome labels of a MultiOutcome market
+pub fn validate_outcome_labels(outcomes: &[String]) -> Result<(), FlashBetError> {
+    if outcomes.len() < 2 || outcomes.len() > MAX_OUTCOMES {
+        return Err(FlashBetError::InvalidOutcomes(format!(
+            "expected 2 to {} outcomes, got {}",
+            MAX_OUTCOMES,
+            outcomes.len()
+        )));
+    }
+    for (index, label) in outcomes.iter().enumerate() {
+        if label.trim().is_empty() {
+            return Err(FlashBetError::InvalidOutcomes(format!(
+                "outcome {} has no label",
+                index
+            )));
+        }
+        if outcomes[..index].contains(label) {
+            return Err(FlashBetError::InvalidOutcomes(format!(
+                "duplicate outcome {}",
+                label
+            )));
+        }
+    }
+    Ok(())
+}
+
 /// Validate outcome is valid for market type
 pub fn validate_outcome_for_market(outcome: Outcome, market_type: &MarketType) -> bool {
     match market_type {
-        MarketType::MatchWinner => true, // All outcomes valid
+        MarketType::MatchWinner => matches!(outcome, Outcome::Home | Outcome::Away | Outcome::Draw),
         MarketType::OverUnder { .. } => matches!(outcome, Outcome::Home | Outcome::Away), // Over=Home, Under=Away
         MarketType::Spread { .. } => matches!(outcome, Outcome::Home | Outcome::Away),
+        MarketType::MultiOutcome { outcomes } => {
+            outcome.is_canonical() && (outcome.index() as usize) < outcomes.len()
+        }
     }
 }
 
@@ -418,7 +1555,38 @@ mod tests {
         assert!(validate_event_id(&EventId::new("")).is_err()); // Empty
         assert!(validate_event_id(&EventId::new("event with spaces")).is_err()); // Spaces
         assert!(validate_event_id(&EventId::new("event@special!chars")).is_err()); // Special chars
-        assert!(validate_event_id(&EventId::new(&"x".repeat(101))).is_err()); // Too long
+        assert!(validate_event_id(&EventId::new("x".repeat(101))).is_err()); // Too long
+    }
+
+    #[test]
+    fn test_basis_points_of() {
+        assert_eq!(
+            basis_points_of(Amount::from_tokens(100), 250),
+            Amount::from_millis(2_500)
+        );
+        assert_eq!(basis_points_of(Amount::from_attos(9_999), 1), Amount::ZERO);
+        assert_eq!(basis_points_of(Amount::MAX, BASIS_POINTS), Amount::MAX);
+        assert!(validate_fee_bps(BASIS_POINTS).is_ok());
+        assert!(validate_fee_bps(BASIS_POINTS + 1).is_err());
+    }
+
+    #[test]
+    fn test_rake() {
+        let total = Amount::from_tokens(100);
+        let winning = Amount::from_tokens(40);
+        let losing_pool = Rake {
+            fee_bps: 500,
+            base: RakeBase::LosingPool,
+        };
+        let total_pool = Rake {
+            fee_bps: 500,
+            base: RakeBase::TotalPool,
+        };
+
+        assert_eq!(losing_pool.fee_on(total, winning), Amount::from_tokens(3));
+        assert_eq!(total_pool.fee_on(total, winning), Amount::from_tokens(5));
+        assert_eq!(total_pool.fee_on(total, Amount::ZERO), Amount::ZERO);
+        assert_eq!(Rake::default().fee_on(total, winning), Amount::ZERO);
     }
 
     #[test]
@@ -433,4 +1601,518 @@ mod tests {
         assert!(validate_outcome_for_market(Outcome::Away, &over_under));
         assert!(!validate_outcome_for_market(Outcome::Draw, &over_under));
     }
+
+    #[test]
+    fn test_over_under_settlement() {
+        let result = |home, away| EventResult {
+            event_id: EventId::new("mlb_game_001"),
+            outcome: Outcome::Draw,
+            score: Some(Score { home, away }),
+            timestamp: Timestamp::from(0),
+        };
+
+        let half_line =
+            MarketType::from_input(MarketTypeInput::OverUnder, Some(850), None).unwrap();
+        assert_eq!(half_line.line_points(), Some(8.5));
+        assert_eq!(
+            half_line.settle(&result(5, 4)).unwrap(),
+            Settlement::Winner(Outcome::Home)
+        );
+        assert_eq!(
+            half_line.settle(&result(5, 3)).unwrap(),
+            Settlement::Winner(Outcome::Away)
+        );
+
+        let whole_line = MarketType::OverUnder { line: 800 };
+        assert_eq!(whole_line.settle(&result(5, 3)).unwrap(), Settlement::Push);
+        assert!(whole_line
+            .settle(&EventResult {
+                score: None,
+                ..result(0, 0)
+            })
+            .is_err());
+
+        assert!(MarketType::from_input(MarketTypeInput::OverUnder, None, None).is_err());
+        assert!(MarketType::from_input(MarketTypeInput::OverUnder, Some(825), None).is_err());
+        assert!(MarketType::from_input(MarketTypeInput::OverUnder, Some(-50), None).is_err());
+    }
+
+    #[test]
+    fn test_spread_settlement() {
+        let result = |home, away| EventResult {
+            event_id: EventId::new("epl_game_001"),
+            outcome: Outcome::Draw,
+            score: Some(Score { home, away }),
+            timestamp: Timestamp::from(0),
+        };
+        let spread =
+            |line| MarketType::from_input(MarketTypeInput::Spread, Some(line), None).unwrap();
+
+        // Half line: no push possible
+        assert_eq!(
+            spread(-150).settle(&result(3, 1)).unwrap(),
+            Settlement::Winner(Outcome::Home)
+        );
+        assert_eq!(
+            spread(-150).settle(&result(2, 1)).unwrap(),
+            Settlement::Winner(Outcome::Away)
+        );
+
+        // Whole line: push on an exact tie
+        assert_eq!(
+            spread(-100).settle(&result(2, 1)).unwrap(),
+            Settlement::Push
+        );
+        assert_eq!(
+            spread(100).settle(&result(1, 1)).unwrap(),
+            Settlement::Winner(Outcome::Home)
+        );
+
+        // Quarter lines: -0.25 is split over 0 and -0.5, -0.75 over -0.5 and -1
+        assert_eq!(
+            spread(-25).settle(&result(1, 1)).unwrap(),
+            Settlement::HalfWin(Outcome::Away)
+        );
+        assert_eq!(
+            spread(-25).settle(&result(2, 1)).unwrap(),
+            Settlement::Winner(Outcome::Home)
+        );
+        assert_eq!(
+            spread(-75).settle(&result(2, 1)).unwrap(),
+            Settlement::HalfWin(Outcome::Home)
+        );
+        assert_eq!(
+            spread(-75).settle(&result(1, 1)).unwrap(),
+            Settlement::Winner(Outcome::Away)
+        );
+        assert_eq!(
+            spread(25).settle(&result(0, 0)).unwrap(),
+            Settlement::HalfWin(Outcome::Home)
+        );
+
+        assert!(MarketType::from_input(MarketTypeInput::Spread, Some(-30), None).is_err());
+        assert!(MarketType::from_input(MarketTypeInput::Spread, None, None).is_err());
+    }
+    #[test]
+    fn test_outcome_index_round_trip() {
+        for index in 0..10 {
+            let outcome = Outcome::from_index(index);
+            assert_eq!(outcome.index(), index);
+            assert!(outcome.is_canonical());
+        }
+        assert_eq!(Outcome::from_index(2), Outcome::Draw);
+        assert!(!Outcome::Other(1).is_canonical());
+    }
+
+    #[test]
+    fn test_outcome_scalar() {
+        let parse = |value| <Outcome as ScalarType>::parse(value).ok();
+        assert_eq!(
+            parse(Value::String("AWAY".to_string())),
+            Some(Outcome::Away)
+        );
+        assert_eq!(
+            parse(Value::Enum(async_graphql::Name::new("DRAW"))),
+            Some(Outcome::Draw)
+        );
+        assert_eq!(
+            parse(Value::String("OUTCOME_5".to_string())),
+            Some(Outcome::Other(5))
+        );
+        assert_eq!(
+            parse(Value::String("OUTCOME_0".to_string())),
+            Some(Outcome::Home)
+        );
+        assert_eq!(parse(Value::Number(7.into())), Some(Outcome::Other(7)));
+        assert_eq!(parse(Value::String("WIN".to_string())), None);
+        assert_eq!(
+            Outcome::Other(5).to_value(),
+            Value::String("OUTCOME_5".to_string())
+        );
+    }
+
+    #[test]
+    fn test_multi_outcome_market() {
+        let labels = |names: &[&str]| Some(names.iter().map(|name| name.to_string()).collect());
+        let market = MarketType::from_input(
+            MarketTypeInput::MultiOutcome,
+            None,
+            labels(&["Alice", "Bob", "Carol", "Dave"]),
+        )
+        .unwrap();
+
+        assert!(validate_outcome_for_market(Outcome::Draw, &market));
+        assert!(validate_outcome_for_market(Outcome::Other(3), &market));
+        assert!(!validate_outcome_for_market(Outcome::Other(4), &market));
+        assert!(!validate_outcome_for_market(Outcome::Other(1), &market));
+        assert!(!validate_outcome_for_market(
+            Outcome::Other(3),
+            &MarketType::MatchWinner
+        ));
+
+        let result = |outcome| EventResult {
+            event_id: EventId::new("race"),
+            outcome,
+            score: None,
+            timestamp: Timestamp::from(0),
+        };
+        assert_eq!(
+            market.settle(&result(Outcome::Other(3))).unwrap(),
+            Settlement::Winner(Outcome::Other(3))
+        );
+        assert!(market.settle(&result(Outcome::Other(9))).is_err());
+
+        let info = MarketInfo {
+            event_id: EventId::new("race"),
+            description: "Race".to_string(),
+            event_time: Timestamp::from(0),
+            market_type: market,
+            home_team: String::new(),
+            away_team: String::new(),
+            no_winner_policy: NoWinnerPolicy::default(),
+            rake: Rake::default(),
+            market_id: MarketId(0),
+            limits: StakeLimits::default(),
+            pricing: PricingMode::default(),
+            liquidity: None,
+        };
+        let outcomes = info.outcomes();
+        assert_eq!(outcomes.len(), 4);
+        assert_eq!(
+            outcomes[3],
+            OutcomeInfo {
+                index: 3,
+                label: "Dave".to_string()
+            }
+        );
+
+        assert!(MarketType::from_input(MarketTypeInput::MultiOutcome, None, None).is_err());
+        assert!(
+            MarketType::from_input(MarketTypeInput::MultiOutcome, None, labels(&["Solo"])).is_err()
+        );
+        assert!(
+            MarketType::from_input(MarketTypeInput::MultiOutcome, None, labels(&["A", " "]))
+                .is_err()
+        );
+        assert!(
+            MarketType::from_input(MarketTypeInput::MultiOutcome, None, labels(&["A", "A"]))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_payout_multiplier() {
+        let mut info = MarketInfo {
+            event_id: EventId::new("game"),
+            description: String::new(),
+            event_time: Timestamp::from(0),
+            market_type: MarketType::MatchWinner,
+            home_team: String::new(),
+            away_team: String::new(),
+            no_winner_policy: NoWinnerPolicy::default(),
+            rake: Rake::default(),
+            market_id: MarketId(0),
+            limits: StakeLimits::default(),
+            pricing: PricingMode::default(),
+            liquidity: None,
+        };
+        let tokens = Amount::from_tokens;
+        assert_eq!(info.payout_multiplier_bps(tokens(30), tokens(20)), 15_000);
+        assert_eq!(info.payout_multiplier_bps(tokens(20), tokens(20)), 10_000);
+
+        // 5% of the 10 losing tokens is kept: 29.5 / 20
+        info.rake.fee_bps = 500;
+        assert_eq!(info.payout_multiplier_bps(tokens(30), tokens(20)), 14_750);
+
+        assert_eq!(
+            info.payout_multiplier_bps(Amount::MAX, Amount::from_attos(1)),
+            u64::MAX
+        );
+    }
+
+    #[test]
+    fn test_lmsr_fixed_point() {
+        for x in [0.0f64, 0.1, 0.5, 1.0, 2.5, 10.0, 30.0] {
+            let computed = fixed_exp_neg((x * 1e18) as u128) as f64 / 1e18;
+            assert!(
+                (computed - (-x).exp()).abs() < 1e-15,
+                "exp(-{}) = {}",
+                x,
+                computed
+            );
+        }
+        assert_eq!(fixed_exp_neg(0), FIXED_ONE);
+        assert_eq!(fixed_exp_neg(u128::MAX), 0);
+
+        for y in [1.0f64, 1.000001, 1.5, 2.0, 3.0, 7.3, 1000.0] {
+            let computed = fixed_ln((y * 1e18) as u128) as f64 / 1e18;
+            assert!(
+                (computed - y.ln()).abs() < 1e-15,
+                "ln({}) = {}",
+                y,
+                computed
+            );
+        }
+        assert_eq!(fixed_ln(FIXED_ONE), 0);
+    }
+
+    #[test]
+    fn test_lmsr() {
+        let tokens = Amount::from_tokens;
+        let liquidity = tokens(100);
+        let as_f64 = |amount: Amount| u128::from(amount) as f64 / 1e18;
+
+        // The market maker starts out owed b·ln(n), the most it can lose
+        let start = lmsr_cost(liquidity, &[Amount::ZERO; 2]).unwrap();
+        assert!((as_f64(start) - 100.0 * 2f64.ln()).abs() < 1e-12);
+        assert_eq!(
+            lmsr_prices(liquidity, &[Amount::ZERO; 2]).unwrap(),
+            vec![Amount::from_millis(500); 2]
+        );
+
+        // Buying 10 shares costs b·ln((e^0.1 + 1) / 2) and raises their price
+        let after = lmsr_cost(liquidity, &[tokens(10), Amount::ZERO]).unwrap();
+        let cost = as_f64(after.saturating_sub(start));
+        assert!((cost - 100.0 * ((0.1f64.exp() + 1.0) / 2.0).ln()).abs() < 1e-12);
+        let prices = lmsr_prices(liquidity, &[tokens(10), Amount::ZERO]).unwrap();
+        assert!(prices[0] > Amount::from_millis(500) && prices[1] < Amount::from_millis(500));
+        assert!((as_f64(prices[0]) - 1.0 / (1.0 + (-0.1f64).exp())).abs() < 1e-15);
+        let total = prices[0].saturating_add(prices[1]);
+        assert!(total <= Amount::ONE && total >= Amount::ONE.saturating_sub(Amount::from_attos(2)));
+
+        // The cost always covers paying out the largest outcome
+        let lopsided = [tokens(5_000), Amount::ZERO, tokens(1)];
+        assert!(lmsr_cost(liquidity, &lopsided).unwrap() >= tokens(5_000));
+        assert_eq!(lmsr_prices(liquidity, &lopsided).unwrap()[1], Amount::ZERO);
+
+        assert_eq!(lmsr_cost(Amount::ZERO, &[Amount::ZERO; 2]), None);
+        assert_eq!(lmsr_prices(liquidity, &[]), None);
+    }
+
+    #[test]
+    fn test_fixed_odds_payout() {
+        assert_eq!(
+            fixed_odds_payout(Amount::from_tokens(10), 25_000),
+            Amount::from_tokens(25)
+        );
+        assert_eq!(
+            fixed_odds_payout(Amount::from_attos(3), 15_000),
+            Amount::from_attos(4)
+        );
+        assert_eq!(fixed_odds_payout(Amount::MAX, 20_000), Amount::MAX);
+    }
+
+    #[test]
+    fn test_stake_limits() {
+        let tokens = Amount::from_tokens;
+        let limits = StakeLimits {
+            min_stake: tokens(5),
+            max_stake: Some(tokens(50)),
+            max_user_exposure: Some(tokens(80)),
+            max_liability: Some(tokens(1000)),
+        };
+        assert!(limits.validate().is_ok());
+        assert!(StakeLimits {
+            max_stake: Some(tokens(1)),
+            ..limits
+        }
+        .validate()
+        .is_err());
+
+        assert_eq!(limits.check(tokens(5), tokens(0), tokens(0)), Ok(()));
+        assert_eq!(
+            limits.check(tokens(4), tokens(0), tokens(0)),
+            Err(BetRejection::StakeBelowMinimum(tokens(5)))
+        );
+        assert_eq!(
+            limits.check(tokens(51), tokens(0), tokens(0)),
+            Err(BetRejection::StakeAboveMaximum(tokens(50)))
+        );
+        assert_eq!(limits.check(tokens(30), tokens(50), tokens(0)), Ok(()));
+        assert_eq!(
+            limits.check(tokens(31), tokens(50), tokens(0)),
+            Err(BetRejection::ExposureLimitReached(tokens(80)))
+        );
+        assert_eq!(
+            limits.check(tokens(10), tokens(0), tokens(1005)),
+            Err(BetRejection::LiabilityLimitReached(tokens(1000)))
+        );
+        assert_eq!(
+            StakeLimits::default().check(tokens(1), tokens(0), tokens(0)),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_mul_div() {
+        assert_eq!(mul_div(7, 3, 2), Some((10, 1)));
+        assert_eq!(
+            mul_div(u128::MAX, u128::MAX, u128::MAX),
+            Some((u128::MAX, 0))
+        );
+        assert_eq!(mul_div(u128::MAX, 2, u128::MAX), Some((2, 0)));
+        assert_eq!(mul_div(u128::MAX, 2, 1), None);
+        assert_eq!(mul_div(1, 1, 0), None);
+    }
+
+    #[test]
+    fn test_distribute_pro_rata() {
+        let attos = |values: &[u128]| {
+            values
+                .iter()
+                .copied()
+                .map(Amount::from_attos)
+                .collect::<Vec<_>>()
+        };
+
+        // 10 split 1:1:1 leaves one atto, which goes to the first weight
+        assert_eq!(
+            distribute_pro_rata(Amount::from_attos(10), &attos(&[1, 1, 1])),
+            attos(&[4, 3, 3])
+        );
+        // The largest remainder wins the leftover atto, wherever it is
+        assert_eq!(
+            distribute_pro_rata(Amount::from_attos(10), &attos(&[1, 2])),
+            attos(&[3, 7])
+        );
+        assert_eq!(
+            distribute_pro_rata(Amount::from_attos(10), &attos(&[0, 0])),
+            attos(&[0, 0])
+        );
+    }
+
+    #[test]
+    fn test_settle_pool() {
+        let tokens = Amount::from_tokens;
+        let rake = Rake {
+            fee_bps: 1_000,
+            base: RakeBase::LosingPool,
+        };
+
+        let settlement =
+            settle_pool(&[(tokens(10), true), (tokens(30), false)], &rake, false).unwrap();
+        assert_eq!(settlement.payouts, vec![tokens(37), Amount::ZERO]);
+        assert_eq!(settlement.rake, tokens(3));
+
+        // On a split, only half of every stake is in play
+        let settlement =
+            settle_pool(&[(tokens(10), true), (tokens(30), false)], &rake, true).unwrap();
+        assert_eq!(
+            settlement.payouts,
+            vec![
+                tokens(10).saturating_add(Amount::from_millis(13_500)),
+                tokens(15)
+            ]
+        );
+        assert_eq!(settlement.rake, Amount::from_millis(1_500));
+
+        assert_eq!(settle_pool(&[(tokens(10), false)], &rake, false), None);
+    }
+
+    mod properties {
+        use proptest::prelude::*;
+
+        use super::*;
+
+        fn stakes() -> impl Strategy<Value = Vec<(Amount, bool)>> {
+            // Up to a billion tokens per stake, as the sum must stay below the token supply
+            let stake = (0..=10u128.pow(27)).prop_map(Amount::from_attos);
+            prop::collection::vec((stake, any::<bool>()), 1..50)
+        }
+
+        fn rake() -> impl Strategy<Value = Rake> {
+            (0..=BASIS_POINTS, any::<bool>()).prop_map(|(fee_bps, total)| Rake {
+                fee_bps,
+                base: if total {
+                    RakeBase::TotalPool
+                } else {
+                    RakeBase::LosingPool
+                },
+            })
+        }
+
+        proptest! {
+            #[test]
+            fn mul_div_is_exact(value: u128, numerator: u128, denominator in 1..=u128::MAX) {
+                match mul_div(value, numerator, denominator) {
+                    Some((quotient, remainder)) => {
+                        prop_assert!(remainder < denominator);
+                        // quotient * denominator + remainder == value * numerator, in 256 bits
+                        let (high, low) = widening_mul(quotient, denominator);
+                        let (low, carry) = low.overflowing_add(remainder);
+                        prop_assert_eq!(
+                            (high + u128::from(carry), low),
+                            widening_mul(value, numerator)
+                        );
+                    }
+                    None => prop_assert!(widening_mul(value, numerator).0 >= denominator),
+                }
+            }
+
+            #[test]
+            fn distribution_has_no_dust(
+                pool: u64,
+                weights in prop::collection::vec(0..=u64::MAX, 1..50),
+            ) {
+                let pool = Amount::from_attos(u128::from(pool));
+                let weights = weights
+                    .into_iter()
+                    .map(|w| Amount::from_attos(u128::from(w)))
+                    .collect::<Vec<_>>();
+                let shares = distribute_pro_rata(pool, &weights);
+
+                let total_weight: u128 = weights.iter().map(|w| u128::from(*w)).sum();
+                let distributed: u128 = shares.iter().map(|s| u128::from(*s)).sum();
+                if total_weight == 0 {
+                    prop_assert_eq!(distributed, 0);
+                } else {
+                    prop_assert_eq!(distributed, u128::from(pool));
+                }
+                // Every share is its exact pro-rata value rounded down or up
+                for (share, weight) in shares.iter().zip(&weights) {
+                    if total_weight > 0 {
+                        let (exact, _) =
+                            mul_div(pool.into(), (*weight).into(), total_weight).unwrap();
+                        let share = u128::from(*share);
+                        prop_assert!(share == exact || share == exact + 1);
+                    }
+                }
+                prop_assert_eq!(distribute_pro_rata(pool, &weights), shares);
+            }
+
+            #[test]
+            fn settlement_pays_out_the_whole_pool(
+                stakes in stakes(),
+                rake in rake(),
+                half_stakes: bool,
+            ) {
+                let total: u128 = stakes.iter().map(|(stake, _)| u128::from(*stake)).sum();
+                let in_play = |stake: Amount| {
+                    let pushed = if half_stakes {
+                        basis_points_of(stake, BASIS_POINTS / 2)
+                    } else {
+                        Amount::ZERO
+                    };
+                    stake.saturating_sub(pushed)
+                };
+                let in_play_winners = stakes
+                    .iter()
+                    .any(|(stake, won)| *won && in_play(*stake) > Amount::ZERO);
+
+                match settle_pool(&stakes, &rake, half_stakes) {
+                    Some(settlement) => {
+                        let paid: u128 = settlement.payouts.iter().map(|p| u128::from(*p)).sum();
+                        prop_assert_eq!(paid + u128::from(settlement.rake), total);
+                        // Losing bets never get more than the half that was not in play
+                        for ((stake, won), payout) in stakes.iter().zip(&settlement.payouts) {
+                            if !won {
+                                prop_assert!(*payout <= *stake);
+                            }
+                        }
+                    }
+                    None => prop_assert!(!in_play_winners),
+                }
+            }
+        }
+    }
 }
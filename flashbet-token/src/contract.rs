@@ -1,13 +1,19 @@
 #![cfg_attr(target_arch = "wasm32", no_main)]
 
+mod state;
+
 use flashbet_token::{Message, Operation};
 use linera_sdk::{
     abis::fungible::{FungibleResponse, InitialState},
     linera_base_types::{Account, AccountOwner, Amount, WithContractAbi},
+    views::{RootView, View},
     Contract, ContractRuntime,
 };
 
+use self::state::TokenState;
+
 pub struct FlashbetTokenContract {
+    state: TokenState,
     runtime: ContractRuntime<Self>,
 }
 
@@ -24,7 +30,10 @@ impl Contract for FlashbetTokenContract {
     type EventValue = ();
 
     async fn load(runtime: ContractRuntime<Self>) -> Self {
-        FlashbetTokenContract { runtime }
+        let state = TokenState::load(runtime.root_view_storage_context())
+            .await
+            .expect("Failed to load state");
+        FlashbetTokenContract { state, runtime }
     }
 
     async fn instantiate(&mut self, state: Self::InstantiationArgument) {
@@ -118,6 +127,83 @@ impl Contract for FlashbetTokenContract {
 
                 FungibleResponse::Ok
             }
+
+            Operation::TransferToEscrow { amount } => {
+                assert!(amount > Amount::ZERO, "Amount must be positive");
+
+                let application = self
+                    .runtime
+                    .authenticated_caller_id()
+                    .expect("Escrow transfers must be made by an application");
+                let from = self
+                    .runtime
+                    .authenticated_signer()
+                    .expect("Transfer must be signed");
+
+                self.runtime
+                    .check_account_permission(from)
+                    .expect("Not authorized");
+
+                let escrow_account = Account {
+                    chain_id: self.runtime.chain_id(),
+                    owner: self.escrow_owner(),
+                };
+                self.runtime.transfer(from, escrow_account, amount);
+                self.state.credit_escrow(&application, amount).await;
+
+                FungibleResponse::Ok
+            }
+
+            Operation::TransferToEscrowCrossChain {
+                destination,
+                application,
+                amount,
+            } => {
+                assert!(amount > Amount::ZERO, "Amount must be positive");
+
+                let from = self
+                    .runtime
+                    .authenticated_signer()
+                    .expect("Transfer must be signed");
+
+                self.runtime
+                    .check_account_permission(from)
+                    .expect("Not authorized");
+
+                // Move the tokens to the destination chain, then credit them to the escrow there
+                let escrow_account = Account {
+                    chain_id: destination,
+                    owner: self.escrow_owner(),
+                };
+                self.runtime.transfer(from, escrow_account, amount);
+                self.runtime
+                    .prepare_message(Message::CreditEscrow {
+                        application,
+                        amount,
+                    })
+                    .send_to(destination);
+
+                FungibleResponse::Ok
+            }
+
+            Operation::TransferFromEscrow { to, amount } => {
+                assert!(amount > Amount::ZERO, "Amount must be positive");
+
+                let application = self
+                    .runtime
+                    .authenticated_caller_id()
+                    .expect("Only an application can spend its escrow");
+
+                self.state.debit_escrow(&application, amount).await;
+                let escrow_owner = self.escrow_owner();
+                self.runtime.transfer(escrow_owner, to, amount);
+
+                FungibleResponse::Ok
+            }
+
+            Operation::EscrowBalance { application } => {
+                FungibleResponse::Balance(self.state.get_escrow(&application).await)
+            }
         }
     }
 
@@ -132,10 +218,164 @@ impl Contract for FlashbetTokenContract {
                 self.runtime
                     .transfer(AccountOwner::CHAIN, target_account, amount);
             }
+
+            Message::CreditEscrow {
+                application,
+                amount,
+            } => {
+                // The tokens arrived in the token's own account with the same block
+                self.state.credit_escrow(&application, amount).await;
+            }
         }
     }
 
-    async fn store(self) {
-        // No state to save - native tokens handled by runtime
+    async fn store(mut self) {
+        // Native token balances are handled by the runtime
+        self.state.save().await.expect("Failed to save state");
+    }
+}
+
+impl FlashbetTokenContract {
+    /// The token application's own account, which holds every application's escrow
+    /// Only the token can debit it, on behalf of the application an escrow belongs to
+    fn escrow_owner(&mut self) -> AccountOwner {
+        AccountOwner::from(self.runtime.application_id().forget_abi())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use flashbet_token::Operation;
+    use linera_sdk::{
+        abis::fungible::InitialState,
+        linera_base_types::{Account, AccountOwner, Amount, ApplicationId, ChainId, CryptoHash},
+        util::BlockingWait,
+        views::View,
+        Contract, ContractRuntime,
+    };
+
+    use super::{FlashbetTokenContract, TokenState};
+
+    #[test]
+    fn escrow_deposit_and_payout() {
+        let mut app = create_and_instantiate_app();
+        app.runtime.set_authenticated_caller_id(market_app());
+
+        app.execute_operation(Operation::TransferToEscrow {
+            amount: Amount::from_tokens(4),
+        })
+        .blocking_wait();
+        app.execute_operation(Operation::TransferFromEscrow {
+            to: Account {
+                chain_id: chain_id(),
+                owner: recipient(),
+            },
+            amount: Amount::from_tokens(3),
+        })
+        .blocking_wait();
+
+        assert_eq!(app.runtime.owner_balance(signer()), Amount::from_tokens(6));
+        assert_eq!(
+            app.runtime.owner_balance(recipient()),
+            Amount::from_tokens(3)
+        );
+        assert_eq!(
+            app.runtime.owner_balance(escrow_owner()),
+            Amount::from_tokens(1)
+        );
+        assert_eq!(
+            app.state.get_escrow(&market_app()).blocking_wait(),
+            Amount::from_tokens(1)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Insufficient escrow balance")]
+    fn escrow_of_another_application_cannot_be_spent() {
+        let mut app = create_and_instantiate_app();
+        app.runtime.set_authenticated_caller_id(market_app());
+        app.execute_operation(Operation::TransferToEscrow {
+            amount: Amount::from_tokens(4),
+        })
+        .blocking_wait();
+
+        app.runtime
+            .set_authenticated_caller_id(ApplicationId::new(CryptoHash::test_hash("other_app")));
+        app.execute_operation(Operation::TransferFromEscrow {
+            to: Account {
+                chain_id: chain_id(),
+                owner: recipient(),
+            },
+            amount: Amount::from_tokens(1),
+        })
+        .blocking_wait();
+    }
+
+    #[test]
+    #[should_panic(expected = "Only an application can spend its escrow")]
+    fn escrow_payout_requires_calling_application() {
+        let mut app = create_and_instantiate_app();
+
+        app.execute_operation(Operation::TransferFromEscrow {
+            to: Account {
+                chain_id: chain_id(),
+                owner: recipient(),
+            },
+            amount: Amount::from_tokens(1),
+        })
+        .blocking_wait();
+    }
+
+    fn chain_id() -> ChainId {
+        ChainId(CryptoHash::test_hash("market_chain"))
+    }
+
+    fn market_app() -> ApplicationId {
+        ApplicationId::new(CryptoHash::test_hash("market_app"))
+    }
+
+    fn token_app() -> ApplicationId {
+        ApplicationId::new(CryptoHash::test_hash("bet_token"))
+    }
+
+    fn escrow_owner() -> AccountOwner {
+        AccountOwner::from(token_app())
+    }
+
+    fn signer() -> AccountOwner {
+        AccountOwner::from([1; 32])
+    }
+
+    fn recipient() -> AccountOwner {
+        AccountOwner::from([2; 32])
+    }
+
+    fn create_and_instantiate_app() -> FlashbetTokenContract {
+        let runtime = ContractRuntime::new()
+            .with_application_parameters(())
+            .with_application_id(token_app().with_abi())
+            .with_chain_id(chain_id())
+            .with_authenticated_signer(signer())
+            .with_authenticated_caller_id(None)
+            .with_chain_balance(Amount::ZERO)
+            .with_owner_balances([
+                (signer(), Amount::from_tokens(10)),
+                (recipient(), Amount::ZERO),
+                (escrow_owner(), Amount::ZERO),
+            ]);
+        let mut contract = FlashbetTokenContract {
+            state: TokenState::load(runtime.root_view_storage_context())
+                .blocking_wait()
+                .expect("Failed to read from mock key value store"),
+            runtime,
+        };
+
+        contract
+            .instantiate(InitialState {
+                accounts: Default::default(),
+            })
+            .blocking_wait();
+
+        contract
     }
 }
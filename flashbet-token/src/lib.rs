@@ -4,9 +4,11 @@
 
 use async_graphql::Request;
 use linera_sdk::{
-    abis::fungible::{InitialState, FungibleResponse},
+    abis::fungible::FungibleResponse,
     graphql::GraphQLMutationRoot,
-    linera_base_types::{AccountOwner, Amount, ChainId, ContractAbi, ServiceAbi},
+    linera_base_types::{
+        Account, AccountOwner, Amount, ApplicationId, ChainId, ContractAbi, ServiceAbi,
+    },
 };
 use serde::{Deserialize, Serialize};
 
@@ -61,6 +63,38 @@ pub enum Operation {
         /// Amount to transfer
         amount: Amount,
     },
+
+    /// Move tokens from the signer's account into the calling application's escrow
+    /// Must be called by an application, forwarding the signer whose tokens are moved
+    TransferToEscrow {
+        /// Amount to transfer
+        amount: Amount,
+    },
+
+    /// Move tokens from the signer's account into an application's escrow on another chain
+    TransferToEscrowCrossChain {
+        /// Chain holding the escrow
+        destination: ChainId,
+        /// Application the escrow belongs to
+        application: ApplicationId,
+        /// Amount to transfer
+        amount: Amount,
+    },
+
+    /// Pay tokens out of the calling application's escrow to an account on any chain
+    /// Only the application an escrow belongs to can spend it
+    TransferFromEscrow {
+        /// Recipient account
+        to: Account,
+        /// Amount to transfer
+        amount: Amount,
+    },
+
+    /// Query the tokens an application holds in escrow on this chain
+    EscrowBalance {
+        /// Application the escrow belongs to
+        application: ApplicationId,
+    },
 }
 
 /// Cross-chain messages
@@ -73,4 +107,12 @@ pub enum Message {
         /// Amount to credit
         amount: Amount,
     },
+
+    /// Credit tokens to an application's escrow (cross-chain escrow transfer arrival)
+    CreditEscrow {
+        /// Application the escrow belongs to
+        application: ApplicationId,
+        /// Amount to credit
+        amount: Amount,
+    },
 }
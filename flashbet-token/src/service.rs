@@ -1,15 +1,21 @@
 #![cfg_attr(target_arch = "wasm32", no_main)]
 
+mod state;
+
 use async_graphql::{EmptySubscription, Object, Request, Response, Schema, SimpleObject};
 use flashbet_token::Operation;
 use linera_sdk::{
     graphql::GraphQLMutationRoot,
-    linera_base_types::{AccountOwner, Amount, WithServiceAbi},
+    linera_base_types::{AccountOwner, Amount, ApplicationId, WithServiceAbi},
+    views::View,
     Service, ServiceRuntime,
 };
 use std::sync::Arc;
 
+use self::state::TokenState;
+
 pub struct FlashbetTokenService {
+    state: Arc<TokenState>,
     runtime: Arc<ServiceRuntime<Self>>,
 }
 
@@ -69,13 +75,22 @@ impl FlashbetTokenService {
             runtime: self.runtime.clone(),
         }
     }
+
+    /// Query the tokens an application holds in escrow on this chain
+    async fn escrow_balance(&self, application: ApplicationId) -> Amount {
+        self.state.get_escrow(&application).await
+    }
 }
 
 impl Service for FlashbetTokenService {
     type Parameters = ();
 
     async fn new(runtime: ServiceRuntime<Self>) -> Self {
+        let state = TokenState::load(runtime.root_view_storage_context())
+            .await
+            .expect("Failed to load state");
         FlashbetTokenService {
+            state: Arc::new(state),
             runtime: Arc::new(runtime),
         }
     }
@@ -94,6 +109,7 @@ impl Service for FlashbetTokenService {
 impl Clone for FlashbetTokenService {
     fn clone(&self) -> Self {
         FlashbetTokenService {
+            state: self.state.clone(),
             runtime: self.runtime.clone(),
         }
     }
@@ -1,43 +1,45 @@
 use linera_sdk::{
-    linera_base_types::{AccountOwner, Amount},
-    views::{MapView, RootView},
+    linera_base_types::{Amount, ApplicationId},
+    views::{linera_views, MapView, RootView, ViewStorageContext},
 };
 
-/// Token state - stores balances for all accounts
+/// Token state - account balances are native, so only application escrows are tracked here
 #[derive(RootView)]
+#[view(context = ViewStorageContext)]
 pub struct TokenState {
-    /// Account balances
-    pub balances: MapView<AccountOwner, Amount>,
+    /// Escrow balances held by the token for applications on this chain
+    /// The tokens themselves sit in the token application's own account
+    pub escrows: MapView<ApplicationId, Amount>,
 }
 
 impl TokenState {
-    /// Get balance for an account (returns 0 if account doesn't exist)
-    pub async fn get_balance(&self, owner: &AccountOwner) -> Amount {
-        self.balances
-            .get(owner)
+    /// Get the escrow balance of an application (returns 0 if it has none)
+    pub async fn get_escrow(&self, application: &ApplicationId) -> Amount {
+        self.escrows
+            .get(application)
             .await
-            .expect("Failed to read balance")
+            .expect("Failed to read escrow")
             .unwrap_or(Amount::ZERO)
     }
 
-    /// Credit tokens to an account
-    pub async fn credit(&mut self, owner: &AccountOwner, amount: Amount) {
-        let current = self.get_balance(owner).await;
-        // Add amounts using saturating_add to prevent overflow
-        let new_balance = current.saturating_add(amount);
-        self.balances
-            .insert(owner, new_balance)
-            .expect("Failed to update balance");
+    /// Credit tokens to an application's escrow
+    #[allow(dead_code)] // Unused by the service, which only reads escrows
+    pub async fn credit_escrow(&mut self, application: &ApplicationId, amount: Amount) {
+        let current = self.get_escrow(application).await;
+        self.escrows
+            .insert(application, current.saturating_add(amount))
+            .expect("Failed to update escrow");
     }
 
-    /// Debit tokens from an account
-    pub async fn debit(&mut self, owner: &AccountOwner, amount: Amount) {
-        let current = self.get_balance(owner).await;
-        assert!(current >= amount, "Insufficient balance");
-        // Subtract amounts using saturating_sub
-        let new_balance = current.saturating_sub(amount);
-        self.balances
-            .insert(owner, new_balance)
-            .expect("Failed to update balance");
+    /// Debit tokens from an application's escrow
+    #[allow(dead_code)] // Unused by the service, which only reads escrows
+    pub async fn debit_escrow(&mut self, application: &ApplicationId, amount: Amount) {
+        let current = self.get_escrow(application).await;
+        let remaining = current
+            .try_sub(amount)
+            .expect("Insufficient escrow balance");
+        self.escrows
+            .insert(application, remaining)
+            .expect("Failed to update escrow");
     }
 }
@@ -2,11 +2,12 @@
 
 mod state;
 
-use flashbet_shared::{Bet, UserEvent};
-use flashbet_token; // BET token application for cross-application calls
+use flashbet_shared::{Bet, BetRejection, Payout, SettlementNotice, UserEvent, UserNotice};
 use flashbet_user::{InstantiationArgument, Message, Operation, OperationResponse};
 use linera_sdk::{
-    linera_base_types::{Amount, StreamName, WithContractAbi},
+    linera_base_types::{
+        Amount, GenericApplicationId, StreamId, StreamName, StreamUpdate, WithContractAbi,
+    },
     views::{RootView, View},
     Contract, ContractRuntime,
 };
@@ -48,6 +49,9 @@ impl Contract for FlashbetUserContract {
             unsafe { std::mem::transmute(argument.bet_token_id) };
         self.state.bet_token_id.set(Some(typed_app_id));
 
+        // Store the Market application ID, which holds stakes in escrow
+        self.state.market_app_id.set(Some(argument.market_app_id));
+
         // Initialize bet ID counter
         // Note: Balances are now managed by BET token application
         self.state.next_bet_id.set(0);
@@ -157,9 +161,10 @@ impl Contract for FlashbetUserContract {
                 event_id,
                 outcome,
                 amount,
+                min_payout_bps,
             } => {
                 // 1. Validate amount
-                assert!(amount > Amount::ZERO, "Bet amount must be positive");
+                flashbet_shared::validate_bet_amount(amount).expect("Invalid bet amount");
 
                 // 2. Get authenticated signer
                 let signer = self
@@ -187,18 +192,24 @@ impl Contract for FlashbetUserContract {
                     user_chain,
                 };
 
-                // 5. Transfer BET tokens to Market chain via BET token application
+                // 5. Transfer BET tokens into the Market application's escrow on the Market
+                // chain via BET token application, so the market can refund or pay them out
                 // This will fail if user has insufficient balance (checked by BET token app)
-                use linera_sdk::{abis::fungible::FungibleResponse, linera_base_types::AccountOwner};
+                use linera_sdk::abis::fungible::FungibleResponse;
 
                 let bet_token_id = self
                     .state
                     .bet_token_id
                     .get()
                     .expect("BET token ID not initialized");
-                let transfer_operation = flashbet_token::Operation::TransferCrossChain {
+                let market_app_id = self
+                    .state
+                    .market_app_id
+                    .get()
+                    .expect("Market application ID not initialized");
+                let transfer_operation = flashbet_token::Operation::TransferToEscrowCrossChain {
                     destination: market_chain,
-                    to: AccountOwner::CHAIN, // Transfer to Market chain balance
+                    application: market_app_id,
                     amount,
                 };
 
@@ -212,21 +223,52 @@ impl Contract for FlashbetUserContract {
 
                 match response {
                     FungibleResponse::Ok => {}
-                    _ => panic!("Unexpected response from BET token TransferCrossChain operation"),
+                    _ => panic!(
+                        "Unexpected response from BET token TransferToEscrowCrossChain operation"
+                    ),
                 }
 
-                // 6. Record bet in active bets
-                self.state
-                    .active_bets
-                    .insert(&market_id, bet.clone())
-                    .expect("Failed to insert active bet");
+                // 6. Record bet in active bets (several bets per market may be open)
+                self.state.open_active_bet(&bet);
 
                 self.state
                     .bet_history
                     .insert(&bet_id, bet.clone())
                     .expect("Failed to insert bet history");
 
-                // 7. Emit BetPlaced event for Market chain to process
+                // 7. Subscribe to the Market chain's notices for this chain, which report
+                // payouts, refunds and settlements
+                if !self
+                    .state
+                    .notice_subscriptions
+                    .contains(&market_chain)
+                    .await
+                    .expect("Failed to read notice subscriptions")
+                {
+                    self.runtime.subscribe_to_events(
+                        market_chain,
+                        market_app_id,
+                        flashbet_shared::user_notices_stream(user_chain),
+                    );
+                    self.state
+                        .notice_subscriptions
+                        .insert(&market_chain)
+                        .expect("Failed to record notice subscription");
+                }
+
+                // 8. Send the bet to our instance on the Market chain, signed by the bettor
+                // (a closed or unknown market rejects it and returns the stake)
+                self.runtime
+                    .prepare_message(Message::PlaceBet {
+                        bet: bet.clone(),
+                        min_payout_bps,
+                        market_app_id,
+                    })
+                    .with_authentication()
+                    .with_tracking() // Bounces back if the Market chain fails to process it
+                    .send_to(market_chain);
+
+                // 9. Emit BetPlaced event for indexers and the frontend
                 self.runtime.emit(
                     StreamName::from(b"user_bets".to_vec()),
                     &UserEvent::BetPlaced {
@@ -242,20 +284,123 @@ impl Contract for FlashbetUserContract {
 
     async fn execute_message(&mut self, message: Self::Message) {
         match message {
-            Message::PlaceBet { .. } => {
-                // This message is sent TO Market Chains, not received by User Chain
-                // If we receive it back (bounced), just ignore it
+            Message::PlaceBet {
+                bet,
+                min_payout_bps,
+                market_app_id,
+            } => {
+                if self.runtime.message_is_bouncing() != Some(true) {
+                    // On the Market chain: only accept bets sent by the bettor's own User chain
+                    if self.runtime.message_origin_chain_id() != Some(bet.user_chain) {
+                        return;
+                    }
+
+                    // Register the bet with the Market application, forwarding the bettor's
+                    // signature; it checks both and returns the stake of bets it turns down
+                    let response = self
+                        .runtime
+                        .call_application::<flashbet_market::FlashbetMarketAbi>(
+                            true,
+                            market_app_id.with_abi(),
+                            &flashbet_market::Operation::RegisterBet {
+                                bet: bet.clone(),
+                                min_payout_bps,
+                            },
+                        );
+
+                    // Tell the bettor's User chain why its bet was turned down
+                    if let flashbet_market::OperationResponse::BetRejected(reason) = response {
+                        let user_chain = bet.user_chain;
+                        self.runtime
+                            .prepare_message(Message::BetRejected { bet, reason })
+                            .send_to(user_chain);
+                    }
+                    return;
+                }
+
+                // Bounced back: the Market chain rejected the bet's whole bundle, so the
+                // escrow credit was dropped and the stake's transfer bounced back with it
+                self.reject_bet(bet, BetRejection::Bounced).await;
+            }
+
+            Message::BetRejected { bet, reason } => {
+                // The stake arrives via the BET token transfer from the Market chain.
+                // This message just updates our state tracking.
+                self.reject_bet(bet, reason).await;
             }
+        }
+    }
 
-            Message::Payout(payout) => {
-                // Note: Native tokens are automatically received via runtime.transfer()
-                // from Market chain. This message just updates our state tracking.
+    async fn process_streams(&mut self, updates: Vec<StreamUpdate>) {
+        // PlaceBet subscribes to each Market chain's notice stream for this chain.
+        // An application can only read its own events, so each new notice is read
+        // through the Market application.
+        let market_app_id = self
+            .state
+            .market_app_id
+            .get()
+            .expect("Market application ID not initialized");
+        let notices = StreamId {
+            application_id: GenericApplicationId::User(market_app_id),
+            stream_name: flashbet_shared::user_notices_stream(self.runtime.chain_id()),
+        };
 
-                // 1. Remove from active bets
-                self.state
-                    .active_bets
-                    .remove(&payout.market_id)
-                    .expect("Failed to remove active bet");
+        for update in updates {
+            if update.stream_id != notices {
+                continue;
+            }
+
+            // Skip notices that were already processed
+            let first_index = self
+                .state
+                .next_notice_index
+                .get(&update.chain_id)
+                .await
+                .expect("Failed to read next notice index")
+                .map_or(update.previous_index, |next| {
+                    update.previous_index.max(next)
+                });
+
+            for index in first_index..update.next_index {
+                let response = self
+                    .runtime
+                    .call_application::<flashbet_market::FlashbetMarketAbi>(
+                        false,
+                        market_app_id.with_abi(),
+                        &flashbet_market::Operation::ReadNotice {
+                            chain_id: update.chain_id,
+                            index,
+                        },
+                    );
+
+                if let flashbet_market::OperationResponse::Notice(notice) = response {
+                    self.handle_notice(notice).await;
+                }
+            }
+
+            self.state
+                .next_notice_index
+                .insert(&update.chain_id, first_index.max(update.next_index))
+                .expect("Failed to record next notice index");
+        }
+    }
+
+    async fn store(mut self) {
+        self.state.save().await.expect("Failed to save state");
+    }
+}
+
+impl FlashbetUserContract {
+    /// Update state tracking from a notice published by a Market chain
+    async fn handle_notice(&mut self, notice: UserNotice) {
+        match notice {
+            UserNotice::Payout(payout) => {
+                // The winnings were paid to the bettor when the bet was claimed, by a BET
+                // token TransferFromEscrow out of the market's escrow. This notice just
+                // updates our state tracking.
+
+                // 1. Remove the settled bet from active bets
+                self.state.close_active_bet(payout.bet_id).await;
 
                 // 2. Record payout in history
                 self.state
@@ -273,11 +418,90 @@ impl Contract for FlashbetUserContract {
                     },
                 );
             }
+
+            UserNotice::Refund(refund) => {
+                // The stake arrives via the BET token transfer from the Market chain.
+                // This notice just updates our state tracking.
+
+                // 1. Remove the refunded bet from active bets
+                self.state.close_active_bet(refund.bet_id).await;
+
+                // 2. Record refund in history
+                self.state
+                    .refund_history
+                    .insert(&refund.bet_id, refund.clone())
+                    .expect("Failed to insert refund history");
+
+                // 3. Emit event
+                self.runtime.emit(
+                    StreamName::from(b"user_events".to_vec()),
+                    &UserEvent::RefundReceived {
+                        market_id: refund.market_id,
+                        bet_id: refund.bet_id,
+                        amount: refund.amount,
+                    },
+                );
+            }
+
+            UserNotice::Settled { notices } => {
+                // Any amount owed arrives later with its own Payout or Refund notice.
+                // This notice closes the bets, including losing ones.
+                for notice in notices {
+                    self.settle_bet(notice).await;
+                }
+            }
         }
     }
 
-    async fn store(mut self) {
-        self.state.save().await.expect("Failed to save state");
+    /// Close a bet that never entered a pool, recording its returned stake
+    async fn reject_bet(&mut self, bet: Bet, reason: BetRejection) {
+        // 1. Remove from active bets
+        self.state.close_active_bet(bet.bet_id).await;
+
+        // 2. Record returned stake in refund history
+        let refund = Payout {
+            market_id: bet.market_id,
+            bet_id: bet.bet_id,
+            amount: bet.amount,
+            timestamp: self.runtime.system_time(),
+        };
+        self.state
+            .refund_history
+            .insert(&bet.bet_id, refund)
+            .expect("Failed to insert refund history");
+
+        // 3. Emit event
+        self.runtime.emit(
+            StreamName::from(b"user_events".to_vec()),
+            &UserEvent::BetRejected {
+                market_id: bet.market_id,
+                bet_id: bet.bet_id,
+                reason,
+            },
+        );
+    }
+
+    /// Move a bet from the active bets to the settled bets with its final status
+    async fn settle_bet(&mut self, notice: SettlementNotice) {
+        // 1. Remove from active bets
+        self.state.close_active_bet(notice.bet_id).await;
+
+        // 2. Record the final status
+        self.state
+            .settled_bets
+            .insert(&notice.bet_id, notice.clone())
+            .expect("Failed to insert settled bet");
+
+        // 3. Emit event
+        self.runtime.emit(
+            StreamName::from(b"user_events".to_vec()),
+            &UserEvent::BetSettled {
+                market_id: notice.market_id,
+                bet_id: notice.bet_id,
+                status: notice.status,
+                amount: notice.amount,
+            },
+        );
     }
 }
 
@@ -286,17 +510,27 @@ mod tests {
     use super::*;
     use flashbet_user::InstantiationArgument;
     use futures::FutureExt;
-    use linera_sdk::{util::BlockingWait, views::View, ContractRuntime};
+    use linera_sdk::{
+        abis::fungible::FungibleResponse,
+        linera_base_types::{ApplicationId, ChainId, CryptoHash},
+        util::BlockingWait,
+        views::View,
+        ContractRuntime,
+    };
 
     #[test]
     fn test_instantiate() {
         let mut app = create_app();
 
-        app.instantiate(InstantiationArgument {})
+        app.instantiate(instantiation_argument())
             .now_or_never()
             .expect("Instantiation should not await");
 
         assert_eq!(*app.state.next_bet_id.get(), 0);
+        assert_eq!(
+            app.state.bet_token_id.get().map(|id| id.forget_abi()),
+            Some(bet_token_id())
+        );
     }
 
     #[test]
@@ -304,9 +538,12 @@ mod tests {
         use linera_sdk::linera_base_types::AccountOwner;
 
         let mut app = create_app();
-        app.instantiate(InstantiationArgument {})
+        app.instantiate(instantiation_argument())
             .now_or_never()
             .expect("Instantiation should not await");
+        app.runtime.set_call_application_handler(|_, _, _| {
+            linera_sdk::bcs::to_bytes(&FungibleResponse::Balance(Amount::from_tokens(5))).unwrap()
+        });
 
         // Test balance operation
         let response = app.execute_operation(Operation::Balance {
@@ -318,8 +555,7 @@ mod tests {
         // Verify response is Balance variant
         match response {
             OperationResponse::Balance(amount) => {
-                // Balance query succeeded, amount should be non-negative
-                assert!(amount >= Amount::ZERO);
+                assert_eq!(amount, Amount::from_tokens(5));
             }
             _ => panic!("Expected Balance response"),
         }
@@ -328,9 +564,12 @@ mod tests {
     #[test]
     fn test_ticker_symbol() {
         let mut app = create_app();
-        app.instantiate(InstantiationArgument {})
+        app.instantiate(instantiation_argument())
             .now_or_never()
             .expect("Instantiation should not await");
+        app.runtime.set_call_application_handler(|_, _, _| {
+            linera_sdk::bcs::to_bytes(&FungibleResponse::TickerSymbol("BET".to_string())).unwrap()
+        });
 
         // Test ticker symbol operation
         let response = app.execute_operation(Operation::TickerSymbol)
@@ -346,8 +585,600 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_refund_closes_active_bet() {
+        use flashbet_shared::{EventId, MarketId, Outcome, Payout};
+        use linera_sdk::linera_base_types::{AccountOwner, ChainId, Timestamp};
+
+        let mut app = create_app();
+        app.instantiate(instantiation_argument())
+            .now_or_never()
+            .expect("Instantiation should not await");
+
+        let bet = Bet {
+            bet_id: 0,
+            market_id: MarketId(0),
+            event_id: EventId::new("mlb_game_001"),
+            user: AccountOwner::from([1; 32]),
+            outcome: Outcome::Home,
+            amount: Amount::from_tokens(10),
+            timestamp: Timestamp::from(0),
+            user_chain: ChainId(CryptoHash::test_hash("user_chain")),
+        };
+        app.state.open_active_bet(&bet);
+
+        let refund = Payout {
+            market_id: bet.market_id,
+            bet_id: bet.bet_id,
+            amount: bet.amount,
+            timestamp: Timestamp::from(0),
+        };
+        deliver_notices(&mut app, vec![UserNotice::Refund(refund.clone())]);
+
+        assert_eq!(
+            app.state
+                .active_bets
+                .get(&bet.bet_id)
+                .blocking_wait()
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            app.state
+                .refund_history
+                .get(&bet.bet_id)
+                .blocking_wait()
+                .unwrap(),
+            Some(refund)
+        );
+    }
+
+    #[test]
+    fn test_place_bet_sends_authenticated_message() {
+        use flashbet_shared::{EventId, MarketId, Outcome};
+        use linera_sdk::linera_base_types::{AccountOwner, ChainId, Timestamp};
+
+        let user = AccountOwner::from([1; 32]);
+        let user_chain = ChainId(CryptoHash::test_hash("user_chain"));
+        let market_chain = ChainId(CryptoHash::test_hash("market_chain"));
+
+        let mut app = create_app();
+        app.runtime.set_chain_id(user_chain);
+        app.runtime.set_authenticated_signer(Some(user));
+        app.runtime.set_system_time(Timestamp::from(0));
+        app.instantiate(instantiation_argument())
+            .now_or_never()
+            .expect("Instantiation should not await");
+        app.runtime.set_call_application_handler(|_, _, _| {
+            linera_sdk::bcs::to_bytes(&FungibleResponse::Ok).unwrap()
+        });
+
+        app.execute_operation(Operation::PlaceBet {
+            market_chain,
+            market_id: MarketId(0),
+            event_id: EventId::new("mlb_game_001"),
+            outcome: Outcome::Home,
+            amount: Amount::from_tokens(10),
+            min_payout_bps: None,
+        })
+        .blocking_wait();
+
+        let requests = app.runtime.created_send_message_requests();
+        let [request] = requests.as_slice() else {
+            panic!("Expected exactly one message, got {}", requests.len());
+        };
+        assert_eq!(request.destination, market_chain);
+        assert!(request.authenticated);
+        assert!(request.is_tracked);
+        match &request.message {
+            Message::PlaceBet {
+                bet,
+                market_app_id: market,
+                ..
+            } => {
+                assert_eq!(bet.user, user);
+                assert_eq!(bet.user_chain, user_chain);
+                assert_eq!(bet.amount, Amount::from_tokens(10));
+                assert_eq!(*market, market_app_id());
+            }
+            _ => panic!("Expected PlaceBet message"),
+        }
+
+        // Payouts and settlements on that Market chain are reported on its notice stream
+        assert!(app
+            .state
+            .notice_subscriptions
+            .contains(&market_chain)
+            .blocking_wait()
+            .unwrap());
+    }
+
+    #[test]
+    fn test_received_bet_registered_with_market() {
+        use std::sync::{Arc, Mutex};
+
+        use flashbet_shared::{EventId, MarketId, Outcome};
+        use linera_sdk::linera_base_types::{AccountOwner, ChainId, Timestamp};
+
+        let user_chain = ChainId(CryptoHash::test_hash("user_chain"));
+        let bet = Bet {
+            bet_id: 0,
+            market_id: MarketId(0),
+            event_id: EventId::new("mlb_game_001"),
+            user: AccountOwner::from([1; 32]),
+            outcome: Outcome::Home,
+            amount: Amount::from_tokens(10),
+            timestamp: Timestamp::from(0),
+            user_chain,
+        };
+
+        // This application on the Market chain, which was never instantiated there
+        let mut app = create_app();
+        app.runtime
+            .set_chain_id(ChainId(CryptoHash::test_hash("market_chain")));
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let recorded = calls.clone();
+        app.runtime.set_call_application_handler(
+            move |authenticated, application_id, operation| {
+                let operation: flashbet_market::Operation =
+                    linera_sdk::bcs::from_bytes(&operation).unwrap();
+                recorded
+                    .lock()
+                    .unwrap()
+                    .push((authenticated, application_id, operation));
+                linera_sdk::bcs::to_bytes(&flashbet_market::OperationResponse::Ok).unwrap()
+            },
+        );
+
+        // A bet relayed by another chain is ignored
+        for origin in [ChainId(CryptoHash::test_hash("relayer_chain")), user_chain] {
+            app.runtime.set_message_origin_chain_id(origin);
+            app.runtime.set_message_is_bouncing(false);
+            app.execute_message(Message::PlaceBet {
+                bet: bet.clone(),
+                min_payout_bps: Some(15_000),
+                market_app_id: market_app_id(),
+            })
+            .blocking_wait();
+        }
+
+        // The bettor's bet is registered with the Market application, signature forwarded
+        assert!(matches!(
+            calls.lock().unwrap().as_slice(),
+            [(
+                true,
+                market,
+                flashbet_market::Operation::RegisterBet {
+                    bet: registered,
+                    min_payout_bps: Some(15_000),
+                },
+            )] if *market == market_app_id() && *registered == bet
+        ));
+    }
+
+    #[test]
+    fn test_rejected_bet_reported_to_user_chain() {
+        use flashbet_shared::{EventId, MarketId, MarketStatus, Outcome};
+        use linera_sdk::linera_base_types::{AccountOwner, Timestamp};
+
+        let user_chain = ChainId(CryptoHash::test_hash("user_chain"));
+        let bet = Bet {
+            bet_id: 0,
+            market_id: MarketId(0),
+            event_id: EventId::new("mlb_game_001"),
+            user: AccountOwner::from([1; 32]),
+            outcome: Outcome::Home,
+            amount: Amount::from_tokens(10),
+            timestamp: Timestamp::from(0),
+            user_chain,
+        };
+        let reason = BetRejection::MarketNotOpen(MarketStatus::Locked);
+
+        // This application on the Market chain, where the market turns the bet down
+        let mut app = create_app();
+        app.runtime
+            .set_chain_id(ChainId(CryptoHash::test_hash("market_chain")));
+        let response = flashbet_market::OperationResponse::BetRejected(reason.clone());
+        let response = linera_sdk::bcs::to_bytes(&response).unwrap();
+        app.runtime
+            .set_call_application_handler(move |_, _, _| response.clone());
+        app.runtime.set_message_origin_chain_id(user_chain);
+        app.runtime.set_message_is_bouncing(false);
+        app.execute_message(Message::PlaceBet {
+            bet: bet.clone(),
+            min_payout_bps: None,
+            market_app_id: market_app_id(),
+        })
+        .blocking_wait();
+
+        let requests = app.runtime.created_send_message_requests();
+        let [request] = requests.as_slice() else {
+            panic!("Expected exactly one message, got {}", requests.len());
+        };
+        assert_eq!(request.destination, user_chain);
+        assert!(
+            matches!(&request.message, Message::BetRejected { bet: rejected, reason: sent }
+            if *rejected == bet && *sent == reason)
+        );
+    }
+
+    #[test]
+    fn test_overlapping_notice_update_reads_new_notices() {
+        use std::sync::{Arc, Mutex};
+
+        use flashbet_shared::{MarketId, Payout};
+        use linera_sdk::linera_base_types::Timestamp;
+
+        let mut app = create_app();
+        app.instantiate(instantiation_argument())
+            .now_or_never()
+            .expect("Instantiation should not await");
+        let reads = Arc::new(Mutex::new(Vec::new()));
+        let recorded = reads.clone();
+        app.runtime
+            .set_call_application_handler(move |_, _, operation| {
+                let operation: flashbet_market::Operation =
+                    linera_sdk::bcs::from_bytes(&operation).unwrap();
+                let flashbet_market::Operation::ReadNotice { index, .. } = operation else {
+                    panic!("Unexpected Market operation");
+                };
+                recorded.lock().unwrap().push(index);
+                let payout = Payout {
+                    market_id: MarketId(0),
+                    bet_id: u64::from(index),
+                    amount: Amount::from_tokens(20),
+                    timestamp: Timestamp::from(0),
+                };
+                linera_sdk::bcs::to_bytes(&flashbet_market::OperationResponse::Notice(
+                    UserNotice::Payout(payout),
+                ))
+                .unwrap()
+            });
+
+        let update = |next_index, stream_name| StreamUpdate {
+            chain_id: ChainId(CryptoHash::test_hash("market_chain")),
+            stream_id: StreamId {
+                application_id: GenericApplicationId::User(market_app_id()),
+                stream_name,
+            },
+            previous_index: 0,
+            next_index,
+        };
+        let notices = flashbet_shared::user_notices_stream(app.runtime.chain_id());
+        let other_chain_notices =
+            flashbet_shared::user_notices_stream(ChainId(CryptoHash::test_hash("other_chain")));
+
+        // Another chain's notices are not ours to read
+        app.process_streams(vec![update(1, other_chain_notices)])
+            .blocking_wait();
+        app.process_streams(vec![update(1, notices.clone())])
+            .blocking_wait();
+        app.process_streams(vec![update(2, notices)])
+            .blocking_wait();
+
+        assert_eq!(*reads.lock().unwrap(), vec![0, 1]);
+        assert_eq!(app.state.payout_history.count().blocking_wait().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_place_bet_escrows_stake_with_market() {
+        use std::sync::{Arc, Mutex};
+
+        use flashbet_shared::{EventId, MarketId, Outcome};
+        use linera_sdk::linera_base_types::{AccountOwner, ChainId, Timestamp};
+
+        let market_chain = ChainId(CryptoHash::test_hash("market_chain"));
+
+        let mut app = create_app();
+        app.runtime
+            .set_chain_id(ChainId(CryptoHash::test_hash("user_chain")));
+        app.runtime
+            .set_authenticated_signer(Some(AccountOwner::from([1; 32])));
+        app.runtime.set_system_time(Timestamp::from(0));
+        app.instantiate(instantiation_argument())
+            .now_or_never()
+            .expect("Instantiation should not await");
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let recorded = calls.clone();
+        app.runtime.set_call_application_handler(
+            move |authenticated, application_id, operation| {
+                let operation: flashbet_token::Operation =
+                    linera_sdk::bcs::from_bytes(&operation).unwrap();
+                recorded
+                    .lock()
+                    .unwrap()
+                    .push((authenticated, application_id, operation));
+                linera_sdk::bcs::to_bytes(&FungibleResponse::Ok).unwrap()
+            },
+        );
+
+        app.execute_operation(Operation::PlaceBet {
+            market_chain,
+            market_id: MarketId(0),
+            event_id: EventId::new("mlb_game_001"),
+            outcome: Outcome::Home,
+            amount: Amount::from_tokens(10),
+            min_payout_bps: None,
+        })
+        .blocking_wait();
+
+        // The signer's stake goes into the Market application's escrow on the Market chain
+        assert!(matches!(
+            calls.lock().unwrap().as_slice(),
+            [(
+                true,
+                token,
+                flashbet_token::Operation::TransferToEscrowCrossChain {
+                    destination,
+                    application,
+                    amount,
+                },
+            )] if *token == bet_token_id()
+                && *destination == market_chain
+                && *application == market_app_id()
+                && *amount == Amount::from_tokens(10)
+        ));
+    }
+
+    #[test]
+    fn test_bet_rejected_closes_active_bet() {
+        use flashbet_shared::{EventId, MarketId, MarketStatus, Outcome};
+        use linera_sdk::linera_base_types::{AccountOwner, ChainId, Timestamp};
+
+        let mut app = create_app();
+        app.runtime.set_system_time(Timestamp::from(0));
+        app.instantiate(instantiation_argument())
+            .now_or_never()
+            .expect("Instantiation should not await");
+
+        let bet = Bet {
+            bet_id: 0,
+            market_id: MarketId(0),
+            event_id: EventId::new("mlb_game_001"),
+            user: AccountOwner::from([1; 32]),
+            outcome: Outcome::Home,
+            amount: Amount::from_tokens(10),
+            timestamp: Timestamp::from(0),
+            user_chain: ChainId(CryptoHash::test_hash("user_chain")),
+        };
+        app.state.open_active_bet(&bet);
+
+        app.execute_message(Message::BetRejected {
+            bet: bet.clone(),
+            reason: BetRejection::MarketNotOpen(MarketStatus::Locked),
+        })
+        .blocking_wait();
+
+        assert_eq!(
+            app.state
+                .active_bets
+                .get(&bet.bet_id)
+                .blocking_wait()
+                .unwrap(),
+            None
+        );
+        let refund = app
+            .state
+            .refund_history
+            .get(&bet.bet_id)
+            .blocking_wait()
+            .unwrap()
+            .expect("Rejected stake should be recorded as a refund");
+        assert_eq!(refund.amount, bet.amount);
+    }
+
+    #[test]
+    fn test_bounced_bet_recorded_as_refund() {
+        use flashbet_shared::{EventId, MarketId, Outcome};
+        use linera_sdk::linera_base_types::{AccountOwner, ChainId, Timestamp};
+
+        let mut app = create_app();
+        app.runtime.set_system_time(Timestamp::from(0));
+        app.instantiate(instantiation_argument())
+            .now_or_never()
+            .expect("Instantiation should not await");
+
+        let bet = Bet {
+            bet_id: 0,
+            market_id: MarketId(0),
+            event_id: EventId::new("mlb_game_001"),
+            user: AccountOwner::from([1; 32]),
+            outcome: Outcome::Home,
+            amount: Amount::from_tokens(10),
+            timestamp: Timestamp::from(0),
+            user_chain: ChainId(CryptoHash::test_hash("user_chain")),
+        };
+        app.state.open_active_bet(&bet);
+
+        // The stake's transfer bounces back alongside the message
+        app.runtime.set_message_is_bouncing(true);
+        app.execute_message(Message::PlaceBet {
+            bet: bet.clone(),
+            min_payout_bps: None,
+            market_app_id: market_app_id(),
+        })
+        .blocking_wait();
+
+        assert_eq!(
+            app.state
+                .active_bets
+                .get(&bet.bet_id)
+                .blocking_wait()
+                .unwrap(),
+            None
+        );
+        let refund = app
+            .state
+            .refund_history
+            .get(&bet.bet_id)
+            .blocking_wait()
+            .unwrap()
+            .expect("Bounced stake should be recorded as a refund");
+        assert_eq!(refund.amount, bet.amount);
+    }
+
+    #[test]
+    fn test_settlement_notice_closes_losing_bet() {
+        use flashbet_shared::{EventId, MarketId, Outcome, SettlementStatus};
+        use linera_sdk::linera_base_types::{AccountOwner, ChainId, Timestamp};
+
+        let mut app = create_app();
+        app.instantiate(instantiation_argument())
+            .now_or_never()
+            .expect("Instantiation should not await");
+
+        let bet = Bet {
+            bet_id: 0,
+            market_id: MarketId(0),
+            event_id: EventId::new("mlb_game_001"),
+            user: AccountOwner::from([1; 32]),
+            outcome: Outcome::Home,
+            amount: Amount::from_tokens(10),
+            timestamp: Timestamp::from(0),
+            user_chain: ChainId(CryptoHash::test_hash("user_chain")),
+        };
+        app.state.open_active_bet(&bet);
+        app.state
+            .bet_history
+            .insert(&bet.bet_id, bet.clone())
+            .expect("Failed to insert bet history");
+
+        let notice = SettlementNotice {
+            market_id: bet.market_id,
+            event_id: bet.event_id.clone(),
+            bet_id: bet.bet_id,
+            status: SettlementStatus::Lost,
+            amount: Amount::ZERO,
+            timestamp: Timestamp::from(0),
+        };
+        deliver_notices(
+            &mut app,
+            vec![UserNotice::Settled {
+                notices: vec![notice.clone()],
+            }],
+        );
+
+        assert_eq!(
+            app.state
+                .active_bets
+                .get(&bet.bet_id)
+                .blocking_wait()
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            app.state
+                .settled_bets
+                .get(&bet.bet_id)
+                .blocking_wait()
+                .unwrap(),
+            Some(notice)
+        );
+    }
+
+    #[test]
+    fn test_payout_closes_only_its_bet() {
+        use flashbet_shared::{EventId, MarketId, Outcome, Payout};
+        use linera_sdk::linera_base_types::{AccountOwner, ChainId, Timestamp};
+
+        let market_chain = ChainId(CryptoHash::test_hash("market_chain"));
+        let mut app = create_app();
+        app.runtime
+            .set_chain_id(ChainId(CryptoHash::test_hash("user_chain")));
+        app.runtime
+            .set_authenticated_signer(Some(AccountOwner::from([1; 32])));
+        app.runtime.set_system_time(Timestamp::from(0));
+        app.instantiate(instantiation_argument())
+            .now_or_never()
+            .expect("Instantiation should not await");
+        app.runtime.set_call_application_handler(|_, _, _| {
+            linera_sdk::bcs::to_bytes(&FungibleResponse::Ok).unwrap()
+        });
+
+        // Two bets on the same market, both open at once
+        for outcome in [Outcome::Home, Outcome::Away] {
+            app.execute_operation(Operation::PlaceBet {
+                market_chain,
+                market_id: MarketId(0),
+                event_id: EventId::new("mlb_game_001"),
+                outcome,
+                amount: Amount::from_tokens(10),
+                min_payout_bps: None,
+            })
+            .blocking_wait();
+        }
+        let event_bets = app
+            .state
+            .get_active_bets_for_event(&EventId::new("mlb_game_001"))
+            .blocking_wait();
+        assert_eq!(event_bets.len(), 2);
+
+        deliver_notices(
+            &mut app,
+            vec![UserNotice::Payout(Payout {
+                market_id: MarketId(0),
+                bet_id: 0,
+                amount: Amount::from_tokens(20),
+                timestamp: Timestamp::from(0),
+            })],
+        );
+
+        let remaining = app
+            .state
+            .get_active_bets_for_event(&EventId::new("mlb_game_001"))
+            .blocking_wait();
+        assert_eq!(remaining, vec![event_bets[1].clone()]);
+        assert_eq!(app.state.get_active_bets().blocking_wait(), remaining);
+    }
+
+    fn bet_token_id() -> ApplicationId {
+        ApplicationId::new(CryptoHash::test_hash("bet_token"))
+    }
+
+    fn market_app_id() -> ApplicationId {
+        ApplicationId::new(CryptoHash::test_hash("market_app"))
+    }
+
+    fn instantiation_argument() -> InstantiationArgument {
+        InstantiationArgument {
+            bet_token_id: bet_token_id(),
+            market_app_id: market_app_id(),
+        }
+    }
+
+    /// Publishes notices on the Market chain's notice stream for this chain and processes
+    /// the stream update, reading each notice back through the Market application
+    fn deliver_notices(app: &mut FlashbetUserContract, notices: Vec<UserNotice>) {
+        let market_chain = ChainId(CryptoHash::test_hash("market_chain"));
+        let update = StreamUpdate {
+            chain_id: market_chain,
+            stream_id: StreamId {
+                application_id: GenericApplicationId::User(market_app_id()),
+                stream_name: flashbet_shared::user_notices_stream(app.runtime.chain_id()),
+            },
+            previous_index: 0,
+            next_index: notices.len() as u32,
+        };
+        app.runtime
+            .set_call_application_handler(move |_, application_id, operation| {
+                assert_eq!(application_id, market_app_id());
+                let operation: flashbet_market::Operation =
+                    linera_sdk::bcs::from_bytes(&operation).unwrap();
+                let flashbet_market::Operation::ReadNotice { chain_id, index } = operation else {
+                    panic!("Unexpected Market operation");
+                };
+                assert_eq!(chain_id, market_chain);
+                let notice = notices[index as usize].clone();
+                linera_sdk::bcs::to_bytes(&flashbet_market::OperationResponse::Notice(notice))
+                    .unwrap()
+            });
+        app.process_streams(vec![update]).blocking_wait();
+    }
+
     fn create_app() -> FlashbetUserContract {
-        let runtime = ContractRuntime::new().with_application_parameters(());
+        let runtime = ContractRuntime::new()
+            .with_application_parameters(())
+            .with_chain_id(ChainId(CryptoHash::test_hash("user_chain")));
         FlashbetUserContract {
             state: FlashbetUserState::load(runtime.root_view_storage_context())
                 .blocking_wait()
@@ -4,7 +4,7 @@
 //! Uses BET token application for all token operations.
 
 use async_graphql::{Request, Response, SimpleObject};
-use flashbet_shared::{EventId, MarketId, Outcome, Payout, UserEvent};
+use flashbet_shared::{EventId, MarketId, Outcome};
 use linera_sdk::{
     graphql::GraphQLMutationRoot,
     linera_base_types::{AccountOwner, Amount, ApplicationId, ChainId, ContractAbi, ServiceAbi},
@@ -71,18 +71,30 @@ pub enum Operation {
         outcome: Outcome,
         /// Bet amount (in BET tokens)
         amount: Amount,
+        /// Smallest payout multiplier accepted once the bet reaches the pool, in basis points
+        /// (25_000 = 2.5x); the bet is refunded if the odds have moved below it
+        min_payout_bps: Option<u32>,
     },
 }
 
 /// Messages sent/received by the User Chain
 #[derive(Debug, Deserialize, Serialize)]
 pub enum Message {
-    /// Payout from a winning bet (received from Market Chain)
-    Payout(Payout),
+    /// Cross-chain bet message, received by this application on the Market Chain,
+    /// which registers the bet with the Market application there
+    PlaceBet {
+        bet: flashbet_shared::Bet,
+        min_payout_bps: Option<u32>,
+        /// Market application holding the stake in escrow
+        market_app_id: ApplicationId,
+    },
 
-    /// Cross-chain bet message (sent to Market Chain)
-    /// Must match Market Chain's Message::PlaceBet format
-    PlaceBet { bet: flashbet_shared::Bet },
+    /// Bet turned down by the Market Chain, with its stake returned
+    /// Sent by this application on the Market Chain once the Market application rejects it
+    BetRejected {
+        bet: flashbet_shared::Bet,
+        reason: flashbet_shared::BetRejection,
+    },
 }
 
 /// Instantiation argument for User Chain
@@ -92,4 +104,6 @@ pub struct InstantiationArgument {
     /// BET token application ID for calling token operations
     /// Stored without type parameter for GraphQL compatibility
     pub bet_token_id: ApplicationId,
+    /// Market application ID, whose escrow on each Market chain receives the stakes
+    pub market_app_id: ApplicationId,
 }
@@ -5,7 +5,7 @@ mod state;
 use std::sync::Arc;
 
 use async_graphql::{EmptySubscription, Object, Schema};
-use flashbet_shared::{Bet, Payout};
+use flashbet_shared::{Bet, EventId, Payout, SettlementNotice};
 use flashbet_user::Operation;
 use linera_sdk::{
     graphql::GraphQLMutationRoot,
@@ -48,15 +48,16 @@ impl Service for FlashbetUserService {
         // This follows the native-fungible example pattern
         let balance = self.runtime.owner_balance(linera_sdk::linera_base_types::AccountOwner::CHAIN);
 
-        let mut active_bets = Vec::new();
-        self.state
-            .active_bets
-            .for_each_index_value(|_key, bet| {
-                active_bets.push(bet.into_owned());
-                Ok(())
-            })
-            .await
-            .expect("Failed to iterate active bets");
+        let active_bets = self.state.get_active_bets().await;
+
+        // Group active bets by event
+        let mut active_bets_by_event = std::collections::HashMap::<EventId, Vec<Bet>>::new();
+        for bet in &active_bets {
+            active_bets_by_event
+                .entry(bet.event_id.clone())
+                .or_default()
+                .push(bet.clone());
+        }
 
         let mut bet_history = Vec::new();
         self.state
@@ -78,13 +79,36 @@ impl Service for FlashbetUserService {
             .await
             .expect("Failed to iterate payout history");
 
+        let mut refund_history = Vec::new();
+        self.state
+            .refund_history
+            .for_each_index_value(|_key, refund| {
+                refund_history.push(refund.into_owned());
+                Ok(())
+            })
+            .await
+            .expect("Failed to iterate refund history");
+
+        let mut settled_bets = Vec::new();
+        self.state
+            .settled_bets
+            .for_each_index_value(|_key, notice| {
+                settled_bets.push(notice.into_owned());
+                Ok(())
+            })
+            .await
+            .expect("Failed to iterate settled bets");
+
         Schema::build(
             QueryRoot {
                 chain_id,
                 balance,
                 active_bets,
+                active_bets_by_event,
                 bet_history,
                 payout_history,
+                refund_history,
+                settled_bets,
             },
             Operation::mutation_root(self.runtime.clone()),
             EmptySubscription,
@@ -99,8 +123,11 @@ struct QueryRoot {
     chain_id: linera_sdk::linera_base_types::ChainId,
     balance: Amount,
     active_bets: Vec<Bet>,
+    active_bets_by_event: std::collections::HashMap<EventId, Vec<Bet>>,
     bet_history: Vec<Bet>,
     payout_history: Vec<Payout>,
+    refund_history: Vec<Payout>,
+    settled_bets: Vec<SettlementNotice>,
 }
 
 #[Object]
@@ -125,6 +152,14 @@ impl QueryRoot {
         &self.active_bets
     }
 
+    /// Get active bets on one event
+    async fn active_bets_for_event(&self, event_id: EventId) -> Vec<Bet> {
+        self.active_bets_by_event
+            .get(&event_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
     /// Get complete betting history
     async fn bet_history(&self) -> &Vec<Bet> {
         &self.bet_history
@@ -135,6 +170,16 @@ impl QueryRoot {
         &self.payout_history
     }
 
+    /// Get stakes refunded from cancelled markets
+    async fn refund_history(&self) -> &Vec<Payout> {
+        &self.refund_history
+    }
+
+    /// Get settled bets with their final status (won, lost or refunded)
+    async fn settled_bets(&self) -> &Vec<SettlementNotice> {
+        &self.settled_bets
+    }
+
     /// Get total number of bets placed
     async fn total_bets(&self) -> u64 {
         self.bet_history.len() as u64
@@ -3,12 +3,16 @@
 //! Manages active bets and betting history.
 //! Balances are now tracked via BET token application.
 
-use flashbet_shared::{Bet, MarketId, Payout};
+use flashbet_shared::{Bet, EventId, Payout, SettlementNotice};
 use linera_sdk::{
-    linera_base_types::ApplicationId,
-    views::{linera_views, MapView, RegisterView, RootView, ViewStorageContext},
+    linera_base_types::{ApplicationId, ChainId},
+    views::{linera_views, MapView, RegisterView, RootView, SetView, ViewStorageContext},
 };
 
+/// User chain state
+///
+/// The layout is not compatible with versions that kept one active bet per market, and there
+/// is no migration: upgrading means deploying a new application.
 #[derive(RootView)]
 #[view(context = ViewStorageContext)]
 pub struct FlashbetUserState {
@@ -17,8 +21,8 @@ pub struct FlashbetUserState {
     pub bet_token_id: RegisterView<Option<ApplicationId<flashbet_token::FlashbetTokenAbi>>>,
 
     /// Active bets (not yet resolved)
-    /// Maps MarketId -> Bet
-    pub active_bets: MapView<MarketId, Bet>,
+    /// Maps bet_id -> Bet, so several bets on the same market can be open at once
+    pub active_bets: MapView<u64, Bet>,
 
     /// Betting history (all bets ever placed)
     /// Maps bet_id -> Bet
@@ -28,10 +32,33 @@ pub struct FlashbetUserState {
     /// Maps bet_id -> Payout
     pub payout_history: MapView<u64, Payout>,
 
+    /// Refund history (stakes returned from cancelled markets)
+    /// Maps bet_id -> Payout
+    pub refund_history: MapView<u64, Payout>,
+
     /// Counter for generating unique bet IDs
     pub next_bet_id: RegisterView<u64>,
+
+    /// Settled bets with their final status (won, lost or refunded)
+    /// Maps bet_id -> SettlementNotice
+    pub settled_bets: MapView<u64, SettlementNotice>,
+
+    /// Index of active bets by event
+    /// Contains (EventId, bet_id) for every entry of `active_bets`
+    pub active_bets_by_event: SetView<(EventId, u64)>,
+
+    /// Market application ID, whose escrow on each Market chain receives the stakes
+    pub market_app_id: RegisterView<Option<ApplicationId>>,
+
+    /// Market chains whose notice stream for this chain is subscribed to
+    pub notice_subscriptions: SetView<ChainId>,
+
+    /// Next notice to read from each Market chain's notice stream
+    pub next_notice_index: MapView<ChainId, u32>,
 }
 
+// Compiled into both the contract and service binaries, each of which uses a subset
+#[allow(dead_code)]
 impl FlashbetUserState {
     /// Get next bet ID and increment counter
     pub fn get_next_bet_id(&mut self) -> u64 {
@@ -40,4 +67,74 @@ impl FlashbetUserState {
         *next_id += 1;
         id
     }
+
+    /// Record a newly placed bet as active
+    pub fn open_active_bet(&mut self, bet: &Bet) {
+        self.active_bets
+            .insert(&bet.bet_id, bet.clone())
+            .expect("Failed to insert active bet");
+        self.active_bets_by_event
+            .insert(&(bet.event_id.clone(), bet.bet_id))
+            .expect("Failed to index active bet");
+    }
+
+    /// Remove exactly this bet from the active bets, returning it if it was still active
+    pub async fn close_active_bet(&mut self, bet_id: u64) -> Option<Bet> {
+        if let Some(bet) = self
+            .active_bets
+            .get(&bet_id)
+            .await
+            .expect("Failed to read active bet")
+        {
+            self.active_bets
+                .remove(&bet_id)
+                .expect("Failed to remove active bet");
+            self.active_bets_by_event
+                .remove(&(bet.event_id.clone(), bet_id))
+                .expect("Failed to unindex active bet");
+            return Some(bet);
+        }
+
+        None
+    }
+
+    /// Get all active bets
+    pub async fn get_active_bets(&self) -> Vec<Bet> {
+        let mut bets = Vec::new();
+        self.active_bets
+            .for_each_index_value(|_bet_id, bet| {
+                bets.push(bet.into_owned());
+                Ok(())
+            })
+            .await
+            .expect("Failed to iterate active bets");
+        bets
+    }
+
+    /// Get the active bets on an event
+    pub async fn get_active_bets_for_event(&self, event_id: &EventId) -> Vec<Bet> {
+        let mut bet_ids = Vec::new();
+        self.active_bets_by_event
+            .for_each_index(|(bet_event_id, bet_id)| {
+                if bet_event_id == *event_id {
+                    bet_ids.push(bet_id);
+                }
+                Ok(())
+            })
+            .await
+            .expect("Failed to iterate active bets by event");
+
+        let mut bets = Vec::new();
+        for bet_id in bet_ids {
+            if let Some(bet) = self
+                .active_bets
+                .get(&bet_id)
+                .await
+                .expect("Failed to read active bet")
+            {
+                bets.push(bet);
+            }
+        }
+        bets
+    }
 }
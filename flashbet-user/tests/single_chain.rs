@@ -5,35 +5,59 @@
 
 #![cfg(not(target_arch = "wasm32"))]
 
-use flashbet_user::Operation;
-use linera_sdk::test::{QueryOutcome, TestValidator};
-
-/// Tests setting and incrementing a counter
+use flashbet_user::InstantiationArgument;
+use linera_sdk::{
+    abis::fungible::InitialState,
+    linera_base_types::{ApplicationId, CryptoHash},
+    test::{QueryOutcome, TestValidator},
+};
+
+/// Tests instantiating a user application
 ///
-/// Creates the application on a `chain`, initializing it with a 10 then add 10 and obtain 20.
-/// which is then checked.
+/// Creates the BET token and the user application on a `chain` and checks that the user
+/// starts without any bets.
 #[tokio::test(flavor = "multi_thread")]
 async fn single_chain_test() {
-    let (validator, module_id) =
-        TestValidator::with_current_module::<flashbet_user::FlashbetUserAbi, (), u64>().await;
+    let (validator, module_id) = TestValidator::with_current_module::<
+        flashbet_user::FlashbetUserAbi,
+        (),
+        InstantiationArgument,
+    >()
+    .await;
     let mut chain = validator.new_chain().await;
 
-    let initial_state = 10u64;
-    let application_id = chain
-        .create_application(module_id, (), initial_state, vec![])
+    let token_module_id = chain
+        .publish_bytecode_files_in::<flashbet_token::FlashbetTokenAbi, (), InitialState>(
+            "../flashbet-token",
+        )
+        .await;
+    let bet_token_id = chain
+        .create_application(
+            token_module_id,
+            (),
+            InitialState {
+                accounts: Default::default(),
+            },
+            vec![],
+        )
         .await;
 
-    let increment = 10u64;
-    chain
-        .add_block(|block| {
-            block.with_operation(application_id, Operation::Increment { value: increment });
-        })
+    let application_id = chain
+        .create_application(
+            module_id,
+            (),
+            InstantiationArgument {
+                bet_token_id: bet_token_id.forget_abi(),
+                market_app_id: ApplicationId::new(CryptoHash::test_hash("market_app")),
+            },
+            vec![],
+        )
         .await;
 
-    let final_value = initial_state + increment;
-    let QueryOutcome { response, .. } =
-        chain.graphql_query(application_id, "query { value }").await;
-    let state_value = response["value"].as_u64().expect("Failed to get the u64");
+    let QueryOutcome { response, .. } = chain
+        .graphql_query(application_id, "query { tickerSymbol totalBets }")
+        .await;
 
-    assert_eq!(state_value, final_value);
+    assert_eq!(response["tickerSymbol"].as_str(), Some("BET"));
+    assert_eq!(response["totalBets"].as_u64(), Some(0));
 }
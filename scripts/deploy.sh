@@ -68,16 +68,16 @@ echo ""
 echo -e "${YELLOW}[3/4]${NC} Deploying Market Chain..."
 MARKET_APP=$(linera publish-and-create \
   target/wasm32-unknown-unknown/release/flashbet_market_{contract,service}.wasm \
-  --json-argument "{\"oracle_chain\":\"$CHAIN\",\"oracle_app_id\":\"$ORACLE_APP\",\"bet_token_id\":\"$TOKEN_APP\"}")
+  --json-argument "{\"oracle_chain\":\"$CHAIN\",\"oracle_app_id\":\"$ORACLE_APP\",\"bet_token_id\":\"$TOKEN_APP\",\"oracle_keys\":[\"$OWNER\"]}")
 echo -e "${GREEN}✓ Market Chain deployed${NC}"
 echo "  Application ID: $MARKET_APP"
 echo ""
 
-# Deploy User Chain (step 4 - with BET token reference)
+# Deploy User Chain (step 4 - with BET token and Market references)
 echo -e "${YELLOW}[4/4]${NC} Deploying User Chain..."
 USER_APP=$(linera publish-and-create \
   target/wasm32-unknown-unknown/release/flashbet_user_{contract,service}.wasm \
-  --json-argument "{\"bet_token_id\":\"$TOKEN_APP\"}")
+  --json-argument "{\"bet_token_id\":\"$TOKEN_APP\",\"market_app_id\":\"$MARKET_APP\"}")
 echo -e "${GREEN}✓ User Chain deployed${NC}"
 echo "  Application ID: $USER_APP"
 echo ""
@@ -100,6 +100,8 @@ echo "  User:      $USER_APP"
 echo ""
 echo -e "${YELLOW}Next steps:${NC}"
 echo "  1. Start GraphQL service: ./scripts/start_service.sh"
-echo "  2. Run integration test:  ./scripts/test.sh"
+echo "  2. Allow the User app to register bets on the Market:"
+echo "     mutation { setUserApp(userAppId: \"$USER_APP\") }"
+echo "  3. Run integration test:  ./scripts/test.sh"
 echo ""
 echo -e "${BLUE}Save these IDs for testing!${NC}"
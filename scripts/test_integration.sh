@@ -83,7 +83,7 @@ sleep 1
 echo -e "${YELLOW}[6/10]${NC} Deploying Market..."
 MARKET_APP=$(linera publish-and-create \
   target/wasm32-unknown-unknown/release/flashbet_market_{contract,service}.wasm \
-  --json-argument "{\"oracle_chain\":\"$CHAIN\",\"oracle_app_id\":\"$ORACLE_APP\",\"bet_token_id\":\"$TOKEN_APP\"}")
+  --json-argument "{\"oracle_chain\":\"$CHAIN\",\"oracle_app_id\":\"$ORACLE_APP\",\"bet_token_id\":\"$TOKEN_APP\",\"oracle_keys\":[\"$OWNER\"]}")
 echo -e "${GREEN}✓ Market deployed${NC}"
 echo "  Market App: $MARKET_APP"
 echo ""
@@ -93,7 +93,7 @@ sleep 1
 echo -e "${YELLOW}[7/10]${NC} Deploying User..."
 USER_APP=$(linera publish-and-create \
   target/wasm32-unknown-unknown/release/flashbet_user_{contract,service}.wasm \
-  --json-argument "{\"bet_token_id\":\"$TOKEN_APP\"}")
+  --json-argument "{\"bet_token_id\":\"$TOKEN_APP\",\"market_app_id\":\"$MARKET_APP\"}")
 echo -e "${GREEN}✓ User deployed${NC}"
 echo "  User App: $USER_APP"
 echo ""